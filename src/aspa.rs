@@ -0,0 +1,555 @@
+//! Autonomous System Provider Authorizations.
+//!
+//! For details, see draft-ietf-sidrops-aspa-profile.
+
+use std::fmt;
+use std::iter::FromIterator;
+use bcder::{decode, encode};
+use bcder::{Captured, Mode, Oid, Tag, xerr};
+use bcder::encode::{PrimitiveContent, Values};
+use bytes::Bytes;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use crate::oid;
+use crate::cert::{Cert, ResourceCert};
+use crate::crypto::{Signer, SigningError};
+use crate::resources::{AsBlock, AsBlocks, AsId, AsResources};
+use crate::sigobj::{SignedObject, SignedObjectBuilder};
+use crate::x509::{Time, ValidationError, ValidationErrorKind};
+
+
+//------------ Aspa ----------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct Aspa {
+    signed: SignedObject,
+    content: AsProviderAttestation,
+}
+
+impl Aspa {
+    pub fn decode<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        let signed = SignedObject::decode(source, strict)?;
+        if signed.content_type().ne(&oid::CT_ASPA) {
+            return Err(decode::Malformed.into())
+        }
+        let content = signed.decode_content(|cons| {
+            AsProviderAttestation::take_from(cons)
+        })?;
+        Ok(Aspa { signed, content })
+    }
+
+    pub fn process<F>(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        check_crl: F
+    ) -> Result<(ResourceCert, AsProviderAttestation), ValidationError>
+    where F: FnOnce(&Cert) -> Result<(), ValidationError> {
+        self.process_at(issuer, strict, Time::now(), check_crl)
+    }
+
+    pub fn process_at<F>(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        check_crl: F
+    ) -> Result<(ResourceCert, AsProviderAttestation), ValidationError>
+    where F: FnOnce(&Cert) -> Result<(), ValidationError> {
+        let cert = self.signed.validate_at(issuer, strict, now)?;
+        check_crl(cert.as_ref())?;
+        self.content.validate(&cert)?;
+        Ok((cert, self.content))
+    }
+
+    /// Returns a value encoder for a reference to an ASPA.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        self.signed.encode_ref()
+    }
+
+    /// Returns a DER encoded Captured for this.
+    pub fn to_captured(&self) -> Captured {
+        self.encode_ref().to_captured(Mode::Der)
+    }
+
+    /// Returns a reference to the EE certificate of this ASPA.
+    pub fn cert(&self) -> &Cert {
+        self.signed.cert()
+    }
+
+    /// Returns a reference to the AsProviderAttestation content.
+    pub fn content(&self) -> &AsProviderAttestation {
+        &self.content
+    }
+}
+
+
+//--- Deserialize and Serialize
+
+impl Serialize for Aspa {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = self.to_captured().into_bytes();
+        let b64 = base64::encode(&bytes);
+        b64.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Aspa {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Self, D::Error> {
+        use serde::de;
+
+        let string = String::deserialize(deserializer)?;
+        let decoded = base64::decode(&string).map_err(de::Error::custom)?;
+        let bytes = Bytes::from(decoded);
+        Aspa::decode(bytes, true).map_err(de::Error::custom)
+    }
+}
+
+
+//------------ AsProviderAttestation -----------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct AsProviderAttestation {
+    customer_as: AsId,
+    providers: ProviderAsSet,
+}
+
+impl AsProviderAttestation {
+    pub fn customer_as(&self) -> AsId {
+        self.customer_as
+    }
+
+    pub fn providers(&self) -> &ProviderAsSet {
+        &self.providers
+    }
+}
+
+impl AsProviderAttestation {
+    fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            // version [0] EXPLICIT INTEGER, must be 1
+            cons.take_constructed_if(Tag::CTX_0, |c| c.skip_u8_if(1))?;
+            let customer_as = AsId::take_from(cons)?;
+            let providers = ProviderAsSet::take_from(cons, customer_as)?;
+            Ok(AsProviderAttestation { customer_as, providers })
+        })
+    }
+
+    /// Checks that the EE certificate holds exactly the customer AS.
+    ///
+    /// The certificate must not contain any IP resources and its AS
+    /// resources must consist of the customer AS only.
+    fn validate(
+        &self,
+        cert: &ResourceCert
+    ) -> Result<(), ValidationError> {
+        if !cert.v4_resources().is_empty()
+            || !cert.v6_resources().is_empty()
+        {
+            return Err(ValidationError::new(
+                ValidationErrorKind::UnexpectedResources
+            ))
+        }
+        let customer = AsBlocks::from_iter(
+            Some(AsBlock::from(self.customer_as))
+        );
+        if *cert.as_resources() != customer {
+            return Err(ValidationError::new(
+                ValidationErrorKind::ResourcesNotCovered
+            ))
+        }
+        Ok(())
+    }
+
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        encode::sequence((
+            encode::sequence_as(Tag::CTX_0, 1u8.encode()),
+            self.customer_as.encode(),
+            encode::sequence(&self.providers.0),
+        ))
+    }
+}
+
+
+//------------ ProviderAsSet -------------------------------------------------
+
+/// The provider ASes of an ASPA.
+///
+/// The set is never empty, sorted in strictly ascending order, and never
+/// contains the customer AS.
+#[derive(Clone, Debug)]
+pub struct ProviderAsSet(Captured);
+
+impl ProviderAsSet {
+    fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>,
+        customer_as: AsId,
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            cons.capture(|cons| {
+                let mut last = None;
+                while let Some(id) = AsId::take_opt_from(cons)? {
+                    if id == customer_as {
+                        xerr!(return Err(decode::Malformed.into()))
+                    }
+                    if let Some(last) = last {
+                        if id <= last {
+                            xerr!(return Err(decode::Malformed.into()))
+                        }
+                    }
+                    last = Some(id);
+                }
+                if last.is_none() {
+                    xerr!(return Err(decode::Malformed.into()))
+                }
+                Ok(())
+            })
+        }).map(ProviderAsSet)
+    }
+
+    pub fn iter(&self) -> ProviderAsIter<'_> {
+        ProviderAsIter(self.0.as_ref())
+    }
+}
+
+
+//------------ ProviderAsIter ------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct ProviderAsIter<'a>(&'a [u8]);
+
+impl<'a> Iterator for ProviderAsIter<'a> {
+    type Item = AsId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            None
+        }
+        else {
+            Mode::Der.decode(&mut self.0, AsId::take_opt_from).unwrap()
+        }
+    }
+}
+
+
+//------------ AspaBuilder ---------------------------------------------------
+
+pub struct AspaBuilder {
+    customer_as: AsId,
+    providers: Vec<AsId>,
+}
+
+impl AspaBuilder {
+    pub fn new(customer_as: AsId) -> Self {
+        Self::with_providers(customer_as, Vec::new())
+    }
+
+    pub fn with_providers(customer_as: AsId, providers: Vec<AsId>) -> Self {
+        AspaBuilder { customer_as, providers }
+    }
+
+    pub fn customer_as(&self) -> AsId {
+        self.customer_as
+    }
+
+    pub fn set_customer_as(&mut self, customer_as: AsId) {
+        self.customer_as = customer_as
+    }
+
+    pub fn providers(&self) -> &[AsId] {
+        &self.providers
+    }
+
+    pub fn push_provider(&mut self, provider: AsId) {
+        self.providers.push(provider)
+    }
+
+    /// Creates the content of the ASPA.
+    ///
+    /// The providers are sorted and duplicates as well as the customer AS
+    /// are dropped. The resulting provider set may be empty and thus
+    /// invalid, so this is only used via `finalize` which checks for that.
+    fn to_attestation(&self) -> AsProviderAttestation {
+        let mut providers = self.providers.clone();
+        providers.retain(|id| *id != self.customer_as);
+        providers.sort();
+        providers.dedup();
+        AsProviderAttestation {
+            customer_as: self.customer_as,
+            providers: ProviderAsSet(Captured::from_values(
+                Mode::Der,
+                encode::slice(providers.as_slice(), |id: &AsId| id.encode())
+            ))
+        }
+    }
+
+    /// Finalizes the builder into an ASPA.
+    ///
+    /// Fails if there are no providers other than the customer AS as that
+    /// is not allowed and would lead to a malformed ASPA.
+    pub fn finalize<S: Signer>(
+        self,
+        mut sigobj: SignedObjectBuilder,
+        signer: &S,
+        issuer_key: &S::KeyId,
+    ) -> Result<Aspa, BuildError<S::Error>> {
+        let content = self.to_attestation();
+        // There must be some providers in order to make a valid ASPA.
+        if content.providers.0.is_empty() {
+            return Err(BuildError::NoProviders)
+        }
+        sigobj.set_v4_resources(None);
+        sigobj.set_v6_resources(None);
+        sigobj.set_as_resources(Some(AsResources::blocks(
+            AsBlocks::from_iter(Some(AsBlock::from(self.customer_as)))
+        )));
+        let signed = sigobj.finalize(
+            Oid(oid::CT_ASPA.0.into()),
+            content.encode_ref().to_captured(Mode::Der).into_bytes(),
+            signer,
+            issuer_key,
+        )?;
+        Ok(Aspa { signed, content })
+    }
+}
+
+
+//------------ BuildError ----------------------------------------------------
+
+/// An error happened while building an ASPA.
+#[derive(Clone, Debug)]
+pub enum BuildError<S> {
+    /// There are no provider ASes other than the customer AS.
+    NoProviders,
+
+    /// Signing the ASPA failed.
+    Signing(SigningError<S>),
+}
+
+impl<S> From<SigningError<S>> for BuildError<S> {
+    fn from(err: SigningError<S>) -> Self {
+        BuildError::Signing(err)
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for BuildError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::NoProviders => f.write_str("no provider ASes"),
+            BuildError::Signing(ref err) => err.fmt(f),
+        }
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn encode_content(
+        customer: u32, providers: &[u32]
+    ) -> Captured {
+        encode::sequence((
+            encode::sequence_as(Tag::CTX_0, 1u8.encode()),
+            AsId::from(customer).encode(),
+            encode::sequence(
+                encode::slice(providers, |id: &u32| AsId::from(*id).encode())
+            ),
+        )).to_captured(Mode::Der)
+    }
+
+    fn decode_content(
+        data: Captured
+    ) -> Result<AsProviderAttestation, decode::Error> {
+        Mode::Der.decode(data.as_slice(), AsProviderAttestation::take_from)
+    }
+
+    #[test]
+    fn decode_attestation() {
+        let res = decode_content(
+            encode_content(64496, &[64497, 64498, 65000])
+        ).unwrap();
+        assert_eq!(res.customer_as(), AsId::from(64496));
+        assert_eq!(
+            res.providers().iter().collect::<Vec<_>>(),
+            vec![
+                AsId::from(64497), AsId::from(64498), AsId::from(65000)
+            ]
+        );
+
+        assert!(decode_content(encode_content(64496, &[])).is_err());
+        assert!(
+            decode_content(encode_content(64496, &[64498, 64497])).is_err()
+        );
+        assert!(
+            decode_content(encode_content(64496, &[64497, 64497])).is_err()
+        );
+        assert!(
+            decode_content(encode_content(64496, &[64496, 64497])).is_err()
+        );
+    }
+
+    #[test]
+    fn builder_attestation() {
+        let mut builder = AspaBuilder::new(64496.into());
+        builder.push_provider(65000.into());
+        builder.push_provider(64496.into());
+        builder.push_provider(64497.into());
+        builder.push_provider(65000.into());
+        let content = builder.to_attestation();
+        let res = decode_content(
+            content.encode_ref().to_captured(Mode::Der)
+        ).unwrap();
+        assert_eq!(
+            res.providers().iter().collect::<Vec<_>>(),
+            vec![AsId::from(64497), AsId::from(65000)]
+        );
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use std::str::FromStr;
+    use crate::cert::{KeyUsage, Overclaim, TbsCert};
+    use crate::crypto::{PublicKeyFormat, Signer};
+    use crate::crypto::softsigner::OpenSslSigner;
+    use crate::resources::{AsId, Prefix};
+    use crate::tal::TalInfo;
+    use crate::uri;
+    use crate::x509::Validity;
+    use super::*;
+
+    fn make_aspa() -> (Aspa, ResourceCert) {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+
+        let mut cert = TbsCert::new(
+            12u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
+            Overclaim::Trim
+        );
+        cert.set_basic_ca(Some(true));
+        cert.set_ca_repository(Some(uri.clone()));
+        cert.set_rpki_manifest(Some(uri.clone()));
+        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = cert.into_cert(&signer, &key).unwrap();
+
+        let mut aspa = AspaBuilder::new(64496.into());
+        aspa.push_provider(64497.into());
+        aspa.push_provider(64498.into());
+        let aspa = aspa.finalize(
+            SignedObjectBuilder::new(
+                12u64.into(), Validity::from_secs(86400), uri.clone(),
+                uri.clone(), uri
+            ),
+            &signer, &key
+        ).unwrap();
+        let aspa = aspa.encode_ref().to_captured(Mode::Der);
+
+        let aspa = Aspa::decode(aspa.as_slice(), true).unwrap();
+        let cert = cert.validate_ta(
+            TalInfo::from_name("foo".into()).into_arc(), true
+        ).unwrap();
+        (aspa, cert)
+    }
+
+    #[test]
+    fn encode_aspa() {
+        let (aspa, issuer) = make_aspa();
+        let (cert, content) = aspa.process(
+            &issuer, true, |_| Ok(())
+        ).unwrap();
+        assert!(cert.v4_resources().is_empty());
+        assert_eq!(content.customer_as(), AsId::from(64496));
+        assert_eq!(
+            content.providers().iter().collect::<Vec<_>>(),
+            vec![AsId::from(64497), AsId::from(64498)]
+        );
+    }
+
+    #[test]
+    fn finalize_without_providers() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+
+        let mut aspa = AspaBuilder::new(64496.into());
+        aspa.push_provider(64496.into());
+        match aspa.finalize(
+            SignedObjectBuilder::new(
+                12u64.into(), Validity::from_secs(86400), uri.clone(),
+                uri.clone(), uri
+            ),
+            &signer, &key
+        ) {
+            Err(BuildError::NoProviders) => { }
+            _ => panic!("expected BuildError::NoProviders")
+        }
+    }
+
+    #[test]
+    fn serde_aspa() {
+        let (aspa, _) = make_aspa();
+
+        let serialized = serde_json::to_string(&aspa).unwrap();
+        let deser_aspa: Aspa = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            aspa.to_captured().into_bytes(),
+            deser_aspa.to_captured().into_bytes()
+        )
+    }
+}
+
+
+//============ Specification Documentation ===================================
+
+/// ASPA Specification.
+///
+/// This is a documentation-only module. It summarizes the specification for
+/// ASPAs, how they are parsed and constructed.
+///
+/// An Autonomous System Provider Authorization (ASPA) is a [signed object]
+/// in which the holder of a customer AS lists the ASes that are authorized
+/// as its upstream providers. It is specified in
+/// [draft-ietf-sidrops-aspa-profile].
+///
+/// The content of an ASPA signed object is of type `ASProviderAttestation`
+/// which is defined as follows:
+///
+/// ```txt
+/// ASProviderAttestation   ::= SEQUENCE {
+///     version                 [0] INTEGER(1),
+///     customerASID            ASID,
+///     providers               ProviderASSet
+/// }
+///
+/// ProviderASSet           ::= SEQUENCE (SIZE(1..MAX)) OF ASID
+///
+/// ASID                    ::= INTEGER (0..4294967295)
+/// ```
+///
+/// The _version_ must be 1 and is explicitly encoded. The _providers_ must
+/// be in strictly ascending order and must not contain the _customerASID._
+///
+/// The EE certificate must contain exactly the customer AS in its AS
+/// resources and must not have any IP resources.
+///
+/// [signed object]: ../../sigobj/spec/index.html
+/// [draft-ietf-sidrops-aspa-profile]: https://datatracker.ietf.org/doc/draft-ietf-sidrops-aspa-profile/
+pub mod spec { }
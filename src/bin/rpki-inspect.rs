@@ -0,0 +1,607 @@
+//! Shows the content of RPKI objects.
+//!
+//! Usage: `rpki-inspect [options] <path>`
+//!
+//! The file can be a certificate, CRL, manifest, ROA, Ghostbusters record,
+//! ASPA, TAL, or one of the XML documents used by RRDP and the RFC 8181
+//! publication protocol. All fields are printed either as indented text
+//! or, with `--json`, as a JSON object.
+//!
+//! If one or more issuer certificates are given via `--issuer`, the object
+//! is validated, too. The first issuer needs to be a trust anchor, every
+//! following one needs to be issued by the one before it. The object
+//! itself needs to be issued by the last issuer.
+//!
+//! The binary is only built if the `inspect` feature is enabled.
+
+extern crate rpki;
+
+use std::{env, fmt, fs, process};
+use std::str::FromStr;
+use bytes::Bytes;
+use serde::{Serialize, Serializer};
+use serde::ser::{SerializeMap, SerializeSeq};
+use rpki::{publication, rrdp};
+use rpki::aspa::Aspa;
+use rpki::cert::{Cert, OverclaimReport, ResourceCert};
+use rpki::crl::Crl;
+use rpki::gbr::Gbr;
+use rpki::manifest::Manifest;
+use rpki::object::RpkiObject;
+use rpki::resources::{IpBlocksForFamily, IpResources};
+use rpki::roa::Roa;
+use rpki::tal::{Tal, TalInfo};
+use rpki::x509::{Time, ValidationError};
+
+
+//------------ Options -------------------------------------------------------
+
+/// The command line options.
+struct Options {
+    /// The path of the file to inspect.
+    path: String,
+
+    /// Output JSON instead of text.
+    json: bool,
+
+    /// Decode and validate strictly.
+    strict: bool,
+
+    /// The paths of the issuer certificates, starting at the trust anchor.
+    issuers: Vec<String>,
+
+    /// The time to validate at.
+    now: Time,
+}
+
+impl Options {
+    fn from_args() -> Result<Self, String> {
+        let mut path = None;
+        let mut json = false;
+        let mut strict = false;
+        let mut issuers = Vec::new();
+        let mut now = Time::now();
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => json = true,
+                "--strict" => strict = true,
+                "--issuer" => {
+                    issuers.push(args.next().ok_or("missing issuer path")?)
+                }
+                "--time" => {
+                    let time = args.next().ok_or("missing time")?;
+                    now = Time::from_str(&time).map_err(|err| {
+                        format!("invalid time '{}': {}", time, err)
+                    })?;
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option {}", arg))
+                }
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err("more than one path given".into())
+            }
+        }
+        Ok(Options {
+            path: path.ok_or("missing path")?,
+            json, strict, issuers, now
+        })
+    }
+}
+
+
+//------------ Value ---------------------------------------------------------
+
+/// The printable content of an object.
+enum Value {
+    Text(String),
+    List(Vec<Value>),
+    Record(Record),
+}
+
+impl Value {
+    fn text<T: fmt::Display>(value: T) -> Self {
+        Value::Text(value.to_string())
+    }
+
+    fn time(time: Time) -> Self {
+        Value::Text(time.to_rfc3339())
+    }
+
+    fn write_text(
+        &self, f: &mut fmt::Formatter, indent: usize
+    ) -> fmt::Result {
+        match *self {
+            Value::Text(ref text) => writeln!(f, " {}", text),
+            Value::List(ref items) => {
+                writeln!(f)?;
+                for item in items {
+                    write!(f, "{:1$}-", "", indent * 2)?;
+                    item.write_text(f, indent + 1)?;
+                }
+                Ok(())
+            }
+            Value::Record(ref record) => {
+                writeln!(f)?;
+                record.write_text(f, indent)
+            }
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(
+        &self, serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        match *self {
+            Value::Text(ref text) => text.serialize(serializer),
+            Value::List(ref items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            Value::Record(ref record) => record.serialize(serializer),
+        }
+    }
+}
+
+
+//------------ Record --------------------------------------------------------
+
+/// A list of named values.
+#[derive(Default)]
+struct Record(Vec<(&'static str, Value)>);
+
+impl Record {
+    fn push(&mut self, name: &'static str, value: Value) {
+        self.0.push((name, value))
+    }
+
+    fn push_text<T: fmt::Display>(&mut self, name: &'static str, value: T) {
+        self.push(name, Value::text(value))
+    }
+
+    fn push_opt<T: fmt::Display>(
+        &mut self, name: &'static str, value: Option<T>
+    ) {
+        if let Some(value) = value {
+            self.push_text(name, value)
+        }
+    }
+
+    fn write_text(
+        &self, f: &mut fmt::Formatter, indent: usize
+    ) -> fmt::Result {
+        for (name, value) in &self.0 {
+            write!(f, "{:1$}{2}:", "", indent * 2, name)?;
+            value.write_text(f, indent + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(
+        &self, serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_text(f, 0)
+    }
+}
+
+
+//------------ Objects -------------------------------------------------------
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|ch| format!("{:02x}", ch)).collect()
+}
+
+fn ip_resources(res: &IpResources, v4: bool) -> Value {
+    match res.as_blocks() {
+        Some(blocks) if v4 => Value::text(IpBlocksForFamily::v4(blocks)),
+        Some(blocks) => Value::text(IpBlocksForFamily::v6(blocks)),
+        None => Value::text("inherit"),
+    }
+}
+
+fn cert_record(cert: &Cert) -> Record {
+    let mut res = Record::default();
+    res.push_text("serial-number", cert.serial_number());
+    res.push_text("issuer", cert.issuer());
+    res.push_text("subject", cert.subject());
+    res.push("not-before", Value::time(cert.validity().not_before()));
+    res.push("not-after", Value::time(cert.validity().not_after()));
+    res.push_text("subject-key-identifier", cert.subject_key_identifier());
+    res.push_opt(
+        "authority-key-identifier", cert.authority_key_identifier()
+    );
+    res.push_opt("basic-ca", cert.basic_ca());
+    res.push_text("key-usage", format!("{:?}", cert.key_usage()));
+    if cert.extended_key_usage().is_some() {
+        res.push_text("extended-key-usage", "present");
+    }
+    res.push_opt("crl-uri", cert.crl_uri());
+    res.push_opt("ca-issuer", cert.ca_issuer());
+    res.push_opt("ca-repository", cert.ca_repository());
+    res.push_opt("rpki-manifest", cert.rpki_manifest());
+    res.push_opt("signed-object", cert.signed_object());
+    res.push_opt("rpki-notify", cert.rpki_notify());
+    if let Some(v4) = cert.v4_resources() {
+        res.push("ipv4-resources", ip_resources(v4, true));
+    }
+    if let Some(v6) = cert.v6_resources() {
+        res.push("ipv6-resources", ip_resources(v6, false));
+    }
+    res.push_opt("as-resources", cert.as_resources());
+    res
+}
+
+fn crl_record(crl: &Crl) -> Record {
+    let mut res = Record::default();
+    res.push_text("issuer", crl.issuer());
+    res.push("this-update", Value::time(crl.this_update()));
+    res.push("next-update", Value::time(crl.next_update()));
+    res.push_text("crl-number", crl.crl_number());
+    res.push_text(
+        "authority-key-identifier", crl.authority_key_identifier()
+    );
+    res.push("revoked-certs", Value::List(
+        crl.revoked_certs().iter().map(|entry| {
+            let mut item = Record::default();
+            item.push_text("serial-number", entry.user_certificate());
+            item.push(
+                "revocation-date", Value::time(entry.revocation_date())
+            );
+            Value::Record(item)
+        }).collect()
+    ));
+    res
+}
+
+fn manifest_record(mft: &Manifest) -> Record {
+    let mut res = Record::default();
+    res.push("ee-cert", Value::Record(cert_record(mft.cert())));
+    let content = mft.content();
+    res.push_text("manifest-number", content.manifest_number());
+    res.push("this-update", Value::time(content.this_update()));
+    res.push("next-update", Value::time(content.next_update()));
+    res.push("files", Value::List(
+        content.iter().map(|item| {
+            let mut file = Record::default();
+            file.push_text("file", String::from_utf8_lossy(item.file()));
+            file.push_text("hash", hex(item.hash()));
+            Value::Record(file)
+        }).collect()
+    ));
+    res
+}
+
+fn roa_record(roa: &Roa) -> Record {
+    let mut res = Record::default();
+    res.push("ee-cert", Value::Record(cert_record(roa.cert())));
+    res.push_text("as-id", roa.content().as_id());
+    res.push("prefixes", Value::List(
+        roa.content().iter().map(|addr| {
+            Value::Text(format!(
+                "{}/{}-{}",
+                addr.address(), addr.address_length(), addr.max_length()
+            ))
+        }).collect()
+    ));
+    res
+}
+
+fn gbr_record(gbr: &Gbr) -> Record {
+    let mut res = Record::default();
+    res.push("ee-cert", Value::Record(cert_record(gbr.cert())));
+    let content = gbr.content();
+    res.push_text("full-name", content.full_name());
+    res.push_opt("org", content.org());
+    let list = |items: &[String]| {
+        Value::List(items.iter().map(Value::text).collect())
+    };
+    res.push("addresses", list(content.addresses()));
+    res.push("phones", list(content.phones()));
+    res.push("emails", list(content.emails()));
+    res
+}
+
+fn aspa_record(aspa: &Aspa) -> Record {
+    let mut res = Record::default();
+    res.push("ee-cert", Value::Record(cert_record(aspa.cert())));
+    res.push_text("customer-as", aspa.content().customer_as());
+    res.push("providers", Value::List(
+        aspa.content().providers().iter().map(Value::text).collect()
+    ));
+    res
+}
+
+fn tal_record(tal: &Tal) -> Record {
+    let mut res = Record::default();
+    res.push("uris", Value::List(tal.uris().map(Value::text).collect()));
+    res.push_text("key-identifier", tal.key_info().key_identifier());
+    res
+}
+
+fn object_record(object: &RpkiObject) -> Record {
+    let (kind, mut res) = match *object {
+        RpkiObject::Cert(ref cert) => ("certificate", cert_record(cert)),
+        RpkiObject::Crl(ref crl) => ("crl", crl_record(crl)),
+        RpkiObject::Manifest(ref mft) => ("manifest", manifest_record(mft)),
+        RpkiObject::Roa(ref roa) => ("roa", roa_record(roa)),
+        RpkiObject::Gbr(ref gbr) => ("ghostbusters", gbr_record(gbr)),
+        RpkiObject::Aspa(ref aspa) => ("aspa", aspa_record(aspa)),
+    };
+    res.0.insert(0, ("type", Value::text(kind)));
+    res
+}
+
+
+//------------ XML Documents -------------------------------------------------
+
+fn xml_record(data: &[u8]) -> Result<Record, String> {
+    let mut res = Record::default();
+    if let Ok(notify) = rrdp::NotificationFile::parse(data) {
+        res.push_text("type", "rrdp-notification");
+        res.push_text("session-id", notify.session_id);
+        res.push_text("serial", notify.serial);
+        res.push_text("snapshot-uri", notify.snapshot.uri());
+        res.push_text("snapshot-hash", notify.snapshot.hash());
+        res.push("deltas", Value::List(
+            notify.deltas.iter().map(|(serial, delta)| {
+                let mut item = Record::default();
+                item.push_text("serial", serial);
+                item.push_text("uri", delta.uri());
+                item.push_text("hash", delta.hash());
+                Value::Record(item)
+            }).collect()
+        ));
+    }
+    else if let Ok(snapshot) = rrdp::Snapshot::parse(data) {
+        res.push_text("type", "rrdp-snapshot");
+        res.push_text("session-id", snapshot.session_id());
+        res.push_text("serial", snapshot.serial());
+        res.push("publish", Value::List(
+            snapshot.iter().map(|item| Value::text(item.uri())).collect()
+        ));
+    }
+    else if let Ok(delta) = rrdp::Delta::parse(data) {
+        res.push_text("type", "rrdp-delta");
+        res.push_text("session-id", delta.session_id());
+        res.push_text("serial", delta.serial());
+        res.push("elements", Value::List(
+            delta.iter().map(|item| {
+                let mut elem = Record::default();
+                match *item {
+                    rrdp::DeltaElement::Publish { .. } => {
+                        elem.push_text("action", "publish")
+                    }
+                    _ => elem.push_text("action", "withdraw")
+                }
+                elem.push_text("uri", item.uri());
+                elem.push_opt("hash", item.hash());
+                Value::Record(elem)
+            }).collect()
+        ));
+    }
+    else if let Ok(msg) = publication::Message::parse(data) {
+        publication_record(&msg, &mut res);
+    }
+    else {
+        return Err("unsupported XML document".into())
+    }
+    Ok(res)
+}
+
+fn publication_record(msg: &publication::Message, res: &mut Record) {
+    use rpki::publication::{Message, Query, QueryElement, Reply};
+
+    let pdu = |element: &QueryElement| {
+        let mut item = Record::default();
+        match *element {
+            QueryElement::Publish { ref hash, .. } => {
+                item.push_text("action", "publish");
+                item.push_opt("hash", hash.as_ref());
+            }
+            QueryElement::Withdraw { ref hash, .. } => {
+                item.push_text("action", "withdraw");
+                item.push_text("hash", hash);
+            }
+        }
+        item.push_opt("tag", element.tag());
+        item.push_text("uri", element.uri());
+        Value::Record(item)
+    };
+
+    match *msg {
+        Message::Query(Query::List) => {
+            res.push_text("type", "publication-list-query");
+        }
+        Message::Query(Query::Delta(ref elements)) => {
+            res.push_text("type", "publication-query");
+            res.push("elements", Value::List(
+                elements.iter().map(pdu).collect()
+            ));
+        }
+        Message::Reply(Reply::Success) => {
+            res.push_text("type", "publication-success");
+        }
+        Message::Reply(Reply::List(ref elements)) => {
+            res.push_text("type", "publication-list-reply");
+            res.push("elements", Value::List(
+                elements.iter().map(|element| {
+                    let mut item = Record::default();
+                    item.push_text("uri", element.uri());
+                    item.push_text("hash", element.hash());
+                    Value::Record(item)
+                }).collect()
+            ));
+        }
+        Message::Reply(Reply::Errors(ref errors)) => {
+            res.push_text("type", "publication-error");
+            res.push("errors", Value::List(
+                errors.iter().map(|error| {
+                    let mut item = Record::default();
+                    item.push_text("code", error.code());
+                    item.push_opt("tag", error.tag());
+                    item.push_opt("text", error.text());
+                    if let Some(failed) = error.failed_pdu() {
+                        item.push("failed-pdu", pdu(failed));
+                    }
+                    Value::Record(item)
+                }).collect()
+            ));
+        }
+    }
+}
+
+
+//------------ Validation ----------------------------------------------------
+
+/// Loads and validates the chain of issuer certificates.
+fn load_issuers(options: &Options) -> Result<Option<ResourceCert>, String> {
+    let mut res: Option<ResourceCert> = None;
+    for path in &options.issuers {
+        let data = fs::read(path).map_err(|err| {
+            format!("cannot read issuer {}: {}", path, err)
+        })?;
+        let cert = Cert::decode(Bytes::from(data)).map_err(|err| {
+            format!("cannot decode issuer {}: {}", path, err)
+        })?;
+        let cert = match res {
+            Some(ref issuer) => {
+                cert.validate_ca_at(issuer, options.strict, options.now)
+            }
+            None => {
+                cert.validate_ta_at(
+                    TalInfo::from_name(path.clone()).into_arc(),
+                    options.strict, options.now
+                )
+            }
+        }.map_err(|err| format!("invalid issuer {}: {}", path, err))?;
+        res = Some(cert);
+    }
+    Ok(res)
+}
+
+/// Validates an object against its issuer.
+///
+/// Returns the report of trimmed resources for a valid certificate.
+fn validate(
+    object: RpkiObject,
+    issuer: &ResourceCert,
+    options: &Options
+) -> Result<Option<OverclaimReport>, ValidationError> {
+    let (strict, now) = (options.strict, options.now);
+    let report = |cert: ResourceCert| cert.overclaim_report().cloned();
+    match object {
+        RpkiObject::Cert(cert) => {
+            if cert.basic_ca() == Some(true) {
+                cert.validate_ca_at(issuer, strict, now).map(report)
+            }
+            else if cert.extended_key_usage().is_some() {
+                cert.validate_router_at(issuer, strict, now).map(report)
+            }
+            else {
+                cert.validate_ee_at(issuer, strict, now).map(report)
+            }
+        }
+        RpkiObject::Crl(crl) => {
+            crl.validate_at(issuer, strict, now).map(|_| None)
+        }
+        RpkiObject::Manifest(mft) => {
+            mft.validate_at(issuer, strict, now).map(|_| None)
+        }
+        RpkiObject::Roa(roa) => {
+            roa.process_at(issuer, strict, now, |_| Ok(())).map(|_| None)
+        }
+        RpkiObject::Gbr(gbr) => {
+            gbr.process_at(issuer, strict, now, |_| Ok(())).map(|_| None)
+        }
+        RpkiObject::Aspa(aspa) => {
+            aspa.process_at(issuer, strict, now, |_| Ok(())).map(|_| None)
+        }
+    }
+}
+
+
+//------------ main ----------------------------------------------------------
+
+fn inspect(options: &Options) -> Result<Record, String> {
+    let data = fs::read(&options.path).map_err(|err| {
+        format!("cannot read {}: {}", options.path, err)
+    })?;
+    if options.path.ends_with(".tal") {
+        let tal = Tal::read_named(
+            options.path.clone(), &mut data.as_slice()
+        ).map_err(|err| format!("cannot decode TAL: {}", err))?;
+        return Ok(tal_record(&tal))
+    }
+    if options.path.ends_with(".xml") {
+        return xml_record(&data)
+    }
+
+    let object = RpkiObject::decode(
+        Bytes::from(data), options.strict
+    ).map_err(|err| format!("cannot decode object: {}", err))?;
+    if let Err(err) = object.check_extension(&options.path) {
+        eprintln!("Warning: {}", err);
+    }
+    let mut res = object_record(&object);
+    if let Some(issuer) = load_issuers(options)? {
+        match validate(object, &issuer, options) {
+            Ok(report) => {
+                res.push_text("validation", "valid");
+                res.push_opt("trimmed-resources", report);
+            }
+            Err(err) => {
+                res.push_text("validation", format!("invalid: {}", err))
+            }
+        }
+    }
+    Ok(res)
+}
+
+fn main() {
+    let options = match Options::from_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}", err);
+            eprintln!(
+                "Usage: rpki-inspect [--json] [--strict] [--time <time>] \
+                 [--issuer <cert>]... <path>"
+            );
+            process::exit(2)
+        }
+    };
+    let record = match inspect(&options) {
+        Ok(record) => record,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1)
+        }
+    };
+    if options.json {
+        match serde_json::to_string_pretty(&record) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1)
+            }
+        }
+    }
+    else {
+        print!("{}", record)
+    }
+}
@@ -23,7 +23,7 @@ pub use self::builder::CertBuilder;
 pub mod builder;
 pub mod ext;
 
-use std::{borrow, ops};
+use std::{borrow, fmt, mem, ops};
 use std::iter::FromIterator;
 use std::sync::Arc;
 use bcder::{decode, encode};
@@ -35,11 +35,12 @@ use bcder::{
 use bytes::Bytes;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::oid;
-use crate::resources::{AsBlocks, IpBlocks};
+use crate::resources::{AsBlocks, AsId, IpBlocks};
 use crate::tal::TalInfo;
 use crate::uri;
 use crate::x509::{
     Name, SignedData, Serial, Time, Validity, ValidationError,
+    ValidationErrorKind,
     encode_extension, update_first, update_once
 };
 use crate::crypto::{
@@ -162,40 +163,47 @@ impl Cert {
         now: Time,
     ) -> Result<ResourceCert, ValidationError> {
         self.validate_basics(strict, now)?;
+        self.validate_rpki_basics(strict)?;
         self.validate_ca_basics(strict)?;
 
         // 4.8.3. Authority Key Identifier. May be present, if so, must be
         // equal to the subject key indentifier.
         if let Some(ref aki) = self.authority_key_identifier {
             if *aki != self.subject_key_identifier {
-                return Err(ValidationError);
+                return Err(ValidationError::new(
+                    ValidationErrorKind::AuthorityKeyIdentifierMismatch
+                ).with_rfc(6487, "4.8.3"))
             }
         }
 
         // 4.8.6. CRL Distribution Points. There musn’t be one.
         if self.crl_uri.is_some() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidCrlDistributionPoint
+            ).with_rfc(6487, "4.8.6"))
         }
 
         // 4.8.7. Authority Information Access. Must not be present.
         if self.ca_issuer.is_some() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidAuthorityInfoAccess
+            ).with_rfc(6487, "4.8.7"))
         }
 
         // 4.8.10. IP Resources. If present, musn’t be "inherit".
         let v4_resources = IpBlocks::from_resources(
             self.v4_resources.as_ref()
-        )?;
+        ).map_err(|err| err.with_rfc(6487, "4.8.10"))?;
         let v6_resources = IpBlocks::from_resources(
             self.v6_resources.as_ref()
-        )?;
+        ).map_err(|err| err.with_rfc(6487, "4.8.10"))?;
  
         // 4.8.11.  AS Resources. If present, musn’t be "inherit". That
         // IP resources (logical) or AS resources are present has already
         // been checked during parsing.
         let as_resources = AsBlocks::from_resources(
             self.as_resources.as_ref()
-        )?;
+        ).map_err(|err| err.with_rfc(6487, "4.8.11"))?;
 
         self.signed_data.verify_signature(
             &self.subject_public_key_info
@@ -206,7 +214,8 @@ impl Cert {
             v4_resources,
             v6_resources,
             as_resources,
-            tal
+            tal,
+            overclaim: None,
         })
     }
 
@@ -231,6 +240,7 @@ impl Cert {
         now: Time,
     ) -> Result<ResourceCert, ValidationError> {
         self.validate_basics(strict, now)?;
+        self.validate_rpki_basics(strict)?;
         self.validate_ca_basics(strict)?;
         self.validate_issued(issuer, strict)?;
         self.validate_signature(issuer, strict)?;
@@ -258,17 +268,22 @@ impl Cert {
         now: Time,
     ) -> Result<ResourceCert, ValidationError>  {
         self.validate_basics(strict, now)?;
+        self.validate_rpki_basics(strict)?;
         self.validate_issued(issuer, strict)?;
 
         // 4.8.1. Basic Constraints: Must not be present.
         if self.basic_ca.is_some(){
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ).with_rfc(6487, "4.8.1"))
         }
 
         // 4.8.4. Key Usage. Bits for CA or not CA have been checked during
         // parsing already.
         if self.key_usage != KeyUsage::Ee {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidKeyUsage
+            ).with_rfc(6487, "4.8.4"))
         }
 
         // 4.8.8.  Subject Information Access. We need the signed object
@@ -276,10 +291,119 @@ impl Cert {
         if self.ca_repository.is_some() || self.rpki_manifest.is_some()
             || self.signed_object.is_none()
         {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidSubjectInfoAccess
+            ).with_rfc(6487, "4.8.8"))
         }
         if self.rpki_notify.is_some() && strict {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidSubjectInfoAccess
+            ).with_rfc(6487, "4.8.8"))
+        }
+
+        self.validate_signature(issuer, strict)?;
+        self.validate_resources(issuer, strict)
+    }
+
+    /// Validates the certificate as a BGPsec router certificate.
+    ///
+    /// This checks the profile defined in [RFC 8209] which differs from
+    /// that of EE certificates in requiring an ECDSA P-256 key and the
+    /// BGPsec router extended key usage, having no subject information
+    /// access, and carrying AS resources only.
+    ///
+    /// For validation to succeed, the certificate needs to have been signed
+    /// by the provided `issuer` certificate.
+    ///
+    /// Note that this does _not_ check the CRL.
+    ///
+    /// [RFC 8209]: https://tools.ietf.org/html/rfc8209
+    pub fn validate_router(
+        self,
+        issuer: &ResourceCert,
+        strict: bool
+    ) -> Result<ResourceCert, ValidationError> {
+        self.validate_router_at(issuer, strict, Time::now())
+    }
+
+    /// Validates the certificate as a BGPsec router certificate at `now`.
+    ///
+    /// In addition to the checks common to all RPKI certificates, this
+    /// requires the certificate to follow RFC 8209, section 3.1: it must
+    /// have an ECDSA P-256 key, no basic constraints, the key usage of an
+    /// EE certificate, the BGPsec router extended key usage, and no
+    /// subject information access. It must carry AS resources that are not
+    /// inherited and no IP resources at all.
+    ///
+    /// If validation succeeds, the certificate is returned as a
+    /// `ResourceCert` with its AS resources checked against those of
+    /// `issuer`. Its router key can then be retrieved via
+    /// `ResourceCert::router_key`.
+    ///
+    /// Note that this does _not_ check the CRL.
+    pub fn validate_router_at(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+    ) -> Result<ResourceCert, ValidationError> {
+        self.validate_basics(strict, now)?;
+        self.validate_issued(issuer, strict)?;
+
+        // RFC 8209, 3.1.2. Subject Public Key Info. Must be an ECDSA P-256
+        // key as per RFC 8208.
+        if !self.subject_public_key_info.algorithm().allow_router_cert() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidPublicKey
+            ).with_rfc(8209, "3.1.2"))
+        }
+
+        // RFC 8209, 3.1.3.1. Basic Constraints. Must not be present.
+        if self.basic_ca.is_some() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ).with_rfc(8209, "3.1.3.1"))
+        }
+
+        // 4.8.4. Key Usage. Router certificates are EE certificates.
+        if self.key_usage != KeyUsage::Ee {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidKeyUsage
+            ).with_rfc(6487, "4.8.4"))
+        }
+
+        // RFC 8209, 3.1.3.2. Extended Key Usage. Must be present and
+        // contain id-kp-bgpsec-router.
+        if !self.has_bgpsec_router_eku() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidExtendedKeyUsage
+            ).with_rfc(8209, "3.1.3.2"))
+        }
+
+        // RFC 8209, 3.1.3.3. Subject Information Access. Must not be
+        // present.
+        if self.has_sia() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidSubjectInfoAccess
+            ).with_rfc(8209, "3.1.3.3"))
+        }
+
+        // RFC 8209, 3.1.3.4. IP Resources. Must not be present.
+        if self.has_ip_resources() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::UnexpectedResources
+            ).with_rfc(8209, "3.1.3.4"))
+        }
+
+        // RFC 8209, 3.1.3.5. AS Resources. Must be present and must not
+        // be "inherit". Since there are no IP resources, parsing has
+        // already made sure they are present.
+        if self.as_resources.as_ref().map(AsResources::is_inherited)
+            .unwrap_or(true)
+        {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InheritedResources
+            ).with_rfc(8209, "3.1.3.5"))
         }
 
         self.validate_signature(issuer, strict)?;
@@ -301,26 +425,35 @@ impl Cert {
         // 4.2 Serial Number: must be unique over the CA. We cannot check
         // here, and -- XXX --- probably don’t care?
 
-        // 4.3 Signature Algorithm: limited to those in RFC 6485. Already
-        // checked in parsing.
+        // 4.3 Signature Algorithm: limited to those in RFC 7935. Parsing
+        // also accepts ECDSA for BGPsec but since all issuers have RSA keys,
+        // such signatures will fail verification.
         //
         // However, RFC 5280 demands that the two mentions of the signature
         // algorithm are the same. So we do that here.
         if self.signature != self.signed_data.signature().algorithm() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignatureAlgorithmMismatch
+            ).with_rfc(5280, "4.1.1.2"))
         }
 
         // 4.4 Issuer: must have certain format. 
-        Name::validate_rpki(&self.issuer, strict)?;
+        Name::validate_rpki(&self.issuer, strict).map_err(|err| {
+            err.with_rfc(6487, "4.4")
+        })?;
 
         // 4.5 Subject: same as 4.4.
-        Name::validate_rpki(&self.subject, strict)?;
+        Name::validate_rpki(&self.subject, strict).map_err(|err| {
+            err.with_rfc(6487, "4.5")
+        })?;
         
         // 4.6 Validity. Check according to RFC 5280.
-        self.validity.validate_at(now)?;
+        self.validity.validate_at(now).map_err(|err| {
+            err.with_rfc(6487, "4.6")
+        })?;
 
-        // 4.7 Subject Public Key Info: limited algorithms. Already checked
-        // during parsing.
+        // 4.7 Subject Public Key Info: limited algorithms. Differs between
+        // RPKI and BGPsec router certificates.
 
         // 4.8.1. Basic Constraints. Differing requirements for CA and EE
         // certificates.
@@ -329,7 +462,9 @@ impl Cert {
         // of the subjectPublicKey.
         if self.subject_key_identifier() != 
                              self.subject_public_key_info().key_identifier() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::KeyIdentifierMismatch
+            ).with_rfc(6487, "4.8.2"))
         }
 
         // 4.8.3. Authority Key Identifier. Differing requirements of TA and
@@ -337,11 +472,8 @@ impl Cert {
 
         // 4.8.4. Key Usage. Differs between CA and EE certificates.
 
-        // 4.8.5. Extended Key Usage. Must not be present for the kind of
-        // certificates we use here.
-        if self.extended_key_usage().is_some() {
-            return Err(ValidationError)
-        }
+        // 4.8.5. Extended Key Usage. Differs between RPKI and BGPsec
+        // router certificates.
 
         // 4.8.6. CRL Distribution Points. Differs between TA and other
         // certificates.
@@ -364,6 +496,31 @@ impl Cert {
         Ok(())
     }
 
+    /// Validates the parts specific to certificates used within the RPKI.
+    ///
+    /// These are all certificates except BGPsec router certificates.
+    fn validate_rpki_basics(
+        &self,
+        _strict: bool
+    ) -> Result<(), ValidationError> {
+        // 4.7 Subject Public Key Info: limited to RSA keys by RFC 7935.
+        if !self.subject_public_key_info.algorithm().allow_rpki_cert() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidPublicKey
+            ).with_rfc(6487, "4.7"))
+        }
+
+        // 4.8.5. Extended Key Usage. Must not be present for the kind of
+        // certificates we use here.
+        if self.extended_key_usage().is_some() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidExtendedKeyUsage
+            ).with_rfc(6487, "4.8.5"))
+        }
+
+        Ok(())
+    }
+
     /// Validates that the certificate is a correctly issued certificate.
     fn validate_issued(
         &self,
@@ -375,12 +532,16 @@ impl Cert {
         if self.authority_key_identifier()
             != Some(issuer.cert.subject_key_identifier())
         {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::AuthorityKeyIdentifierMismatch
+            ).with_rfc(6487, "4.8.3"))
         }
 
         // 4.8.6. CRL Distribution Points. There must be one.
         if self.crl_uri().is_none() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidCrlDistributionPoint
+            ).with_rfc(6487, "4.8.6"))
         }
 
         // 4.8.7. Authority Information Access. Must be present and contain
@@ -388,7 +549,9 @@ impl Cert {
         // we don’t really need that URI so – XXX – leave it unchecked for
         // now.
         if self.ca_issuer().is_none() {
-            return Err(ValidationError);
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidAuthorityInfoAccess
+            ).with_rfc(6487, "4.8.7"))
         }
 
         Ok(())
@@ -405,20 +568,26 @@ impl Cert {
         // 4.8.1. Basic Constraints: For a CA it must be present (RFC6487)
         // und the “cA” flag must be set (RFC5280).
         if self.basic_ca() != Some(true) {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ).with_rfc(6487, "4.8.1"))
         }
 
         // 4.8.4. Key Usage. Bits for CA or not CA have been checked during
         // parsing already.
         if self.key_usage() != KeyUsage::Ca {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidKeyUsage
+            ).with_rfc(6487, "4.8.4"))
         }
 
         // 4.8.8.  Subject Information Access.
         if self.ca_repository().is_none() || self.rpki_manifest().is_none()
             || self.signed_object().is_some()
         {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidSubjectInfoAccess
+            ).with_rfc(6487, "4.8.8"))
         }
 
         Ok(())
@@ -443,6 +612,32 @@ impl Cert {
         issuer: &ResourceCert,
         _strict: bool
     ) -> Result<ResourceCert, ValidationError> {
+        // Collect all overclaimed resources first so that we can report
+        // all of them and not just those of the first family that fails.
+        let report = OverclaimReport::new(
+            issuer.v4_resources.overclaimed(self.v4_resources()),
+            issuer.v6_resources.overclaimed(self.v6_resources()),
+            issuer.as_resources.overclaimed(self.as_resources()),
+        );
+        let overclaim = if report.is_empty() {
+            None
+        }
+        else if self.overclaim == Overclaim::Refuse {
+            let section = if report.v4_resources().is_empty()
+                                && report.v6_resources().is_empty() {
+                "4.8.11"
+            }
+            else {
+                "4.8.10"
+            };
+            return Err(ValidationError::new(
+                ValidationErrorKind::Overclaim
+            ).with_rfc(6487, section).with_overclaim(report))
+        }
+        else {
+            Some(report)
+        };
+
         Ok(ResourceCert {
             // 4.8.10.  IP Resources. If present, must be encompassed by or
             // trimmed down to the issuer certificate.
@@ -459,6 +654,7 @@ impl Cert {
             )?,
             cert: self,
             tal: issuer.tal.clone(),
+            overclaim,
         })
     }
 }
@@ -774,12 +970,48 @@ impl TbsCert {
 
     /// Returns a reference to the extended key usage if present.
     ///
-    /// Since this field isn’t allowed in any certificate used for RPKI
-    /// objects directly, we do not currently support setting this field.
+    /// The value is the content of the DER-encoded sequence of key purpose
+    /// object identifiers.
     pub fn extended_key_usage(&self) -> Option<&Captured> {
         self.extended_key_usage.as_ref()
     }
 
+    /// Sets the extended key usage.
+    ///
+    /// The value needs to be the content of the DER-encoded sequence of
+    /// key purpose object identifiers. This extension is only allowed in
+    /// BGPsec router certificates for which you can use
+    /// [`set_bgpsec_router_eku`] instead.
+    ///
+    /// [`set_bgpsec_router_eku`]: #method.set_bgpsec_router_eku
+    pub fn set_extended_key_usage(&mut self, value: Option<Captured>) {
+        self.extended_key_usage = value
+    }
+
+    /// Returns whether the extended key usage allows BGPsec router use.
+    pub fn has_bgpsec_router_eku(&self) -> bool {
+        let eku = match self.extended_key_usage {
+            Some(ref eku) => eku,
+            None => return false
+        };
+        Mode::Der.decode(eku.as_slice(), |cons| {
+            let mut res = false;
+            while let Some(id) = Oid::take_opt_from(cons)? {
+                if id == oid::KP_BGPSEC_ROUTER {
+                    res = true
+                }
+            }
+            Ok(res)
+        }).unwrap_or(false)
+    }
+
+    /// Sets the extended key usage to that of a BGPsec router certificate.
+    pub fn set_bgpsec_router_eku(&mut self) {
+        self.extended_key_usage = Some(Captured::from_values(
+            Mode::Der, oid::KP_BGPSEC_ROUTER.encode()
+        ))
+    }
+
     /// Returns a reference to the certificate’s CRL distribution point.
     pub fn crl_uri(&self) -> Option<&uri::Rsync> {
         self.crl_uri.as_ref()
@@ -840,6 +1072,12 @@ impl TbsCert {
         self.rpki_notify = uri
     }
 
+    /// Returns whether the certificate has any subject information access.
+    pub fn has_sia(&self) -> bool {
+        self.ca_repository.is_some() || self.rpki_manifest.is_some()
+            || self.signed_object.is_some() || self.rpki_notify.is_some()
+    }
+
     /// Returns the overclaim mode of the certificate.
     pub fn overclaim(&self) -> Overclaim {
         self.overclaim
@@ -1137,7 +1375,7 @@ impl TbsCert {
     /// the value of the DER-encoded bit string of the subject public key.
     ///
     /// Conforming CAs MUST mark this extension as non-critical.
-    fn take_subject_key_identifier<S: decode::Source>(
+    pub(crate) fn take_subject_key_identifier<S: decode::Source>(
         cons: &mut decode::Constructed<S>,
         subject_key_id: &mut Option<KeyIdentifier>,
     ) -> Result<(), S::Err> {
@@ -1156,7 +1394,7 @@ impl TbsCert {
     /// Must be present except in self-signed CA certificates where it is
     /// optional. The keyIdentifier field must be present, the other must not
     /// be.
-    fn take_authority_key_identifier<S: decode::Source>(
+    pub(crate) fn take_authority_key_identifier<S: decode::Source>(
         cons: &mut decode::Constructed<S>,
         authority_key_id: &mut Option<KeyIdentifier>,
     ) -> Result<(), S::Err> {
@@ -1517,35 +1755,40 @@ impl TbsCert {
                 }),
 
                 // Subject Information Access
-                encode_extension(
-                    &oid::PE_SUBJECT_INFO_ACCESS, false,
-                    encode::sequence((
-                        self.ca_repository.as_ref().map(|uri| {
-                            encode::sequence((
-                                oid::AD_CA_REPOSITORY.encode(),
-                                uri.encode_general_name()
-                            ))
-                        }),
-                        self.rpki_manifest.as_ref().map(|uri| {
-                            encode::sequence((
-                                oid::AD_RPKI_MANIFEST.encode(),
-                                uri.encode_general_name()
-                            ))
-                        }),
-                        self.signed_object.as_ref().map(|uri| {
-                            encode::sequence((
-                                oid::AD_SIGNED_OBJECT.encode(),
-                                uri.encode_general_name()
-                            ))
-                        }),
-                        self.rpki_notify.as_ref().map(|uri| {
-                            encode::sequence((
-                                oid::AD_RPKI_NOTIFY.encode(),
-                                uri.encode_general_name()
-                            ))
-                        })
+                if self.has_sia() {
+                    Some(encode_extension(
+                        &oid::PE_SUBJECT_INFO_ACCESS, false,
+                        encode::sequence((
+                            self.ca_repository.as_ref().map(|uri| {
+                                encode::sequence((
+                                    oid::AD_CA_REPOSITORY.encode(),
+                                    uri.encode_general_name()
+                                ))
+                            }),
+                            self.rpki_manifest.as_ref().map(|uri| {
+                                encode::sequence((
+                                    oid::AD_RPKI_MANIFEST.encode(),
+                                    uri.encode_general_name()
+                                ))
+                            }),
+                            self.signed_object.as_ref().map(|uri| {
+                                encode::sequence((
+                                    oid::AD_SIGNED_OBJECT.encode(),
+                                    uri.encode_general_name()
+                                ))
+                            }),
+                            self.rpki_notify.as_ref().map(|uri| {
+                                encode::sequence((
+                                    oid::AD_RPKI_NOTIFY.encode(),
+                                    uri.encode_general_name()
+                                ))
+                            })
+                        ))
                     ))
-                ),
+                }
+                else {
+                    None
+                },
 
                 // Certificate Policies
                 encode_extension(
@@ -1690,6 +1933,11 @@ pub struct ResourceCert {
 
     /// The TAL this is based on.
     tal: Arc<TalInfo>,
+
+    /// The resources trimmed from the certificate during validation.
+    ///
+    /// This is `None` if nothing needed trimming.
+    overclaim: Option<OverclaimReport>,
 }
 
 impl ResourceCert {
@@ -1718,10 +1966,36 @@ impl ResourceCert {
         &self.tal
     }
 
+    /// Returns the report of resources trimmed during validation.
+    ///
+    /// This is only ever present for certificates using
+    /// `Overclaim::Trim` that claimed resources not held by their issuer.
+    /// The resources of the certificate have already been reduced to
+    /// exclude those in the report.
+    pub fn overclaim_report(&self) -> Option<&OverclaimReport> {
+        self.overclaim.as_ref()
+    }
+
     /// Converts the certificate into its TAL info.
     pub fn into_tal(self) -> Arc<TalInfo> {
         self.tal
     }
+
+    /// Returns the router key of the certificate.
+    ///
+    /// For a validated BGPsec router certificate, the returned
+    /// [`RouterKey`] combines the certificate’s AS resources with its key
+    /// identifier and public key. For all other certificates the result is
+    /// meaningless.
+    ///
+    /// [`RouterKey`]: struct.RouterKey.html
+    pub fn router_key(&self) -> RouterKey {
+        RouterKey {
+            as_resources: self.as_resources.clone(),
+            key_identifier: self.cert.subject_key_identifier(),
+            key: self.cert.subject_public_key_info().clone(),
+        }
+    }
 }
 
 
@@ -1748,6 +2022,55 @@ impl AsRef<TbsCert> for ResourceCert {
 }
 
 
+//------------ RouterKey -----------------------------------------------------
+
+/// The key of a BGPsec router.
+///
+/// This combines an AS number with the subject key identifier and the
+/// public key of a validated BGPsec router certificate as needed by BGPsec
+/// speakers (see section 3 of [RFC 8210]).
+///
+/// [RFC 8210]: https://tools.ietf.org/html/rfc8210
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RouterKey {
+    /// The AS numbers the key is valid for.
+    as_resources: AsBlocks,
+
+    /// The subject key identifier of the router certificate.
+    key_identifier: KeyIdentifier,
+
+    /// The public key of the router certificate.
+    key: PublicKey,
+}
+
+impl RouterKey {
+    /// Returns the AS numbers the key is valid for.
+    ///
+    /// A router certificate may claim large ranges of AS numbers, so these
+    /// are kept as blocks rather than expanded into individual numbers.
+    pub fn as_resources(&self) -> &AsBlocks {
+        &self.as_resources
+    }
+
+    /// Returns whether the key is valid for the given AS number.
+    pub fn contains_asn(&self, asn: AsId) -> bool {
+        self.as_resources.iter().any(|block| {
+            block.min() <= asn && asn <= block.max()
+        })
+    }
+
+    /// Returns the subject key identifier of the router certificate.
+    pub fn key_identifier(&self) -> KeyIdentifier {
+        self.key_identifier
+    }
+
+    /// Returns a reference to the router’s public key.
+    pub fn key(&self) -> &PublicKey {
+        &self.key
+    }
+}
+
+
 //------------ KeyUsage ------------------------------------------------------
 
 /// The allowed key usages of a resource certificate.
@@ -1858,6 +2181,138 @@ impl Overclaim {
 }
 
 
+//------------ OverclaimReport -----------------------------------------------
+
+/// The resources a certificate claims beyond those of its issuer.
+///
+/// If a certificate uses [`Overclaim::Trim`], the report is available
+/// through [`ResourceCert::overclaim_report`] after validation. If it uses
+/// [`Overclaim::Refuse`], validation fails and the report is attached to
+/// the error instead.
+///
+/// The reports attached to errors by [`IpBlocks::validate_issued`] only
+/// know the overclaimed IP resources but not their address family. These
+/// are available through [`ip_resources`] until the report is assigned a
+/// family via [`with_family`].
+///
+/// [`IpBlocks::validate_issued`]: ../resources/struct.IpBlocks.html#method.validate_issued
+/// [`ip_resources`]: #method.ip_resources
+/// [`with_family`]: #method.with_family
+/// [`Overclaim::Trim`]: enum.Overclaim.html#variant.Trim
+/// [`Overclaim::Refuse`]: enum.Overclaim.html#variant.Refuse
+/// [`ResourceCert::overclaim_report`]: struct.ResourceCert.html#method.overclaim_report
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverclaimReport {
+    /// The overclaimed IPv4 resources.
+    v4_resources: IpBlocks,
+
+    /// The overclaimed IPv6 resources.
+    v6_resources: IpBlocks,
+
+    /// The overclaimed AS resources.
+    as_resources: AsBlocks,
+
+    /// Overclaimed IP resources of an address family not yet known.
+    ip_resources: IpBlocks,
+}
+
+impl OverclaimReport {
+    /// Creates a new report from the overclaimed resources.
+    pub fn new(
+        v4_resources: IpBlocks,
+        v6_resources: IpBlocks,
+        as_resources: AsBlocks,
+    ) -> Self {
+        OverclaimReport {
+            v4_resources, v6_resources, as_resources,
+            ip_resources: IpBlocks::empty(),
+        }
+    }
+
+    /// Creates a report for overclaimed IP resources of unknown family.
+    pub fn from_ip_blocks(ip_resources: IpBlocks) -> Self {
+        OverclaimReport {
+            v4_resources: IpBlocks::empty(),
+            v6_resources: IpBlocks::empty(),
+            as_resources: AsBlocks::empty(),
+            ip_resources,
+        }
+    }
+
+    /// Creates a report for overclaimed AS resources only.
+    pub fn from_as_blocks(as_resources: AsBlocks) -> Self {
+        Self::new(IpBlocks::empty(), IpBlocks::empty(), as_resources)
+    }
+
+    /// Assigns the IP resources of unknown family to the given family.
+    pub fn with_family(mut self, family: AddressFamily) -> Self {
+        let blocks = mem::replace(&mut self.ip_resources, IpBlocks::empty());
+        let target = match family {
+            AddressFamily::Ipv4 => &mut self.v4_resources,
+            AddressFamily::Ipv6 => &mut self.v6_resources,
+        };
+        *target = target.union(&blocks);
+        self
+    }
+
+    /// Returns the overclaimed IPv4 resources.
+    pub fn v4_resources(&self) -> &IpBlocks {
+        &self.v4_resources
+    }
+
+    /// Returns the overclaimed IPv6 resources.
+    pub fn v6_resources(&self) -> &IpBlocks {
+        &self.v6_resources
+    }
+
+    /// Returns the overclaimed AS resources.
+    pub fn as_resources(&self) -> &AsBlocks {
+        &self.as_resources
+    }
+
+    /// Returns the overclaimed IP resources of unknown address family.
+    pub fn ip_resources(&self) -> &IpBlocks {
+        &self.ip_resources
+    }
+
+    /// Returns whether no resources were overclaimed at all.
+    pub fn is_empty(&self) -> bool {
+        self.v4_resources.is_empty()
+            && self.v6_resources.is_empty()
+            && self.as_resources.is_empty()
+            && self.ip_resources.is_empty()
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for OverclaimReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut sep = "";
+        if !self.v4_resources.is_empty() {
+            write!(f, "IPv4 {}", self.v4_resources.as_v4())?;
+            sep = "; ";
+        }
+        if !self.v6_resources.is_empty() {
+            write!(f, "{}IPv6 {}", sep, self.v6_resources.as_v6())?;
+            sep = "; ";
+        }
+        if !self.as_resources.is_empty() {
+            write!(f, "{}{}", sep, self.as_resources)?;
+            sep = "; ";
+        }
+        if !self.ip_resources.is_empty() {
+            write!(
+                f, "{}{} IP address blocks", sep,
+                self.ip_resources.iter().count()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+
 //============ Tests =========================================================
 
 #[cfg(test)]
@@ -1920,5 +2375,175 @@ mod signer_test {
         let talinfo = TalInfo::from_name("foo".into()).into_arc();
         cert.validate_ta(talinfo, true).unwrap();
     }
+
+    #[test]
+    fn build_router_cert() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+        let mut ta = TbsCert::new(
+            12u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, pubkey.clone(), KeyUsage::Ca,
+            Overclaim::Trim
+        );
+        ta.set_basic_ca(Some(true));
+        ta.set_ca_repository(Some(uri.clone()));
+        ta.set_rpki_manifest(Some(uri.clone()));
+        ta.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let ta = ta.into_cert(&signer, &key).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let ta = ta.validate_ta(talinfo, true).unwrap();
+
+        let router_key = signer.create_key(
+            PublicKeyFormat::EcdsaP256
+        ).unwrap();
+        let router_key = signer.get_key_info(&router_key).unwrap();
+        assert_eq!(*router_key.algorithm(), PublicKeyFormat::EcdsaP256);
+
+        let mut cert = TbsCert::new(
+            13u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, router_key.clone(),
+            KeyUsage::Ee, Overclaim::Trim
+        );
+        cert.set_authority_key_identifier(
+            Some(pubkey.key_identifier())
+        );
+        cert.set_crl_uri(Some(uri.clone()));
+        cert.set_ca_issuer(Some(uri));
+        cert.set_bgpsec_router_eku();
+        cert.build_as_resource_blocks(|b| b.push(AsId::from(64496)));
+        let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+        let cert = Cert::decode(cert.as_slice()).unwrap();
+        assert!(cert.has_bgpsec_router_eku());
+        assert!(!cert.has_sia());
+
+        assert_eq!(
+            cert.clone().validate_ee(&ta, true).unwrap_err().kind(),
+            ValidationErrorKind::InvalidPublicKey
+        );
+        let cert = cert.validate_router(&ta, true).unwrap();
+        let key = cert.router_key();
+        assert!(key.contains_asn(AsId::from(64496)));
+        assert!(!key.contains_asn(AsId::from(64497)));
+        assert_eq!(key.as_resources().iter().count(), 1);
+        assert_eq!(key.key_identifier(), router_key.key_identifier());
+        assert_eq!(*key.key(), router_key);
+    }
+
+    #[test]
+    fn build_wide_router_cert() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+        let mut ta = TbsCert::new(
+            12u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, pubkey.clone(), KeyUsage::Ca,
+            Overclaim::Trim
+        );
+        ta.set_basic_ca(Some(true));
+        ta.set_ca_repository(Some(uri.clone()));
+        ta.set_rpki_manifest(Some(uri.clone()));
+        ta.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let ta = ta.into_cert(&signer, &key).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let ta = ta.validate_ta(talinfo, true).unwrap();
+
+        let router_key = signer.create_key(
+            PublicKeyFormat::EcdsaP256
+        ).unwrap();
+        let router_key = signer.get_key_info(&router_key).unwrap();
+        let mut cert = TbsCert::new(
+            13u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, router_key,
+            KeyUsage::Ee, Overclaim::Trim
+        );
+        cert.set_authority_key_identifier(
+            Some(pubkey.key_identifier())
+        );
+        cert.set_crl_uri(Some(uri.clone()));
+        cert.set_ca_issuer(Some(uri));
+        cert.set_bgpsec_router_eku();
+        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+        let cert = Cert::decode(cert.as_slice()).unwrap();
+
+        // All four billion AS numbers end up in a single block.
+        let key = cert.validate_router(&ta, true).unwrap().router_key();
+        assert_eq!(key.as_resources().iter().count(), 1);
+        assert!(key.contains_asn(AsId::MIN));
+        assert!(key.contains_asn(AsId::MAX));
+    }
+
+    #[test]
+    fn overclaim_report() {
+        use std::net::Ipv4Addr;
+
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+        let mut ta = TbsCert::new(
+            12u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, pubkey.clone(), KeyUsage::Ca,
+            Overclaim::Trim
+        );
+        ta.set_basic_ca(Some(true));
+        ta.set_ca_repository(Some(uri.clone()));
+        ta.set_rpki_manifest(Some(uri.clone()));
+        ta.build_v4_resource_blocks(|b| {
+            b.push(Prefix::new(Ipv4Addr::new(10, 0, 0, 0), 8))
+        });
+        ta.build_as_resource_blocks(|b| {
+            b.push((AsId::from(1), AsId::from(10)))
+        });
+        let ta = ta.into_cert(&signer, &key).unwrap();
+        let talinfo = TalInfo::from_name("foo".into()).into_arc();
+        let ta = ta.validate_ta(talinfo, true).unwrap();
+        assert!(ta.overclaim_report().is_none());
+
+        let child = |overclaim| {
+            let mut cert = TbsCert::new(
+                13u64.into(), pubkey.to_subject_name(),
+                Validity::from_secs(86400), Some(pubkey.to_subject_name()),
+                pubkey.clone(), KeyUsage::Ca, overclaim
+            );
+            cert.set_basic_ca(Some(true));
+            cert.set_authority_key_identifier(
+                Some(pubkey.key_identifier())
+            );
+            cert.set_crl_uri(Some(uri.clone()));
+            cert.set_ca_issuer(Some(uri.clone()));
+            cert.set_ca_repository(Some(uri.clone()));
+            cert.set_rpki_manifest(Some(uri.clone()));
+            cert.build_v4_resource_blocks(|b| {
+                b.push(Prefix::new(Ipv4Addr::new(10, 0, 0, 0), 8));
+                b.push(Prefix::new(Ipv4Addr::new(192, 168, 0, 0), 16));
+            });
+            cert.build_as_resource_blocks(|b| {
+                b.push((AsId::from(5), AsId::from(20)))
+            });
+            let cert = cert.into_cert(&signer, &key).unwrap().to_captured();
+            Cert::decode(cert.as_slice()).unwrap()
+        };
+
+        let cert = child(Overclaim::Trim).validate_ca(&ta, true).unwrap();
+        assert_eq!(cert.v4_resources().as_v4().to_string(), "10.0.0.0/8");
+        assert_eq!(cert.as_resources().to_string(), "AS5-AS10");
+        let report = cert.overclaim_report().unwrap();
+        assert_eq!(
+            report.v4_resources().as_v4().to_string(), "192.168.0.0/16"
+        );
+        assert!(report.v6_resources().is_empty());
+        assert_eq!(report.as_resources().to_string(), "AS11-AS20");
+        assert_eq!(report.to_string(), "IPv4 192.168.0.0/16; AS11-AS20");
+
+        let err = child(Overclaim::Refuse).validate_ca(
+            &ta, true
+        ).unwrap_err();
+        assert_eq!(err.kind(), ValidationErrorKind::Overclaim);
+        assert_eq!(err.overclaim_report(), Some(report));
+    }
 }
 
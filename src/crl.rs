@@ -4,8 +4,9 @@
 //! revocation lists (CRLs), limiting the values that are allowed in the
 //! various fields.
 //!
-//! This module implements the CRLs themselves via the type [`Crl`] as well
-//! as a [`CrlStore`] that can keep several CRLs which may be helpful during
+//! This module implements the CRLs themselves via the type [`Crl`], CRLs
+//! validated against their issuer via [`ValidatedCrl`], as well as a
+//! [`CrlStore`] that can keep several CRLs which may be helpful during
 //! validation.
 //!
 //! The RPKI CRL profile is defined in RFC 6487 based on the Internet RPIX
@@ -13,6 +14,7 @@
 //!
 //! [`Crl`]: struct.Crl.html
 //! [`CrlStore`]: struct.CrlStore.html
+//! [`ValidatedCrl`]: struct.ValidatedCrl.html
 
 use std::ops;
 use std::collections::HashSet;
@@ -23,11 +25,13 @@ use bcder::encode::PrimitiveContent;
 use bytes::Bytes;
 use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use crate::{oid, uri};
+use crate::cert::ResourceCert;
 use crate::crypto::{
     KeyIdentifier, PublicKey, SignatureAlgorithm, Signer, SigningError
 };
 use crate::x509::{
     Name, RepresentationError, Serial, SignedData, Time, ValidationError,
+    ValidationErrorKind,
     encode_extension, update_once
 };
 
@@ -119,11 +123,84 @@ impl Crl {
         public_key: &PublicKey
     ) -> Result<(), ValidationError> {
         if self.tbs.signature != self.signed_data.signature().algorithm() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignatureAlgorithmMismatch
+            ).with_rfc(5280, "5.1.1.2"))
         }
         self.signed_data.verify_signature(public_key)
     }
 
+    /// Validates the CRL as issued by the given CA certificate at `now`.
+    ///
+    /// In addition to the signature, this checks all the constraints of
+    /// section 5 of RFC 6487 that can be checked on the CRL alone, i.e.,
+    /// the issuer name and authority key identifier must match those of
+    /// `issuer` and the CRL must be current.
+    ///
+    /// Whether the CRL number has increased since the last CRL seen for
+    /// the same issuer cannot be checked here. This happens when adding
+    /// the validated CRL to a [`CrlStore`] via `push_validated`.
+    ///
+    /// [`CrlStore`]: struct.CrlStore.html
+    pub fn validate_at(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time
+    ) -> Result<ValidatedCrl, ValidationError> {
+        // Version: must be v2. This has been checked during parsing.
+
+        // Signature Algorithm: limited to those in RFC 7935. As for
+        // certificates, RFC 5280 demands that the two mentions of the
+        // algorithm are the same.
+        if self.tbs.signature != self.signed_data.signature().algorithm() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignatureAlgorithmMismatch
+            ).with_rfc(5280, "5.1.1.2"))
+        }
+
+        // Issuer: must follow the rules for names in section 4.4 and be
+        // the subject name of the issuing CA.
+        self.tbs.issuer.validate_rpki(strict).map_err(|err| {
+            err.with_rfc(6487, "4.4")
+        })?;
+        if self.tbs.issuer != *issuer.subject() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::IssuerNameMismatch
+            ).with_rfc(5280, "5.1.2.3"))
+        }
+
+        // Authority Key Identifier: must be present, which has been checked
+        // during parsing, and match the issuer’s subject key identifier.
+        if self.tbs.authority_key_id != issuer.subject_key_identifier() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::AuthorityKeyIdentifierMismatch
+            ).with_rfc(6487, "5"))
+        }
+
+        // CRL Number: must be present, which has been checked during
+        // parsing. Any other extensions have been rejected then, too, as
+        // have CRL entry extensions.
+
+        self.signed_data.verify_signature(
+            issuer.subject_public_key_info()
+        )?;
+
+        // This Update and Next Update: the CRL must be current.
+        if self.tbs.this_update > now {
+            return Err(ValidationError::new(
+                ValidationErrorKind::CrlNotYetValid
+            ).with_rfc(5280, "5.1.2.4"))
+        }
+        if self.tbs.next_update < now {
+            return Err(ValidationError::new(
+                ValidationErrorKind::StaleCrl
+            ).with_rfc(5280, "5.1.2.5"))
+        }
+
+        Ok(ValidatedCrl { crl: self })
+    }
+
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
         self.signed_data.encode_ref()
     }
@@ -174,6 +251,52 @@ impl<'de> Deserialize<'de> for Crl {
 }
 
 
+//------------ ValidatedCrl --------------------------------------------------
+
+/// A CRL that has been validated against its issuing CA certificate.
+///
+/// Values of this type are returned by [`Crl::validate_at`]. They can be
+/// added to a [`CrlStore`] which then allows looking them up by their
+/// authority key identifier.
+///
+/// [`Crl::validate_at`]: struct.Crl.html#method.validate_at
+/// [`CrlStore`]: struct.CrlStore.html
+#[derive(Clone, Debug)]
+pub struct ValidatedCrl {
+    /// The validated CRL.
+    crl: Crl,
+}
+
+impl ValidatedCrl {
+    /// Returns a reference to the underlying CRL.
+    pub fn as_crl(&self) -> &Crl {
+        &self.crl
+    }
+
+    /// Converts the value into the underlying CRL.
+    pub fn into_crl(self) -> Crl {
+        self.crl
+    }
+}
+
+
+//--- Deref and AsRef
+
+impl ops::Deref for ValidatedCrl {
+    type Target = Crl;
+
+    fn deref(&self) -> &Crl {
+        &self.crl
+    }
+}
+
+impl AsRef<Crl> for ValidatedCrl {
+    fn as_ref(&self) -> &Crl {
+        &self.crl
+    }
+}
+
+
 //------------ TbsCertList ---------------------------------------------------
 
 /// The payload of a certificate revocation list.
@@ -593,6 +716,16 @@ impl CrlEntry {
         })
     }
 
+    /// Returns the serial number of the revoked certificate.
+    pub fn user_certificate(self) -> Serial {
+        self.user_certificate
+    }
+
+    /// Returns the time of revocation.
+    pub fn revocation_date(self) -> Time {
+        self.revocation_date
+    }
+
     /// Returns a value encoder for the entry.
     pub fn encode(self) -> impl encode::Values {
         encode::sequence((
@@ -666,6 +799,37 @@ impl CrlStore {
         self.crls.push((uri, crl))
     }
 
+    /// Adds a validated CRL to the store.
+    ///
+    /// The CRL is keyed by both its rsync `uri` and its authority key
+    /// identifier. If the store already contains a CRL for the same
+    /// authority key identifier, the new CRL replaces it unless its CRL
+    /// number is lower, in which case an error is returned and the store
+    /// is left unchanged. Any CRL stored under `uri` is replaced, too.
+    pub fn push_validated(
+        &mut self,
+        uri: uri::Rsync,
+        crl: ValidatedCrl
+    ) -> Result<(), ValidationError> {
+        let mut crl = crl.into_crl();
+        let aki = *crl.authority_key_identifier();
+        if let Some(stored) = self.get_by_aki(&aki) {
+            if crl.crl_number() < stored.crl_number() {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::CrlNumberRollback
+                ).with_rfc(5280, "5.2.3"))
+            }
+        }
+        self.crls.retain(|(stored_uri, stored)| {
+            *stored_uri != uri && *stored.authority_key_identifier() != aki
+        });
+        if self.cache_serials {
+            crl.cache_serials()
+        }
+        self.crls.push((uri, crl));
+        Ok(())
+    }
+
     /// Returns a reference to a CRL if it is available in the store.
     pub fn get(&self, uri: &uri::Rsync) -> Option<&Crl> {
         for &(ref stored_uri, ref crl) in &self.crls {
@@ -675,6 +839,19 @@ impl CrlStore {
         }
         None
     }
+
+    /// Returns a reference to the CRL with the given authority key id.
+    ///
+    /// If there are several such CRLs in the store, the most recently
+    /// added one is returned.
+    pub fn get_by_aki(&self, aki: &KeyIdentifier) -> Option<&Crl> {
+        for (_, crl) in self.crls.iter().rev() {
+            if *crl.authority_key_identifier() == *aki {
+                return Some(crl)
+            }
+        }
+        None
+    }
 }
 
 impl Default for CrlStore {
@@ -689,6 +866,24 @@ impl Default for CrlStore {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::cert::Cert;
+    use crate::tal::TalInfo;
+
+    fn issuers(at: Time) -> (ResourceCert, ResourceCert) {
+        let ta = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap().validate_ta_at(
+            TalInfo::from_name("foo".into()).into_arc(), false, at
+        ).unwrap();
+        let ca = Cert::decode(
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        ).unwrap().validate_ca_at(&ta, false, at).unwrap();
+        (ta, ca)
+    }
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
 
     #[test]
     fn decode_certs() {
@@ -713,6 +908,90 @@ mod test {
             deser_crl.to_captured().into_bytes()
         );
     }
+
+    #[test]
+    fn validate_at() {
+        let at = Time::utc(2019, 4, 6, 12, 0, 0);
+        let (ta, ca) = issuers(at);
+        let ta_crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref()
+        ).unwrap();
+        let ca_crl = Crl::decode(
+            include_bytes!("../test-data/ca1.crl").as_ref()
+        ).unwrap();
+
+        ta_crl.clone().validate_at(&ta, true, at).unwrap();
+        ca_crl.clone().validate_at(&ca, true, at).unwrap();
+        assert_eq!(
+            ca_crl.clone().validate_at(&ta, true, at).unwrap_err().kind(),
+            ValidationErrorKind::IssuerNameMismatch
+        );
+        assert_eq!(
+            ca_crl.clone().validate_at(
+                &ca, true, Time::utc(2019, 4, 8, 0, 0, 0)
+            ).unwrap_err().kind(),
+            ValidationErrorKind::StaleCrl
+        );
+        assert_eq!(
+            ca_crl.validate_at(
+                &ca, true, Time::utc(2019, 4, 6, 0, 0, 0)
+            ).unwrap_err().kind(),
+            ValidationErrorKind::CrlNotYetValid
+        );
+    }
+
+    #[test]
+    fn store_by_aki() {
+        let at = Time::utc(2019, 4, 6, 12, 0, 0);
+        let (ta, ca) = issuers(at);
+        let ta_crl = Crl::decode(
+            include_bytes!("../test-data/ta.crl").as_ref()
+        ).unwrap().validate_at(&ta, false, at).unwrap();
+        let ca_crl = Crl::decode(
+            include_bytes!("../test-data/ca1.crl").as_ref()
+        ).unwrap().validate_at(&ca, false, at).unwrap();
+
+        let mut store = CrlStore::new();
+        store.push_validated(
+            rsync("rsync://example.com/ta/ta.crl"), ta_crl.clone()
+        ).unwrap();
+        store.push_validated(
+            rsync("rsync://example.com/ca/ca.crl"), ca_crl.clone()
+        ).unwrap();
+        assert_eq!(
+            store.get_by_aki(&ta.subject_key_identifier()).unwrap()
+                .crl_number(),
+            ta_crl.crl_number()
+        );
+        assert_eq!(
+            store.get_by_aki(&ca.subject_key_identifier()).unwrap()
+                .crl_number(),
+            ca_crl.crl_number()
+        );
+        assert!(
+            store.get(&rsync("rsync://example.com/ca/ca.crl")).is_some()
+        );
+
+        // A CRL with a lower number for the same key is rejected.
+        let mut older = ca_crl.clone().into_crl();
+        older.tbs.set_crl_number(1u64.into());
+        assert_eq!(
+            store.push_validated(
+                rsync("rsync://example.com/ca/other.crl"),
+                ValidatedCrl { crl: older }
+            ).unwrap_err().kind(),
+            ValidationErrorKind::CrlNumberRollback
+        );
+        assert!(
+            store.get(&rsync("rsync://example.com/ca/other.crl")).is_none()
+        );
+
+        // The same CRL again replaces the old one.
+        store.push_validated(
+            rsync("rsync://example.com/ca/ca.crl"), ca_crl
+        ).unwrap();
+        assert_eq!(store.crls.len(), 2);
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
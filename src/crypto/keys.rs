@@ -4,7 +4,7 @@ use std::{error, fmt, io, str};
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 use bcder::{decode, encode};
-use bcder::{BitString, Mode, OctetString, Tag};
+use bcder::{BitString, Mode, OctetString, Oid, Tag};
 use bcder::encode::{PrimitiveContent, Values};
 use bytes::Bytes;
 use ring::{digest, signature};
@@ -23,12 +23,60 @@ use ring::signature::VerificationAlgorithm;
 
 /// The formats of public keys used by RPKI.
 ///
-/// Currently, RPKI uses exactly one type of public keys, RSA keys with a size
-/// of 2048 bits. However, as that might change in the future, we are not
-/// hard-coding that format but rather use this type – which for the time
-/// being is zero-sized.
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
-pub struct PublicKeyFormat(());
+/// Certificates and signed objects of the RPKI itself currently use exactly
+/// one type of public keys, RSA keys with a size of 2048 bits. BGPsec router
+/// certificates as defined in [RFC 8209] instead use ECDSA keys on the P-256
+/// curve, so both formats need to be supported.
+///
+/// [RFC 8209]: https://tools.ietf.org/html/rfc8209
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PublicKeyFormat {
+    /// An RSA public key.
+    ///
+    /// This is the default format.
+    Rsa,
+
+    /// An ECDSA public key for the P-256 elliptic curve.
+    EcdsaP256,
+}
+
+impl PublicKeyFormat {
+    /// Returns whether the format is allowed for RPKI certificates.
+    ///
+    /// This is true for RSA keys only as required by section 3 of
+    /// [RFC 7935].
+    ///
+    /// [RFC 7935]: https://tools.ietf.org/html/rfc7935
+    pub fn allow_rpki_cert(self) -> bool {
+        match self {
+            PublicKeyFormat::Rsa => true,
+            PublicKeyFormat::EcdsaP256 => false,
+        }
+    }
+
+    /// Returns whether the format is allowed for BGPsec router certificates.
+    ///
+    /// This is true for ECDSA P-256 keys only as required by section 3.1 of
+    /// [RFC 8208].
+    ///
+    /// [RFC 8208]: https://tools.ietf.org/html/rfc8208
+    pub fn allow_router_cert(self) -> bool {
+        match self {
+            PublicKeyFormat::Rsa => false,
+            PublicKeyFormat::EcdsaP256 => true,
+        }
+    }
+}
+
+
+//--- Default
+
+impl Default for PublicKeyFormat {
+    fn default() -> Self {
+        PublicKeyFormat::Rsa
+    }
+}
+
 
 /// # ASN.1 Algorithm Identifiers
 ///
@@ -41,14 +89,19 @@ pub struct PublicKeyFormat(());
 ///      parameters         ANY DEFINED BY algorithm OPTIONAL }
 /// ```
 ///
-/// Right now, the object identifier needs to be that of `rsaEncryption`
+/// For RSA keys, the object identifier needs to be that of `rsaEncryption`
 /// defined by [RFC 4055] and the parameters must be present and NULL.
 /// Then parsing, we generously also allow it to be absent altogether.
 ///
+/// For ECDSA keys, the object identifier needs to be `id-ecPublicKey`
+/// defined by [RFC 5480] and the parameters must be the `namedCurve`
+/// object identifier `secp256r1`.
+///
 /// The functions and methods in this section allow decoding and encoding of
 /// these identifiers.
 ///
 /// [RFC 4055]: https://tools.ietf.org/html/rfc4055
+/// [RFC 5480]: https://tools.ietf.org/html/rfc5480
 impl PublicKeyFormat{
     /// Takes and returns a algorithm identifier.
     ///
@@ -64,17 +117,40 @@ impl PublicKeyFormat{
     fn from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
-        oid::RSA_ENCRYPTION.skip_if(cons)?;
-        cons.take_opt_null()?;
-        Ok(PublicKeyFormat::default())
+        let alg = Oid::take_from(cons)?;
+        if alg == oid::RSA_ENCRYPTION {
+            cons.take_opt_null()?;
+            Ok(PublicKeyFormat::Rsa)
+        }
+        else if alg == oid::EC_PUBLIC_KEY {
+            oid::SECP256R1.skip_if(cons)?;
+            Ok(PublicKeyFormat::EcdsaP256)
+        }
+        else {
+            Err(decode::Malformed.into())
+        }
     }
 
     /// Provides an encoder for the algorihm identifier.
     pub fn encode(self) -> impl encode::Values {
-        encode::sequence((
-            oid::RSA_ENCRYPTION.encode(),
-            ().encode(),
-        ))
+        match self {
+            PublicKeyFormat::Rsa => {
+                encode::Choice2::One(
+                    encode::sequence((
+                        oid::RSA_ENCRYPTION.encode(),
+                        ().encode(),
+                    ))
+                )
+            }
+            PublicKeyFormat::EcdsaP256 => {
+                encode::Choice2::Two(
+                    encode::sequence((
+                        oid::EC_PUBLIC_KEY.encode(),
+                        oid::SECP256R1.encode(),
+                    ))
+                )
+            }
+        }
     }
 }
 
@@ -108,10 +184,20 @@ impl PublicKey {
     }
 
     /// Verifies a signature using this public key.
+    ///
+    /// Verification fails if the signature’s algorithm doesn’t fit the
+    /// format of the key.
     pub fn verify(
         &self, message: &[u8], signature: &Signature
     ) -> Result<(), VerificationError> {
-        signature::RSA_PKCS1_2048_8192_SHA256.verify(
+        if signature.algorithm().public_key_format() != self.algorithm {
+            return Err(VerificationError)
+        }
+        let alg: &dyn VerificationAlgorithm = match self.algorithm {
+            PublicKeyFormat::Rsa => &signature::RSA_PKCS1_2048_8192_SHA256,
+            PublicKeyFormat::EcdsaP256 => &signature::ECDSA_P256_SHA256_ASN1,
+        };
+        alg.verify(
             Input::from(self.bits()),
             Input::from(message),
             Input::from(signature.value().as_ref())
@@ -14,19 +14,47 @@ use super::keys::PublicKeyFormat;
 ///
 /// These are the algorithms used for creating and verifying signatures. For
 /// RPKI, [RFC 7935] allows only one algorithm, RSA PKCS #1 v1.5 with
-/// SHA-256. Because of that, this type is currently a zero-sized struct.
-/// Should additional algorithms ever be allowed, it will change into an
-/// enum.
+/// SHA-256. BGPsec router keys, however, use ECDSA with the P-256 curve and
+/// SHA-256 as required by [RFC 8608], so this algorithm is supported, too.
 ///
 /// [RFC 7935]: https://tools.ietf.org/html/rfc7935
-#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
-pub struct SignatureAlgorithm(());
+/// [RFC 8608]: https://tools.ietf.org/html/rfc8608
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SignatureAlgorithm {
+    /// RSA PKCS #1 v1.5 with SHA-256.
+    ///
+    /// This is the default algorithm.
+    RsaSha256,
+
+    /// ECDSA using the P-256 curve with SHA-256.
+    EcdsaP256Sha256,
+}
 
 
 impl SignatureAlgorithm {
     /// Returns the preferred public key format for this algorithm.
     pub fn public_key_format(self) -> PublicKeyFormat {
-        PublicKeyFormat::default()
+        match self {
+            SignatureAlgorithm::RsaSha256 => PublicKeyFormat::Rsa,
+            SignatureAlgorithm::EcdsaP256Sha256 => PublicKeyFormat::EcdsaP256,
+        }
+    }
+
+    /// Returns the signature algorithm to use with a public key format.
+    pub fn for_key_format(format: PublicKeyFormat) -> Self {
+        match format {
+            PublicKeyFormat::Rsa => SignatureAlgorithm::RsaSha256,
+            PublicKeyFormat::EcdsaP256 => SignatureAlgorithm::EcdsaP256Sha256,
+        }
+    }
+}
+
+
+//--- Default
+
+impl Default for SignatureAlgorithm {
+    fn default() -> Self {
+        SignatureAlgorithm::RsaSha256
     }
 }
 
@@ -44,12 +72,12 @@ impl SignatureAlgorithm {
 ///      parameters                  ANY DEFINED BY algorithm OPTIONAL }
 /// ```
 ///
-/// Currently, [RFC 7935] allows only one algorithm, but sadly it uses
-/// different identifiers in different places. For X.509-related objects,
-/// i.e., certificates, CRLs, and certification requests, this is
-/// `sha256WithRSAEncryption` from [RFC 4055].  For signed objects, the
-/// identifier must be `rsaEncryption` from [RFC 3370] for constructed
-/// objects while both must be accepted when reading objects.
+/// For RSA, [RFC 7935] sadly uses different identifiers in different
+/// places. For X.509-related objects, i.e., certificates, CRLs, and
+/// certification requests, this is `sha256WithRSAEncryption` from
+/// [RFC 4055].  For signed objects, the identifier must be `rsaEncryption`
+/// from [RFC 3370] for constructed objects while both must be accepted when
+/// reading objects.
 ///
 /// Because of these differences, you’ll find two sets of functions and
 /// methods in this section. Those prefixed with `x509` deal with the
@@ -60,8 +88,12 @@ impl SignatureAlgorithm {
 /// absent field for the latter as well. When constructing identifiers,
 /// we will always include a parameters field and set it to NULL.
 ///
+/// For ECDSA, both places use `ecdsa-with-SHA256` from [RFC 5758] and the
+/// parameters field must be absent.
+///
 /// [RFC 3370]: https://tools.ietf.org/html/rfc3370
 /// [RFC 4055]: https://tools.ietf.org/html/rfc4055
+/// [RFC 5758]: https://tools.ietf.org/html/rfc5758
 /// [RFC 7935]: https://tools.ietf.org/html/rfc7935
 impl SignatureAlgorithm {
     /// Takes a signature algorithm identifier for X.509 objects.
@@ -78,9 +110,17 @@ impl SignatureAlgorithm {
     fn x509_from_constructed<S: decode::Source>(
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
-        oid::SHA256_WITH_RSA_ENCRYPTION.skip_if(cons)?;
-        cons.take_opt_null()?;
-        Ok(SignatureAlgorithm::default())
+        let oid = Oid::take_from(cons)?;
+        if oid == oid::SHA256_WITH_RSA_ENCRYPTION {
+            cons.take_opt_null()?;
+            Ok(SignatureAlgorithm::RsaSha256)
+        }
+        else if oid == oid::ECDSA_WITH_SHA256 {
+            Ok(SignatureAlgorithm::EcdsaP256Sha256)
+        }
+        else {
+            Err(decode::Malformed.into())
+        }
     }
 
     /// Takes a signature algorithm identifier for CMS objects.
@@ -98,28 +138,51 @@ impl SignatureAlgorithm {
         cons: &mut decode::Constructed<S>
     ) -> Result<Self, S::Err> {
         let oid = Oid::take_from(cons)?;
-        if oid != oid::RSA_ENCRYPTION && oid != oid::SHA256_WITH_RSA_ENCRYPTION
+        if oid == oid::RSA_ENCRYPTION || oid == oid::SHA256_WITH_RSA_ENCRYPTION
         {
-            return Err(decode::Malformed.into())
+            cons.take_opt_null()?;
+            Ok(SignatureAlgorithm::RsaSha256)
+        }
+        else if oid == oid::ECDSA_WITH_SHA256 {
+            Ok(SignatureAlgorithm::EcdsaP256Sha256)
+        }
+        else {
+            Err(decode::Malformed.into())
         }
-        cons.take_opt_null()?;
-        Ok(SignatureAlgorithm::default())
     }
 
     /// Provides an encoder for X.509 objects.
     pub fn x509_encode(self) -> impl encode::Values {
-        encode::sequence((
-            oid::SHA256_WITH_RSA_ENCRYPTION.encode(),
-            ().encode(),
-        ))
+        match self {
+            SignatureAlgorithm::RsaSha256 => {
+                encode::Choice2::One(encode::sequence((
+                    oid::SHA256_WITH_RSA_ENCRYPTION.encode(),
+                    ().encode(),
+                )))
+            }
+            SignatureAlgorithm::EcdsaP256Sha256 => {
+                encode::Choice2::Two(encode::sequence(
+                    oid::ECDSA_WITH_SHA256.encode()
+                ))
+            }
+        }
     }
 
     /// Provides an encoder for CMS objects.
     pub fn cms_encode(self) -> impl encode::Values {
-        encode::sequence((
-            oid::RSA_ENCRYPTION.encode(),
-            ().encode(),
-        ))
+        match self {
+            SignatureAlgorithm::RsaSha256 => {
+                encode::Choice2::One(encode::sequence((
+                    oid::RSA_ENCRYPTION.encode(),
+                    ().encode(),
+                )))
+            }
+            SignatureAlgorithm::EcdsaP256Sha256 => {
+                encode::Choice2::Two(encode::sequence(
+                    oid::ECDSA_WITH_SHA256.encode()
+                ))
+            }
+        }
     }
 }
 
@@ -6,8 +6,10 @@
 //! Publication Server. In particular, this is not required when validating.
 
 use std::io;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
 use openssl::rsa::Rsa;
-use openssl::pkey::{PKey, Private};
+use openssl::pkey::{Id, PKey, Private};
 use openssl::hash::MessageDigest;
 use ring::rand;
 use ring::rand::SecureRandom;
@@ -133,57 +135,86 @@ pub struct KeyId(usize);
 struct KeyPair(PKey<Private>);
 
 impl KeyPair {
-    fn new(_algorithm: PublicKeyFormat) -> Result<Self, io::Error> {
+    fn new(algorithm: PublicKeyFormat) -> Result<Self, io::Error> {
         // Issues unwrapping this indicate a bug in the openssl library.
         // So, there is no way to recover.
-        let rsa = Rsa::generate(2048)?;
-        let pkey = PKey::from_rsa(rsa)?;
+        let pkey = match algorithm {
+            PublicKeyFormat::Rsa => PKey::from_rsa(Rsa::generate(2048)?)?,
+            PublicKeyFormat::EcdsaP256 => {
+                let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+                PKey::from_ec_key(EcKey::generate(&group)?)?
+            }
+        };
         Ok(KeyPair(pkey))
     }
 
     fn from_der(der: &[u8]) -> Result<Self, io::Error> {
-        let res = PKey::private_key_from_der(der)?;
-        if res.bits() != 2048 {
+        Self::check(PKey::private_key_from_der(der)?)
+    }
+
+    fn from_pem(pem: &[u8]) -> Result<Self, io::Error> {
+        Self::check(PKey::private_key_from_pem(pem)?)
+    }
+
+    /// Checks that a private key is of one of the supported formats.
+    ///
+    /// These are RSA keys with 2048 bits and ECDSA keys on the P-256 curve.
+    fn check(key: PKey<Private>) -> Result<Self, io::Error> {
+        let res = KeyPair(key);
+        if res.format()? == PublicKeyFormat::Rsa && res.0.bits() != 2048 {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
-                format!("invalid key length {}", res.bits())
+                format!("invalid key length {}", res.0.bits())
             ))
         }
-        Ok(KeyPair(res))
+        Ok(res)
     }
 
-    fn from_pem(pem: &[u8]) -> Result<Self, io::Error> {
-        let res = PKey::private_key_from_pem(pem)?;
-        if res.bits() != 2048 {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                format!("invalid key length {}", res.bits())
+    /// Returns the public key format of the key pair.
+    fn format(&self) -> Result<PublicKeyFormat, io::Error> {
+        match self.0.id() {
+            Id::RSA => Ok(PublicKeyFormat::Rsa),
+            Id::EC => {
+                let nid = self.0.ec_key()?.group().curve_name();
+                if nid == Some(Nid::X9_62_PRIME256V1) {
+                    Ok(PublicKeyFormat::EcdsaP256)
+                }
+                else {
+                    Err(io::Error::new(
+                        io::ErrorKind::Other, "unsupported elliptic curve"
+                    ))
+                }
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::Other, "unsupported key type"
             ))
         }
-        Ok(KeyPair(res))
     }
 
     fn get_key_info(&self) -> Result<PublicKey, io::Error>
     {
         // Issues unwrapping this indicate a bug in the openssl
         // library. So, there is no way to recover.
-        let der = self.0.rsa().unwrap().public_key_to_der()?;
+        let der = self.0.public_key_to_der()?;
         Ok(PublicKey::decode(der.as_ref()).unwrap())
     }
 
     fn sign(
         &self,
-        _algorithm: SignatureAlgorithm,
+        algorithm: SignatureAlgorithm,
         data: &[u8]
     ) -> Result<Signature, io::Error> {
+        if algorithm.public_key_format() != self.format()? {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "signature algorithm doesn’t match key format"
+            ))
+        }
         let mut signer = ::openssl::sign::Signer::new(
             MessageDigest::sha256(), &self.0
         )?;
         signer.update(data)?;
-        Ok(Signature::new(
-            SignatureAlgorithm::default(),
-            signer.sign_to_vec()?.into()
-        ))
+        Ok(Signature::new(algorithm, signer.sign_to_vec()?.into()))
     }
 }
 
@@ -210,5 +241,29 @@ pub mod tests {
         let s = OpenSslSigner::new();
         s.sign_one_off(SignatureAlgorithm::default(), b"foobar").unwrap();
     }
+
+    #[test]
+    fn ecdsa_sign_verify() {
+        let mut s = OpenSslSigner::new();
+        let ki = s.create_key(PublicKeyFormat::EcdsaP256).unwrap();
+        let data = b"foobar";
+        let info = s.get_key_info(&ki).unwrap();
+        assert_eq!(*info.algorithm(), PublicKeyFormat::EcdsaP256);
+        assert!(s.sign(&ki, SignatureAlgorithm::RsaSha256, data).is_err());
+        let sig = s.sign(
+            &ki, SignatureAlgorithm::EcdsaP256Sha256, data
+        ).unwrap();
+        info.verify(data, &sig).unwrap();
+        assert!(info.verify(b"barfoo", &sig).is_err());
+
+        let (sig, info) = s.sign_one_off(
+            SignatureAlgorithm::EcdsaP256Sha256, data
+        ).unwrap();
+        info.verify(data, &sig).unwrap();
+
+        let rsa = s.create_key(PublicKeyFormat::Rsa).unwrap();
+        let sig = s.sign(&rsa, SignatureAlgorithm::RsaSha256, data).unwrap();
+        assert!(info.verify(data, &sig).is_err());
+    }
 }
 
@@ -0,0 +1,797 @@
+//! Ghostbusters Records.
+//!
+//! For details, see RFC 6493.
+
+use std::{error, fmt, str};
+use bcder::{decode, encode};
+use bcder::{Captured, Mode, Oid};
+use bcder::encode::Values;
+use bytes::Bytes;
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use crate::oid;
+use crate::cert::{Cert, ResourceCert};
+use crate::crypto::{Signer, SigningError};
+use crate::sigobj::{SignedObject, SignedObjectBuilder};
+use crate::x509::{Time, ValidationError, ValidationErrorKind};
+
+
+//------------ Gbr -----------------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct Gbr {
+    signed: SignedObject,
+    content: GhostbustersRecord,
+}
+
+impl Gbr {
+    pub fn decode<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        let signed = SignedObject::decode(source, strict)?;
+        if signed.content_type().ne(&oid::CT_RPKI_GHOSTBUSTERS) {
+            return Err(decode::Malformed.into())
+        }
+        let content = GhostbustersRecord::from_vcard(
+            signed.content().to_bytes()
+        ).map_err(|_| decode::Malformed)?;
+        Ok(Gbr { signed, content })
+    }
+
+    pub fn process<F>(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        check_crl: F
+    ) -> Result<(ResourceCert, GhostbustersRecord), ValidationError>
+    where F: FnOnce(&Cert) -> Result<(), ValidationError> {
+        self.process_at(issuer, strict, Time::now(), check_crl)
+    }
+
+    pub fn process_at<F>(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        now: Time,
+        check_crl: F
+    ) -> Result<(ResourceCert, GhostbustersRecord), ValidationError>
+    where F: FnOnce(&Cert) -> Result<(), ValidationError> {
+        let cert = self.signed.validate_at(issuer, strict, now)?;
+        check_crl(cert.as_ref())?;
+        Self::validate_cert(cert.as_ref())?;
+        Ok((cert, self.content))
+    }
+
+    /// Checks that the EE certificate has only inherited resources.
+    fn validate_cert(cert: &Cert) -> Result<(), ValidationError> {
+        let inherited = {
+            cert.v4_resources().map(|res| res.is_inherited()).unwrap_or(true)
+            && cert.v6_resources().map(|res| res.is_inherited())
+                .unwrap_or(true)
+            && cert.as_resources().map(|res| res.is_inherited())
+                .unwrap_or(true)
+        };
+        if inherited {
+            Ok(())
+        }
+        else {
+            Err(ValidationError::new(
+                ValidationErrorKind::UnexpectedResources
+            ))
+        }
+    }
+
+    /// Returns a value encoder for a reference to a Ghostbusters record.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        self.signed.encode_ref()
+    }
+
+    /// Returns a DER encoded Captured for this.
+    pub fn to_captured(&self) -> Captured {
+        self.encode_ref().to_captured(Mode::Der)
+    }
+
+    /// Returns a reference to the EE certificate of this record.
+    pub fn cert(&self) -> &Cert {
+        self.signed.cert()
+    }
+
+    /// Returns a reference to the vCard content.
+    pub fn content(&self) -> &GhostbustersRecord {
+        &self.content
+    }
+}
+
+
+//--- Deserialize and Serialize
+
+impl Serialize for Gbr {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S
+    ) -> Result<S::Ok, S::Error> {
+        let bytes = self.to_captured().into_bytes();
+        let b64 = base64::encode(&bytes);
+        b64.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Gbr {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D
+    ) -> Result<Self, D::Error> {
+        use serde::de;
+
+        let string = String::deserialize(deserializer)?;
+        let decoded = base64::decode(&string).map_err(de::Error::custom)?;
+        let bytes = Bytes::from(decoded);
+        Gbr::decode(bytes, true).map_err(de::Error::custom)
+    }
+}
+
+
+//------------ GhostbustersRecord --------------------------------------------
+
+/// The content of a Ghostbusters record.
+///
+/// This is a vCard restricted to the profile defined in RFC 6493. The
+/// values of the properties are provided as they appear in the vCard, i.e.,
+/// with any vCard escaping and structure left intact.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GhostbustersRecord {
+    /// The raw vCard.
+    vcard: Bytes,
+
+    /// The value of the FN property.
+    full_name: String,
+
+    /// The value of the ORG property if present.
+    org: Option<String>,
+
+    /// The values of all ADR properties.
+    addresses: Vec<String>,
+
+    /// The values of all TEL properties.
+    phones: Vec<String>,
+
+    /// The values of all EMAIL properties.
+    emails: Vec<String>,
+}
+
+impl GhostbustersRecord {
+    /// Parses a vCard into a Ghostbusters record.
+    ///
+    /// The vCard has to be UTF-8 encoded and has to follow the profile of
+    /// RFC 6493: it must start with `BEGIN:VCARD` followed by `VERSION:4.0`,
+    /// end with `END:VCARD`, contain exactly one FN property, at most one
+    /// ORG property, and at least one ADR, TEL, or EMAIL property. No other
+    /// properties are allowed.
+    pub fn from_vcard(vcard: Bytes) -> Result<Self, VCardError> {
+        let text = str::from_utf8(vcard.as_ref())
+            .map_err(|_| VCardError::InvalidEncoding)?;
+        let lines = unfold(text)?;
+        let mut lines = lines.iter().map(|line| split_line(line));
+
+        match lines.next() {
+            Some(Ok(("BEGIN", value))) if value.eq_ignore_ascii_case("VCARD")
+                => { }
+            Some(Err(err)) => return Err(err),
+            _ => return Err(VCardError::MissingProperty("BEGIN"))
+        }
+        match lines.next() {
+            Some(Ok(("VERSION", "4.0"))) => { }
+            Some(Err(err)) => return Err(err),
+            _ => return Err(VCardError::MissingProperty("VERSION"))
+        }
+
+        let mut full_name = None;
+        let mut org = None;
+        let mut addresses = Vec::new();
+        let mut phones = Vec::new();
+        let mut emails = Vec::new();
+        let mut end = false;
+        for line in lines {
+            let (name, value) = line?;
+            if end {
+                return Err(VCardError::InvalidLine)
+            }
+            match name {
+                "FN" => {
+                    if full_name.is_some() {
+                        return Err(VCardError::DuplicateProperty("FN"))
+                    }
+                    full_name = Some(value.into())
+                }
+                "ORG" => {
+                    if org.is_some() {
+                        return Err(VCardError::DuplicateProperty("ORG"))
+                    }
+                    org = Some(value.into())
+                }
+                "ADR" => addresses.push(value.into()),
+                "TEL" => phones.push(value.into()),
+                "EMAIL" => emails.push(value.into()),
+                "END" if value.eq_ignore_ascii_case("VCARD") => end = true,
+                _ => return Err(VCardError::UnexpectedProperty)
+            }
+        }
+        if !end {
+            return Err(VCardError::MissingProperty("END"))
+        }
+        let full_name = match full_name {
+            Some(full_name) => full_name,
+            None => return Err(VCardError::MissingProperty("FN"))
+        };
+        if addresses.is_empty() && phones.is_empty() && emails.is_empty() {
+            return Err(VCardError::MissingProperty("ADR, TEL, or EMAIL"))
+        }
+        Ok(GhostbustersRecord {
+            vcard, full_name, org, addresses, phones, emails
+        })
+    }
+
+    /// Returns the raw vCard.
+    pub fn vcard(&self) -> &Bytes {
+        &self.vcard
+    }
+
+    /// Returns the formatted name of the contact.
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    /// Returns the organization of the contact if present.
+    pub fn org(&self) -> Option<&str> {
+        self.org.as_deref()
+    }
+
+    /// Returns the postal addresses of the contact.
+    pub fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    /// Returns the telephone numbers of the contact.
+    pub fn phones(&self) -> &[String] {
+        &self.phones
+    }
+
+    /// Returns the email addresses of the contact.
+    pub fn emails(&self) -> &[String] {
+        &self.emails
+    }
+}
+
+
+//------------ GbrBuilder ----------------------------------------------------
+
+#[derive(Clone, Debug)]
+pub struct GbrBuilder {
+    full_name: String,
+    org: Option<String>,
+    addresses: Vec<String>,
+    phones: Vec<String>,
+    emails: Vec<String>,
+}
+
+impl GbrBuilder {
+    pub fn new(full_name: String) -> Self {
+        GbrBuilder {
+            full_name,
+            org: None,
+            addresses: Vec::new(),
+            phones: Vec::new(),
+            emails: Vec::new(),
+        }
+    }
+
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    pub fn set_full_name(&mut self, full_name: String) {
+        self.full_name = full_name
+    }
+
+    pub fn org(&self) -> Option<&str> {
+        self.org.as_deref()
+    }
+
+    pub fn set_org(&mut self, org: Option<String>) {
+        self.org = org
+    }
+
+    pub fn push_address(&mut self, address: String) {
+        self.addresses.push(address)
+    }
+
+    pub fn push_phone(&mut self, phone: String) {
+        self.phones.push(phone)
+    }
+
+    pub fn push_email(&mut self, email: String) {
+        self.emails.push(email)
+    }
+
+    /// Returns the encoded vCard.
+    ///
+    /// The values are used as given and thus need to be escaped according
+    /// to the vCard rules already. Long lines are folded.
+    pub fn to_vcard(&self) -> Bytes {
+        let mut res = String::new();
+        push_line(&mut res, "BEGIN:VCARD");
+        push_line(&mut res, "VERSION:4.0");
+        push_line(&mut res, &format!("FN:{}", self.full_name));
+        if let Some(ref org) = self.org {
+            push_line(&mut res, &format!("ORG:{}", org));
+        }
+        for item in &self.addresses {
+            push_line(&mut res, &format!("ADR:{}", item));
+        }
+        for item in &self.phones {
+            push_line(&mut res, &format!("TEL:{}", item));
+        }
+        for item in &self.emails {
+            push_line(&mut res, &format!("EMAIL:{}", item));
+        }
+        push_line(&mut res, "END:VCARD");
+        res.into()
+    }
+
+    /// Creates the content of the Ghostbusters record.
+    ///
+    /// Fails if some value contains a line break, as that would allow
+    /// adding arbitrary properties, or if the values don’t result in a
+    /// vCard that follows the profile, e.g., because there is no contact
+    /// information.
+    pub fn to_record(&self) -> Result<GhostbustersRecord, VCardError> {
+        let has_line_break = Some(&self.full_name).into_iter()
+            .chain(self.org.as_ref())
+            .chain(self.addresses.iter())
+            .chain(self.phones.iter())
+            .chain(self.emails.iter())
+            .any(|value| value.contains(|ch| ch == '\r' || ch == '\n'));
+        if has_line_break {
+            return Err(VCardError::LineBreak)
+        }
+        GhostbustersRecord::from_vcard(self.to_vcard())
+    }
+
+    /// Finalizes the builder into a Ghostbusters record.
+    ///
+    /// The EE certificate will use inherited resources only.
+    ///
+    /// Fails if the values don’t result in a vCard that follows the
+    /// profile. You can check this beforehand via [`to_record`].
+    ///
+    /// [`to_record`]: #method.to_record
+    pub fn finalize<S: Signer>(
+        self,
+        mut sigobj: SignedObjectBuilder,
+        signer: &S,
+        issuer_key: &S::KeyId,
+    ) -> Result<Gbr, BuildError<S::Error>> {
+        let content = self.to_record()?;
+        sigobj.set_v4_resources_inherit();
+        sigobj.set_v6_resources_inherit();
+        sigobj.set_as_resources_inherit();
+        let signed = sigobj.finalize(
+            Oid(oid::CT_RPKI_GHOSTBUSTERS.0.into()),
+            content.vcard.clone(),
+            signer,
+            issuer_key,
+        )?;
+        Ok(Gbr { signed, content })
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// The maximum length of a vCard line in octets before it is folded.
+const MAX_LINE_LEN: usize = 75;
+
+/// Splits a vCard into its unfolded content lines.
+///
+/// Lines are accepted with either CRLF or bare LF line endings. A line
+/// starting with a space or tab continues the previous line. Empty lines
+/// are dropped.
+fn unfold(text: &str) -> Result<Vec<String>, VCardError> {
+    let mut res: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = if line.ends_with('\r') {
+            &line[..line.len() - 1]
+        }
+        else {
+            line
+        };
+        if line.starts_with(' ') || line.starts_with('\t') {
+            match res.last_mut() {
+                Some(last) => last.push_str(&line[1..]),
+                None => return Err(VCardError::InvalidLine)
+            }
+        }
+        else if !line.is_empty() {
+            res.push(line.into())
+        }
+    }
+    Ok(res)
+}
+
+/// Splits a content line into the upper-cased name and the value.
+///
+/// Parameters are dropped. Since they may contain colons in quoted
+/// strings, quotes are considered when looking for the start of the value.
+fn split_line(line: &str) -> Result<(&'static str, &str), VCardError> {
+    let mut quoted = false;
+    let mut colon = None;
+    for (pos, ch) in line.char_indices() {
+        match ch {
+            '"' => quoted = !quoted,
+            ':' if !quoted => {
+                colon = Some(pos);
+                break
+            }
+            _ => { }
+        }
+    }
+    let colon = colon.ok_or(VCardError::InvalidLine)?;
+    let name = match line[..colon].find(';') {
+        Some(pos) => &line[..pos],
+        None => &line[..colon]
+    };
+    let name = [
+        "BEGIN", "VERSION", "FN", "ORG", "ADR", "TEL", "EMAIL", "END"
+    ].iter().find(|item| item.eq_ignore_ascii_case(name));
+    match name {
+        Some(name) => Ok((name, &line[colon + 1..])),
+        None => Err(VCardError::UnexpectedProperty)
+    }
+}
+
+/// Appends a content line to a vCard, folding it if necessary.
+fn push_line(target: &mut String, mut line: &str) {
+    let mut limit = MAX_LINE_LEN;
+    while line.len() > limit {
+        let mut pos = limit;
+        while !line.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        target.push_str(&line[..pos]);
+        target.push_str("\r\n ");
+        line = &line[pos..];
+        // Continuation lines start with the extra space.
+        limit = MAX_LINE_LEN - 1;
+    }
+    target.push_str(line);
+    target.push_str("\r\n");
+}
+
+
+//------------ VCardError ----------------------------------------------------
+
+/// A vCard does not follow the profile of a Ghostbusters record.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VCardError {
+    /// The vCard is not encoded in UTF-8.
+    InvalidEncoding,
+
+    /// A content line is malformed or follows the END property.
+    InvalidLine,
+
+    /// A property not allowed in a Ghostbusters record is present.
+    UnexpectedProperty,
+
+    /// A required property is missing or not in the right place.
+    MissingProperty(&'static str),
+
+    /// A property allowed only once appears multiple times.
+    DuplicateProperty(&'static str),
+
+    /// A value given to the builder contains a line break.
+    LineBreak,
+}
+
+impl fmt::Display for VCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VCardError::InvalidEncoding => f.write_str("invalid encoding"),
+            VCardError::InvalidLine => f.write_str("invalid content line"),
+            VCardError::UnexpectedProperty => {
+                f.write_str("unexpected property")
+            }
+            VCardError::MissingProperty(name) => {
+                write!(f, "missing property {}", name)
+            }
+            VCardError::DuplicateProperty(name) => {
+                write!(f, "duplicate property {}", name)
+            }
+            VCardError::LineBreak => f.write_str("line break in value"),
+        }
+    }
+}
+
+impl error::Error for VCardError { }
+
+
+//------------ BuildError ----------------------------------------------------
+
+/// An error happened while building a Ghostbusters record.
+#[derive(Clone, Debug)]
+pub enum BuildError<S> {
+    /// The values don’t result in a vCard that follows the profile.
+    VCard(VCardError),
+
+    /// Signing the record failed.
+    Signing(SigningError<S>),
+}
+
+impl<S> From<VCardError> for BuildError<S> {
+    fn from(err: VCardError) -> Self {
+        BuildError::VCard(err)
+    }
+}
+
+impl<S> From<SigningError<S>> for BuildError<S> {
+    fn from(err: SigningError<S>) -> Self {
+        BuildError::Signing(err)
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for BuildError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::VCard(err) => err.fmt(f),
+            BuildError::Signing(ref err) => err.fmt(f),
+        }
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The example from section 7 of RFC 6493.
+    const EXAMPLE: &str = "BEGIN:VCARD\r\n\
+        VERSION:4.0\r\n\
+        FN:Human Being\r\n\
+        ORG:Example Organization\r\n\
+        ADR;TYPE=WORK:;;42 Twisty Passage;Deep Cavern;WA;98666;U.S.A.\r\n\
+        TEL;TYPE=VOICE,TEXT,WORK;VALUE=uri:tel:+1-666-555-1212\r\n\
+        TEL;TYPE=FAX,WORK;VALUE=uri:tel:+1-666-555-1213\r\n\
+        EMAIL:human@example.com\r\n\
+        END:VCARD\r\n";
+
+    fn parse(vcard: &str) -> Result<GhostbustersRecord, VCardError> {
+        GhostbustersRecord::from_vcard(Bytes::from(vcard.to_string()))
+    }
+
+    #[test]
+    fn parse_example() {
+        let record = parse(EXAMPLE).unwrap();
+        assert_eq!(record.full_name(), "Human Being");
+        assert_eq!(record.org(), Some("Example Organization"));
+        assert_eq!(
+            record.addresses(),
+            &[String::from(";;42 Twisty Passage;Deep Cavern;WA;98666;U.S.A.")]
+        );
+        assert_eq!(
+            record.phones(),
+            &[
+                String::from("tel:+1-666-555-1212"),
+                String::from("tel:+1-666-555-1213")
+            ]
+        );
+        assert_eq!(record.emails(), &[String::from("human@example.com")]);
+    }
+
+    #[test]
+    fn parse_folded() {
+        let record = parse(
+            "begin:vcard\nversion:4.0\nFN:Human\n  Being\n\
+             EMAIL;PID=\"1:2\":human@example.com\nEND:VCARD\n"
+        ).unwrap();
+        assert_eq!(record.full_name(), "Human Being");
+        assert_eq!(record.emails(), &[String::from("human@example.com")]);
+    }
+
+    #[test]
+    fn parse_profile_violations() {
+        assert_eq!(
+            parse("VERSION:4.0\r\nFN:x\r\nEMAIL:x\r\nEND:VCARD\r\n"),
+            Err(VCardError::MissingProperty("BEGIN"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:3.0\r\nFN:x\r\nEMAIL:x\r\n\
+                   END:VCARD\r\n"),
+            Err(VCardError::MissingProperty("VERSION"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nEMAIL:x\r\nEND:VCARD\r\n"),
+            Err(VCardError::MissingProperty("FN"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:x\r\nEND:VCARD\r\n"),
+            Err(VCardError::MissingProperty("ADR, TEL, or EMAIL"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:x\r\nFN:y\r\n\
+                   EMAIL:x\r\nEND:VCARD\r\n"),
+            Err(VCardError::DuplicateProperty("FN"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:x\r\nNOTE:x\r\n\
+                   EMAIL:x\r\nEND:VCARD\r\n"),
+            Err(VCardError::UnexpectedProperty)
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:x\r\nEMAIL:x\r\n"),
+            Err(VCardError::MissingProperty("END"))
+        );
+        assert_eq!(
+            parse("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:x\r\nEMAIL:x\r\n\
+                   END:VCARD\r\nEMAIL:y\r\n"),
+            Err(VCardError::InvalidLine)
+        );
+        assert_eq!(
+            GhostbustersRecord::from_vcard(Bytes::from_static(b"\xff")),
+            Err(VCardError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn build_record() {
+        let mut builder = GbrBuilder::new("Human Being".into());
+        builder.set_org(Some("Example Organization".into()));
+        builder.push_email("human@example.com".into());
+        builder.push_phone("tel:+1-666-555-1212".into());
+        builder.push_address(format!(";;{};WA", "x".repeat(200)));
+        let record = builder.to_record().unwrap();
+        assert_eq!(record.full_name(), "Human Being");
+        assert_eq!(record.org(), Some("Example Organization"));
+        assert_eq!(
+            record.addresses(), &[format!(";;{};WA", "x".repeat(200))]
+        );
+        assert!(
+            record.vcard().split(|ch| *ch == b'\n').all(|line| {
+                line.len() <= MAX_LINE_LEN + 1
+            })
+        );
+
+        let mut builder = GbrBuilder::new("Human\r\nBeing".into());
+        builder.push_email("human@example.com".into());
+        assert_eq!(builder.to_record().unwrap_err(), VCardError::LineBreak);
+
+        // A line break must not sneak in additional properties.
+        let mut builder = GbrBuilder::new("Human Being".into());
+        builder.push_email("human@example.com\nTEL:+1-666-555-1212".into());
+        assert_eq!(builder.to_record().unwrap_err(), VCardError::LineBreak);
+        let mut builder = GbrBuilder::new("Human Being".into());
+        builder.set_org(Some("Example\rEMAIL:evil@example.com".into()));
+        builder.push_email("human@example.com".into());
+        assert_eq!(builder.to_record().unwrap_err(), VCardError::LineBreak);
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use std::str::FromStr;
+    use crate::cert::{KeyUsage, Overclaim, TbsCert};
+    use crate::crypto::{PublicKeyFormat, Signer};
+    use crate::crypto::softsigner::OpenSslSigner;
+    use crate::resources::{AsId, Prefix};
+    use crate::tal::TalInfo;
+    use crate::uri;
+    use crate::x509::Validity;
+    use super::*;
+
+    fn make_gbr() -> (Gbr, ResourceCert) {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let pubkey = signer.get_key_info(&key).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+
+        let mut cert = TbsCert::new(
+            12u64.into(), pubkey.to_subject_name(),
+            Validity::from_secs(86400), None, pubkey, KeyUsage::Ca,
+            Overclaim::Trim
+        );
+        cert.set_basic_ca(Some(true));
+        cert.set_ca_repository(Some(uri.clone()));
+        cert.set_rpki_manifest(Some(uri.clone()));
+        cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_v6_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+        cert.build_as_resource_blocks(|b| b.push((AsId::MIN, AsId::MAX)));
+        let cert = cert.into_cert(&signer, &key).unwrap();
+
+        let mut gbr = GbrBuilder::new("Human Being".into());
+        gbr.push_email("human@example.com".into());
+        let gbr = gbr.finalize(
+            SignedObjectBuilder::new(
+                12u64.into(), Validity::from_secs(86400), uri.clone(),
+                uri.clone(), uri
+            ),
+            &signer, &key
+        ).unwrap();
+        let gbr = gbr.encode_ref().to_captured(Mode::Der);
+
+        let gbr = Gbr::decode(gbr.as_slice(), true).unwrap();
+        let cert = cert.validate_ta(
+            TalInfo::from_name("foo".into()).into_arc(), true
+        ).unwrap();
+        (gbr, cert)
+    }
+
+    #[test]
+    fn encode_gbr() {
+        let (gbr, issuer) = make_gbr();
+        let (_, content) = gbr.process(&issuer, true, |_| Ok(())).unwrap();
+        assert_eq!(content.full_name(), "Human Being");
+        assert_eq!(content.emails(), &[String::from("human@example.com")]);
+    }
+
+    #[test]
+    fn finalize_invalid() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let uri = uri::Rsync::from_str("rsync://example.com/m/p").unwrap();
+
+        // No contact information.
+        match GbrBuilder::new("Human Being".into()).finalize(
+            SignedObjectBuilder::new(
+                12u64.into(), Validity::from_secs(86400), uri.clone(),
+                uri.clone(), uri
+            ),
+            &signer, &key
+        ) {
+            Err(BuildError::VCard(VCardError::MissingProperty(_))) => { }
+            _ => panic!("expected missing property error")
+        }
+    }
+
+    #[test]
+    fn serde_gbr() {
+        let (gbr, _) = make_gbr();
+
+        let serialized = serde_json::to_string(&gbr).unwrap();
+        let deser_gbr: Gbr = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(
+            gbr.to_captured().into_bytes(),
+            deser_gbr.to_captured().into_bytes()
+        )
+    }
+}
+
+
+//============ Specification Documentation ===================================
+
+/// Ghostbusters Record Specification.
+///
+/// This is a documentation-only module. It summarizes the specification for
+/// Ghostbusters records, how they are parsed and constructed.
+///
+/// A Ghostbusters record is a [signed object] that provides contact
+/// information for the operator of a CA. It is specified in [RFC 6493].
+///
+/// Unlike other signed objects, the content of a Ghostbusters record is not
+/// ASN.1 but a [vCard] in the text form defined by [RFC 6350]. It is limited
+/// to the following profile:
+///
+/// * the first property is `BEGIN:VCARD` and the second `VERSION:4.0`,
+/// * the last property is `END:VCARD`,
+/// * the FN property must be present,
+/// * the ORG property may be present,
+/// * at least one of the ADR, TEL, or EMAIL properties must be present,
+/// * no other properties are allowed.
+///
+/// The EE certificate of a Ghostbusters record must use “inherit” for all
+/// its resources.
+///
+/// [signed object]: ../../sigobj/spec/index.html
+/// [vCard]: https://tools.ietf.org/html/rfc6350
+/// [RFC 6350]: https://tools.ietf.org/html/rfc6350
+/// [RFC 6493]: https://tools.ietf.org/html/rfc6493
+pub mod spec { }
@@ -0,0 +1,799 @@
+//! Identity certificates.
+//!
+//! The protocols for provisioning resources (RFC 6492) and publishing
+//! objects (RFC 8181) as well as the out-of-band setup exchange (RFC 8183)
+//! use a separate PKI, often called BPKI, to identify the parties. Its
+//! certificates follow the X.509 profile of RFC 5280 but carry neither
+//! resources nor subject information access. They therefore can’t be
+//! represented by [`Cert`] which enforces the RPKI profile.
+//!
+//! This module provides the type [`IdCert`] for such certificates.
+//!
+//! [`Cert`]: ../cert/struct.Cert.html
+//! [`IdCert`]: struct.IdCert.html
+
+use std::ops;
+use bcder::{decode, encode};
+use bcder::{BitString, Captured, Mode, OctetString, Oid, Tag, xerr};
+use bcder::encode::PrimitiveContent;
+use crate::oid;
+use crate::cert::{KeyUsage, TbsCert};
+use crate::crypto::{
+    KeyIdentifier, PublicKey, SignatureAlgorithm, Signer, SigningError
+};
+use crate::x509::{
+    Name, SignedData, Serial, Time, Validity, ValidationError,
+    ValidationErrorKind, encode_extension, update_once
+};
+
+
+//------------ IdCert --------------------------------------------------------
+
+/// An identity certificate.
+#[derive(Clone, Debug)]
+pub struct IdCert {
+    /// The outer structure of the certificate.
+    signed_data: SignedData,
+
+    /// The actual data of the certificate.
+    tbs: TbsIdCert,
+}
+
+/// # Decoding and Encoding
+///
+impl IdCert {
+    /// Decodes a source as an identity certificate.
+    pub fn decode<S: decode::Source>(source: S) -> Result<Self, S::Err> {
+        Mode::Der.decode(source, Self::take_from)
+    }
+
+    /// Takes an encoded certificate from the beginning of a value.
+    pub fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(Self::from_constructed)
+    }
+
+    /// Parses the content of a Certificate sequence.
+    pub fn from_constructed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        let signed_data = SignedData::from_constructed(cons)?;
+        let tbs = signed_data.data().clone().decode(
+            TbsIdCert::from_constructed
+        )?;
+        Ok(Self { signed_data, tbs })
+    }
+
+    /// Returns a value encoder for a reference to the certificate.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        self.signed_data.encode_ref()
+    }
+
+    /// Returns a captured encoding of the certificate.
+    pub fn to_captured(&self) -> Captured {
+        Captured::from_values(Mode::Der, self.encode_ref())
+    }
+}
+
+/// # Validation
+///
+impl IdCert {
+    /// Validates the certificate as a trust anchor.
+    ///
+    /// This checks that the certificate is a current, self-signed CA
+    /// certificate. Identity certificates exchanged out-of-band via
+    /// RFC 8183 are of this kind.
+    pub fn validate_ta(&self) -> Result<(), ValidationError> {
+        self.validate_ta_at(Time::now())
+    }
+
+    /// Validates the certificate as a trust anchor at the given time.
+    pub fn validate_ta_at(&self, now: Time) -> Result<(), ValidationError> {
+        self.validate_basics(now)?;
+
+        // A trust anchor must be a CA.
+        if self.basic_ca != Some(true) {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ))
+        }
+
+        // The authority key identifier may be present. If it is, it must
+        // be equal to the subject key identifier.
+        if let Some(aki) = self.authority_key_identifier {
+            if aki != self.subject_key_identifier {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::AuthorityKeyIdentifierMismatch
+                ))
+            }
+        }
+
+        self.signed_data.verify_signature(&self.subject_public_key_info)
+    }
+
+    /// Validates the certificate as an EE certificate.
+    ///
+    /// For validation to succeed, the certificate needs to have been signed
+    /// by the provided `issuer` certificate which has to be a currently
+    /// valid CA certificate with a subject equal to the certificate’s
+    /// issuer.
+    ///
+    /// Note that this does _not_ check the CRL.
+    pub fn validate_ee(
+        &self,
+        issuer: &IdCert
+    ) -> Result<(), ValidationError> {
+        self.validate_ee_at(issuer, Time::now())
+    }
+
+    /// Validates the certificate as an EE certificate at the given time.
+    pub fn validate_ee_at(
+        &self,
+        issuer: &IdCert,
+        now: Time,
+    ) -> Result<(), ValidationError> {
+        self.validate_basics(now)?;
+
+        // An EE certificate must not claim to be a CA.
+        if self.basic_ca == Some(true) {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ))
+        }
+
+        // The issuer, on the other hand, must be a currently valid CA.
+        if issuer.basic_ca != Some(true) {
+            return Err(ValidationError::new(
+                ValidationErrorKind::InvalidBasicConstraints
+            ))
+        }
+        issuer.validity.validate_at(now)?;
+
+        // The issuer name must be the subject name of the issuer.
+        if self.issuer != issuer.subject {
+            return Err(ValidationError::new(
+                ValidationErrorKind::IssuerNameMismatch
+            ).with_rfc(5280, "4.1.2.4"))
+        }
+
+        // The authority key identifier is optional in RFC 5280 but if it
+        // is present it must match the issuer.
+        if let Some(aki) = self.authority_key_identifier {
+            if aki != issuer.subject_key_identifier {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::AuthorityKeyIdentifierMismatch
+                ))
+            }
+        }
+
+        self.signed_data.verify_signature(&issuer.subject_public_key_info)
+    }
+
+    /// Validates the parts common to all identity certificates.
+    fn validate_basics(&self, now: Time) -> Result<(), ValidationError> {
+        // The signature algorithm inside and outside must be the same.
+        if self.signature != self.signed_data.signature().algorithm() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignatureAlgorithmMismatch
+            ).with_rfc(5280, "4.1.1.2"))
+        }
+
+        self.validity.validate_at(now)?;
+
+        // The subject key identifier must be derived from the key. RFC 5280
+        // only recommends this but everyone does it and we rely on it to
+        // match certificates.
+        if self.subject_key_identifier
+            != self.subject_public_key_info.key_identifier()
+        {
+            return Err(ValidationError::new(
+                ValidationErrorKind::KeyIdentifierMismatch
+            ))
+        }
+        Ok(())
+    }
+}
+
+
+//--- Deref and AsRef
+
+impl ops::Deref for IdCert {
+    type Target = TbsIdCert;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tbs
+    }
+}
+
+impl AsRef<TbsIdCert> for IdCert {
+    fn as_ref(&self) -> &TbsIdCert {
+        &self.tbs
+    }
+}
+
+
+//--- PartialEq and Eq
+
+/// Identity certificates are compared by their DER encoding.
+impl PartialEq for IdCert {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_captured().as_slice() == other.to_captured().as_slice()
+    }
+}
+
+impl Eq for IdCert { }
+
+
+//------------ TbsIdCert -----------------------------------------------------
+
+/// The data of an identity certificate.
+///
+/// Only the extensions relevant for identity certificates are kept. Any
+/// other non-critical extensions are ignored when parsing, unknown
+/// critical extensions cause parsing to fail.
+#[derive(Clone, Debug)]
+pub struct TbsIdCert {
+    /// The serial number.
+    serial_number: Serial,
+
+    /// The algorithm used for signing the certificate.
+    signature: SignatureAlgorithm,
+
+    /// The name of the issuer.
+    issuer: Name,
+
+    /// The validity of the certificate.
+    validity: Validity,
+
+    /// The name of the subject of this certificate.
+    subject: Name,
+
+    /// Information about the public key of this certificate.
+    subject_public_key_info: PublicKey,
+
+    /// Basic Constraints.
+    ///
+    /// The field indicates whether the extension is present and, if so,
+    /// whether the "cA" boolean is set.
+    basic_ca: Option<bool>,
+
+    /// Subject Key Identifier.
+    subject_key_identifier: KeyIdentifier,
+
+    /// Authority Key Identifier.
+    authority_key_identifier: Option<KeyIdentifier>,
+}
+
+/// # Creation and Conversion
+///
+impl TbsIdCert {
+    /// Creates a new value from the necessary data.
+    ///
+    /// If `subject` is `None`, the subject name is derived from the
+    /// subject public key.
+    pub fn new(
+        serial_number: Serial,
+        issuer: Name,
+        validity: Validity,
+        subject: Option<Name>,
+        subject_public_key_info: PublicKey,
+    ) -> Self {
+        Self {
+            serial_number,
+            signature: SignatureAlgorithm::default(),
+            issuer,
+            validity,
+            subject: {
+                subject.unwrap_or_else(||
+                    Name::from_pub_key(&subject_public_key_info)
+                )
+            },
+            subject_key_identifier: {
+                KeyIdentifier::from_public_key(&subject_public_key_info)
+            },
+            subject_public_key_info,
+            basic_ca: None,
+            authority_key_identifier: None,
+        }
+    }
+
+    /// Converts the value into a signed certificate.
+    pub fn into_cert<S: Signer>(
+        self,
+        signer: &S,
+        key: &S::KeyId,
+    ) -> Result<IdCert, SigningError<S::Error>> {
+        let data = Captured::from_values(Mode::Der, self.encode_ref());
+        let signature = signer.sign(key, self.signature, &data)?;
+        Ok(IdCert {
+            signed_data: SignedData::new(data, signature),
+            tbs: self
+        })
+    }
+}
+
+/// # Data Access
+///
+impl TbsIdCert {
+    /// Returns the serial number of the certificate.
+    pub fn serial_number(&self) -> Serial {
+        self.serial_number
+    }
+
+    /// Sets the serial number of the certificate.
+    pub fn set_serial_number<S: Into<Serial>>(&mut self, serial: S) {
+        self.serial_number = serial.into()
+    }
+
+    /// Returns a reference to the issuer.
+    pub fn issuer(&self) -> &Name {
+        &self.issuer
+    }
+
+    /// Sets the issuer.
+    pub fn set_issuer(&mut self, name: Name) {
+        self.issuer = name
+    }
+
+    /// Returns the validity of the certificate.
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    /// Sets the validity of the certificate.
+    pub fn set_validity(&mut self, validity: Validity) {
+        self.validity = validity
+    }
+
+    /// Returns a reference to the subject.
+    pub fn subject(&self) -> &Name {
+        &self.subject
+    }
+
+    /// Sets the subject.
+    pub fn set_subject(&mut self, subject: Name) {
+        self.subject = subject
+    }
+
+    /// Returns a reference to the public key.
+    pub fn subject_public_key_info(&self) -> &PublicKey {
+        &self.subject_public_key_info
+    }
+
+    /// Sets the public key.
+    ///
+    /// This also updates the subject key identifier.
+    pub fn set_subject_public_key(&mut self, key: PublicKey) {
+        self.subject_key_identifier = KeyIdentifier::from_public_key(&key);
+        self.subject_public_key_info = key;
+    }
+
+    /// Returns the cA field of the basic constraints extension if present.
+    pub fn basic_ca(&self) -> Option<bool> {
+        self.basic_ca
+    }
+
+    /// Sets the basic constraints extension.
+    ///
+    /// If `value` is `Some(true)`, the certificate will also be encoded
+    /// with the key usage of a CA certificate, otherwise with that of an
+    /// EE certificate.
+    pub fn set_basic_ca(&mut self, value: Option<bool>) {
+        self.basic_ca = value
+    }
+
+    /// Returns the subject key identifier.
+    pub fn subject_key_identifier(&self) -> KeyIdentifier {
+        self.subject_key_identifier
+    }
+
+    /// Returns the authority key identifier if present.
+    pub fn authority_key_identifier(&self) -> Option<KeyIdentifier> {
+        self.authority_key_identifier
+    }
+
+    /// Sets the authority key identifier.
+    pub fn set_authority_key_identifier(
+        &mut self,
+        id: Option<KeyIdentifier>
+    ) {
+        self.authority_key_identifier = id
+    }
+}
+
+/// # Decoding and Encoding
+///
+impl TbsIdCert {
+    /// Parses the content of a Certificate sequence.
+    pub fn from_constructed<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| {
+            // version [0] EXPLICIT Version DEFAULT v1.
+            //  -- we need extensions so we want v3 which is 2.
+            cons.take_constructed_if(Tag::CTX_0, |c| c.skip_u8_if(2))?;
+
+            let serial_number = Serial::take_from(cons)?;
+            let signature = SignatureAlgorithm::x509_take_from(cons)?;
+            let issuer = Name::take_from(cons)?;
+            let validity = Validity::take_from(cons)?;
+            let subject = Name::take_from(cons)?;
+            let subject_public_key_info = PublicKey::take_from(cons)?;
+
+            let mut basic_ca = None;
+            let mut subject_key_id = None;
+            let mut authority_key_id = None;
+            let mut key_usage = None;
+
+            cons.take_constructed_if(Tag::CTX_3, |c| c.take_sequence(|cons| {
+                while let Some(()) = cons.take_opt_sequence(|cons| {
+                    let id = Oid::take_from(cons)?;
+                    let critical = cons.take_opt_bool()?.unwrap_or(false);
+                    let value = OctetString::take_from(cons)?;
+                    Mode::Der.decode(value.to_source(), |content| {
+                        if id == oid::CE_BASIC_CONSTRAINTS {
+                            TbsCert::take_basic_constraints(
+                                content, &mut basic_ca
+                            )
+                        } else if id == oid::CE_SUBJECT_KEY_IDENTIFIER {
+                            TbsCert::take_subject_key_identifier(
+                                content, &mut subject_key_id
+                            )
+                        } else if id == oid::CE_AUTHORITY_KEY_IDENTIFIER {
+                            TbsCert::take_authority_key_identifier(
+                                content, &mut authority_key_id
+                            )
+                        } else if id == oid::CE_KEY_USAGE {
+                            // The BPKI doesn’t restrict key usage, so we
+                            // only make sure the extension appears once.
+                            update_once(&mut key_usage, || {
+                                BitString::take_from(content)
+                            })
+                        } else if critical {
+                            xerr!(Err(decode::Malformed))
+                        } else {
+                            // RFC 5280 says we can ignore non-critical
+                            // extensions we don’t know of.
+                            content.skip_all()
+                        }
+                    })?;
+                    Ok(())
+                })? { }
+                Ok(())
+            }))?;
+
+            Ok(Self {
+                serial_number,
+                signature,
+                issuer,
+                validity,
+                subject,
+                subject_public_key_info,
+                basic_ca,
+                subject_key_identifier:
+                    subject_key_id.ok_or(decode::Malformed)?,
+                authority_key_identifier: authority_key_id,
+            })
+        })
+    }
+
+    /// Returns an encoder for the value.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        let key_usage = if self.basic_ca == Some(true) {
+            KeyUsage::Ca
+        }
+        else {
+            KeyUsage::Ee
+        };
+        encode::sequence((
+            encode::sequence_as(Tag::CTX_0, 2.encode()), // version
+            self.serial_number.encode(),
+            self.signature.x509_encode(),
+            self.issuer.encode_ref(),
+            self.validity.encode(),
+            self.subject.encode_ref(),
+            self.subject_public_key_info.encode_ref(),
+            // no issuerUniqueID
+            // no subjetUniqueID
+            // extensions
+            encode::sequence_as(Tag::CTX_3, encode::sequence((
+                // Basic Constraints
+                self.basic_ca.map(|ca| {
+                    encode_extension(
+                        &oid::CE_BASIC_CONSTRAINTS, true,
+                        encode::sequence(
+                            if ca {
+                                Some(ca.encode())
+                            }
+                            else {
+                                None
+                            }
+                        )
+                    )
+                }),
+
+                // Subject Key Identifier
+                encode_extension(
+                    &oid::CE_SUBJECT_KEY_IDENTIFIER, false,
+                    self.subject_key_identifier.encode_ref(),
+                ),
+
+                // Authority Key Identifier
+                self.authority_key_identifier.as_ref().map(|id| {
+                    encode_extension(
+                        &oid::CE_AUTHORITY_KEY_IDENTIFIER, false,
+                        encode::sequence(id.encode_ref_as(Tag::CTX_0))
+                    )
+                }),
+
+                // Key Usage
+                encode_extension(
+                    &oid::CE_KEY_USAGE, true,
+                    key_usage.encode()
+                ),
+            )))
+        ))
+    }
+}
+
+
+//------------ IdCertBuilder -------------------------------------------------
+
+/// A builder for identity certificates.
+///
+/// The builder can create two kinds of certificates: self-signed trust
+/// anchor certificates via [`build_ta`] and EE certificates issued by such
+/// a trust anchor via [`build_ee`]. In both cases, the issuer’s key is
+/// held by a [`Signer`].
+///
+/// Unless given explicitly, issuer and subject names are derived from the
+/// respective public keys via [`Name::from_pub_key`].
+///
+/// [`build_ta`]: #method.build_ta
+/// [`build_ee`]: #method.build_ee
+/// [`Signer`]: ../crypto/signer/trait.Signer.html
+/// [`Name::from_pub_key`]: ../x509/struct.Name.html#method.from_pub_key
+#[derive(Clone, Debug)]
+pub struct IdCertBuilder {
+    /// The serial number of the certificate.
+    ///
+    /// Must be provided.
+    serial_number: Serial,
+
+    /// The validity of the certificate.
+    ///
+    /// Must be provided.
+    validity: Validity,
+
+    /// The issuer name of the certificate.
+    ///
+    /// If this is `None` (the default), it will be generated from the
+    /// issuer’s public key.
+    issuer: Option<Name>,
+
+    /// The subject name of the certificate.
+    ///
+    /// If this is `None` (the default), it will be generated from the
+    /// subject’s public key.
+    subject: Option<Name>,
+}
+
+impl IdCertBuilder {
+    /// Creates a new builder.
+    pub fn new(serial_number: Serial, validity: Validity) -> Self {
+        IdCertBuilder {
+            serial_number,
+            validity,
+            issuer: None,
+            subject: None,
+        }
+    }
+
+    pub fn serial_number(&self) -> Serial {
+        self.serial_number
+    }
+
+    pub fn set_serial_number(&mut self, serial: Serial) {
+        self.serial_number = serial
+    }
+
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    pub fn set_validity(&mut self, validity: Validity) {
+        self.validity = validity
+    }
+
+    pub fn issuer(&self) -> Option<&Name> {
+        self.issuer.as_ref()
+    }
+
+    pub fn set_issuer(&mut self, name: Option<Name>) {
+        self.issuer = name
+    }
+
+    pub fn subject(&self) -> Option<&Name> {
+        self.subject.as_ref()
+    }
+
+    pub fn set_subject(&mut self, name: Option<Name>) {
+        self.subject = name
+    }
+
+    /// Builds a self-signed trust anchor certificate for `key`.
+    ///
+    /// If an issuer name has been set, it is ignored. The issuer of a
+    /// self-signed certificate is always its subject.
+    pub fn build_ta<S: Signer>(
+        self,
+        signer: &S,
+        key: &S::KeyId,
+    ) -> Result<IdCert, SigningError<S::Error>> {
+        let public_key = signer.get_key_info(key)?;
+        let subject = self.subject.unwrap_or_else(|| {
+            Name::from_pub_key(&public_key)
+        });
+        let key_id = public_key.key_identifier();
+        let mut cert = TbsIdCert::new(
+            self.serial_number,
+            subject.clone(),
+            self.validity,
+            Some(subject),
+            public_key,
+        );
+        cert.set_basic_ca(Some(true));
+        cert.set_authority_key_identifier(Some(key_id));
+        cert.into_cert(signer, key)
+    }
+
+    /// Builds an EE certificate for `public_key` issued by `issuer_key`.
+    pub fn build_ee<S: Signer>(
+        self,
+        public_key: PublicKey,
+        signer: &S,
+        issuer_key: &S::KeyId,
+    ) -> Result<IdCert, SigningError<S::Error>> {
+        let issuer_pub = signer.get_key_info(issuer_key)?;
+        let mut cert = TbsIdCert::new(
+            self.serial_number,
+            self.issuer.unwrap_or_else(|| Name::from_pub_key(&issuer_pub)),
+            self.validity,
+            self.subject,
+            public_key,
+        );
+        cert.set_authority_key_identifier(Some(issuer_pub.key_identifier()));
+        cert.into_cert(signer, issuer_key)
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_and_validate() {
+        let at = Time::utc(2027, 1, 1, 0, 0, 0);
+        let ta = IdCert::decode(
+            include_bytes!("../test-data/id-ta.cer").as_ref()
+        ).unwrap();
+        let ee = IdCert::decode(
+            include_bytes!("../test-data/id-ee.cer").as_ref()
+        ).unwrap();
+        assert_eq!(ta.basic_ca(), Some(true));
+        assert_eq!(ee.basic_ca(), None);
+        assert_eq!(
+            ee.authority_key_identifier(),
+            Some(ta.subject_key_identifier())
+        );
+
+        ta.validate_ta_at(at).unwrap();
+        ee.validate_ee_at(&ta, at).unwrap();
+        assert!(ee.validate_ta_at(at).is_err());
+        assert!(ta.validate_ee_at(&ta, at).is_err());
+        assert!(ee.validate_ee_at(&ee, at).is_err());
+        assert!(ta.validate_ta_at(Time::utc(2020, 1, 1, 0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn reject_unknown_critical() {
+        // Resource certificates have critical extensions we don’t know.
+        assert!(IdCert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).is_err());
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use bcder::encode::Values;
+    use crate::crypto::PublicKeyFormat;
+    use crate::crypto::softsigner::OpenSslSigner;
+    use super::*;
+
+    #[test]
+    fn build_ta_and_ee() {
+        let mut signer = OpenSslSigner::new();
+        let ta_key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let ee_key = signer.create_key(PublicKeyFormat::default()).unwrap();
+
+        let ta = IdCertBuilder::new(
+            1u64.into(), Validity::from_secs(86400)
+        ).build_ta(&signer, &ta_key).unwrap();
+        let ta = IdCert::decode(
+            ta.encode_ref().to_captured(Mode::Der).as_slice()
+        ).unwrap();
+        ta.validate_ta().unwrap();
+
+        let ee = IdCertBuilder::new(
+            2u64.into(), Validity::from_secs(86400)
+        ).build_ee(
+            signer.get_key_info(&ee_key).unwrap(), &signer, &ta_key
+        ).unwrap();
+        let ee = IdCert::decode(ee.to_captured().as_slice()).unwrap();
+        ee.validate_ee(&ta).unwrap();
+        assert!(ee.validate_ta().is_err());
+
+        // An EE certificate issued by a different key must fail.
+        let other = IdCertBuilder::new(
+            3u64.into(), Validity::from_secs(86400)
+        ).build_ee(
+            signer.get_key_info(&ee_key).unwrap(), &signer, &ee_key
+        ).unwrap();
+        assert!(other.validate_ee(&ta).is_err());
+
+        // An “EE” certificate issued by an EE certificate must fail even
+        // if names and key identifiers match.
+        let sub_key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let sub = IdCertBuilder::new(
+            4u64.into(), Validity::from_secs(86400)
+        ).build_ee(
+            signer.get_key_info(&sub_key).unwrap(), &signer, &ee_key
+        ).unwrap();
+        assert_eq!(sub.issuer(), ee.subject());
+        assert_eq!(
+            sub.authority_key_identifier(),
+            Some(ee.subject_key_identifier())
+        );
+        assert!(sub.validate_ee(&ee).is_err());
+
+        // An EE certificate with a different issuer name must fail.
+        let mut builder = IdCertBuilder::new(
+            5u64.into(), Validity::from_secs(86400)
+        );
+        builder.set_issuer(Some(Name::from_pub_key(
+            &signer.get_key_info(&ee_key).unwrap()
+        )));
+        let misnamed = builder.build_ee(
+            signer.get_key_info(&sub_key).unwrap(), &signer, &ta_key
+        ).unwrap();
+        assert!(misnamed.validate_ee(&ta).is_err());
+    }
+
+    #[test]
+    fn reject_expired_issuer() {
+        let mut signer = OpenSslSigner::new();
+        let ta_key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let ee_key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let at = Time::utc(2030, 1, 1, 0, 0, 0);
+
+        let ta = IdCertBuilder::new(
+            1u64.into(),
+            Validity::new(at, Time::utc(2030, 2, 1, 0, 0, 0))
+        ).build_ta(&signer, &ta_key).unwrap();
+        let ee = IdCertBuilder::new(
+            2u64.into(),
+            Validity::new(at, Time::utc(2031, 1, 1, 0, 0, 0))
+        ).build_ee(
+            signer.get_key_info(&ee_key).unwrap(), &signer, &ta_key
+        ).unwrap();
+        ee.validate_ee_at(&ta, Time::utc(2030, 1, 15, 0, 0, 0)).unwrap();
+        assert!(
+            ee.validate_ee_at(&ta, Time::utc(2030, 6, 1, 0, 0, 0)).is_err()
+        );
+    }
+}
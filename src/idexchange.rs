@@ -0,0 +1,868 @@
+//! The out-of-band setup protocol.
+//!
+//! Before a child CA can talk to its parent via the provisioning protocol
+//! of RFC 6492 or a CA can publish via the publication protocol of RFC
+//! 8181, both sides need to learn about each other’s identity. RFC 8183
+//! defines four XML documents for this exchange: the child sends a
+//! [`ChildRequest`] to its parent which answers with a [`ParentResponse`],
+//! while a publisher sends a [`PublisherRequest`] to a repository which
+//! answers with a [`RepositoryResponse`].
+//!
+//! Each document carries the self-signed BPKI trust anchor certificate of
+//! the sending party. These are decoded into [`IdCert`] values and have
+//! to be valid trust anchors for a document to be accepted.
+//!
+//! [`ChildRequest`]: struct.ChildRequest.html
+//! [`IdCert`]: ../idcert/struct.IdCert.html
+//! [`ParentResponse`]: struct.ParentResponse.html
+//! [`PublisherRequest`]: struct.PublisherRequest.html
+//! [`RepositoryResponse`]: struct.RepositoryResponse.html
+
+use std::{error, fmt, io, str};
+use bytes::Bytes;
+use crate::idcert::IdCert;
+use crate::uri;
+use crate::xml::decode::{Content, Error, Name, Reader};
+use crate::xml::encode;
+
+
+//------------ Handle --------------------------------------------------------
+
+/// The name of a party in the exchanges.
+///
+/// Handles are used in RFC 8183 as well as RFC 6492 and RFC 8181 to
+/// identify the parties of an exchange. They consist of between 1 and 255
+/// ASCII letters, digits, dashes, underscores, and slashes.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Handle(String);
+
+impl Handle {
+    /// Returns the handle as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl str::FromStr for Handle {
+    type Err = InvalidHandle;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || s.len() > 255 || !s.bytes().all(|ch| {
+            ch.is_ascii_alphanumeric() || ch == b'-' || ch == b'_'
+                || ch == b'/'
+        }) {
+            return Err(InvalidHandle)
+        }
+        Ok(Handle(s.into()))
+    }
+}
+
+impl AsRef<str> for Handle {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+
+//------------ ChildRequest --------------------------------------------------
+
+/// The child request defined in section 5.2.1 of RFC 8183.
+///
+/// The request is sent by a child CA to its prospective parent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChildRequest {
+    tag: Option<String>,
+    child_handle: Handle,
+    child_bpki_ta: IdCert,
+}
+
+impl ChildRequest {
+    /// Creates a new child request.
+    pub fn new(child_handle: Handle, child_bpki_ta: IdCert) -> Self {
+        ChildRequest { tag: None, child_handle, child_bpki_ta }
+    }
+
+    /// Returns the optional tag of the request.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Sets the tag of the request.
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag
+    }
+
+    /// Returns the handle the child uses for itself.
+    pub fn child_handle(&self) -> &Handle {
+        &self.child_handle
+    }
+
+    /// Returns the BPKI trust anchor certificate of the child.
+    pub fn child_bpki_ta(&self) -> &IdCert {
+        &self.child_bpki_ta
+    }
+
+    /// Parses a child request from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut tag = None;
+        let mut child_handle = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != CHILD_REQUEST {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => check_version(value.ascii_into()?),
+                b"tag" => {
+                    tag = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"child_handle" => {
+                    child_handle = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let child_bpki_ta = take_bpki_ta(
+            &mut outer, &mut reader, CHILD_BPKI_TA
+        )?;
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+        Ok(ChildRequest {
+            tag,
+            child_handle: child_handle.ok_or(Error::Malformed)?,
+            child_bpki_ta
+        })
+    }
+
+    /// Writes the child request as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(CHILD_REQUEST.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .opt_attr(b"tag", self.tag.as_ref())?
+            .attr(b"child_handle", &self.child_handle)?
+            .content(|content| {
+                content.element(CHILD_BPKI_TA.local())?
+                    .content(|content| {
+                        content.base64(
+                            self.child_bpki_ta.to_captured().as_slice()
+                        )
+                    })?
+                    .done()
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ ParentResponse ------------------------------------------------
+
+/// The parent response defined in section 5.2.2 of RFC 8183.
+///
+/// The response is sent by a parent CA to a child CA in reply to a
+/// [`ChildRequest`]. It informs the child about the identity of the parent
+/// and where to contact it.
+///
+/// The optional `offer` and `referral` elements that a parent may use to
+/// hint at a repository are accepted when parsing but ignored.
+///
+/// [`ChildRequest`]: struct.ChildRequest.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParentResponse {
+    tag: Option<String>,
+    parent_handle: Handle,
+    child_handle: Handle,
+    service_uri: uri::Https,
+    parent_bpki_ta: IdCert,
+}
+
+impl ParentResponse {
+    /// Creates a new parent response.
+    pub fn new(
+        parent_handle: Handle,
+        child_handle: Handle,
+        service_uri: uri::Https,
+        parent_bpki_ta: IdCert,
+    ) -> Self {
+        ParentResponse {
+            tag: None, parent_handle, child_handle, service_uri,
+            parent_bpki_ta
+        }
+    }
+
+    /// Returns the optional tag of the response.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Sets the tag of the response.
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag
+    }
+
+    /// Returns the handle of the parent.
+    pub fn parent_handle(&self) -> &Handle {
+        &self.parent_handle
+    }
+
+    /// Returns the handle the parent assigned to the child.
+    pub fn child_handle(&self) -> &Handle {
+        &self.child_handle
+    }
+
+    /// Returns the URI of the parent’s provisioning service.
+    pub fn service_uri(&self) -> &uri::Https {
+        &self.service_uri
+    }
+
+    /// Returns the BPKI trust anchor certificate of the parent.
+    pub fn parent_bpki_ta(&self) -> &IdCert {
+        &self.parent_bpki_ta
+    }
+
+    /// Parses a parent response from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut tag = None;
+        let mut parent_handle = None;
+        let mut child_handle = None;
+        let mut service_uri = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != PARENT_RESPONSE {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => check_version(value.ascii_into()?),
+                b"tag" => {
+                    tag = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"parent_handle" => {
+                    parent_handle = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"child_handle" => {
+                    child_handle = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"service_uri" => {
+                    service_uri = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let parent_bpki_ta = take_bpki_ta(
+            &mut outer, &mut reader, PARENT_BPKI_TA
+        )?;
+
+        // Skip over any offer or referral elements.
+        loop {
+            let mut referral = false;
+            let inner = outer.take_opt_element(&mut reader, |element| {
+                match element.name() {
+                    OFFER => Ok(()),
+                    REFERRAL => {
+                        referral = true;
+                        Ok(())
+                    }
+                    _ => Err(Error::Malformed)
+                }
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            if referral {
+                inner.take_text(&mut reader, |_| Ok::<_, Error>(()))?;
+            }
+            inner.take_end(&mut reader)?;
+        }
+
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+        Ok(ParentResponse {
+            tag,
+            parent_handle: parent_handle.ok_or(Error::Malformed)?,
+            child_handle: child_handle.ok_or(Error::Malformed)?,
+            service_uri: service_uri.ok_or(Error::Malformed)?,
+            parent_bpki_ta,
+        })
+    }
+
+    /// Writes the parent response as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(PARENT_RESPONSE.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .opt_attr(b"tag", self.tag.as_ref())?
+            .attr(b"service_uri", &self.service_uri)?
+            .attr(b"child_handle", &self.child_handle)?
+            .attr(b"parent_handle", &self.parent_handle)?
+            .content(|content| {
+                content.element(PARENT_BPKI_TA.local())?
+                    .content(|content| {
+                        content.base64(
+                            self.parent_bpki_ta.to_captured().as_slice()
+                        )
+                    })?
+                    .done()
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ PublisherRequest ----------------------------------------------
+
+/// The publisher request defined in section 5.2.3 of RFC 8183.
+///
+/// The request is sent by a CA that wants to publish its objects to the
+/// repository it wants to publish with.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublisherRequest {
+    tag: Option<String>,
+    publisher_handle: Handle,
+    publisher_bpki_ta: IdCert,
+}
+
+impl PublisherRequest {
+    /// Creates a new publisher request.
+    pub fn new(publisher_handle: Handle, publisher_bpki_ta: IdCert) -> Self {
+        PublisherRequest { tag: None, publisher_handle, publisher_bpki_ta }
+    }
+
+    /// Returns the optional tag of the request.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Sets the tag of the request.
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag
+    }
+
+    /// Returns the handle the publisher uses for itself.
+    pub fn publisher_handle(&self) -> &Handle {
+        &self.publisher_handle
+    }
+
+    /// Returns the BPKI trust anchor certificate of the
+    /// publisher.
+    pub fn publisher_bpki_ta(&self) -> &IdCert {
+        &self.publisher_bpki_ta
+    }
+
+    /// Parses a publisher request from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut tag = None;
+        let mut publisher_handle = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != PUBLISHER_REQUEST {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => check_version(value.ascii_into()?),
+                b"tag" => {
+                    tag = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"publisher_handle" => {
+                    publisher_handle = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let publisher_bpki_ta = take_bpki_ta(
+            &mut outer, &mut reader, PUBLISHER_BPKI_TA
+        )?;
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+        Ok(PublisherRequest {
+            tag,
+            publisher_handle: publisher_handle.ok_or(Error::Malformed)?,
+            publisher_bpki_ta
+        })
+    }
+
+    /// Writes the publisher request as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(PUBLISHER_REQUEST.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .opt_attr(b"tag", self.tag.as_ref())?
+            .attr(b"publisher_handle", &self.publisher_handle)?
+            .content(|content| {
+                content.element(PUBLISHER_BPKI_TA.local())?
+                    .content(|content| {
+                        content.base64(
+                            self.publisher_bpki_ta.to_captured().as_slice()
+                        )
+                    })?
+                    .done()
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ RepositoryResponse --------------------------------------------
+
+/// The repository response defined in section 5.2.4 of RFC 8183.
+///
+/// The response is sent by a repository to a publisher in reply to a
+/// [`PublisherRequest`]. It informs the publisher about the identity of
+/// the repository, where to contact it, and where its objects will be
+/// published.
+///
+/// [`PublisherRequest`]: struct.PublisherRequest.html
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepositoryResponse {
+    tag: Option<String>,
+    publisher_handle: Handle,
+    service_uri: uri::Https,
+    sia_base: uri::Rsync,
+    rrdp_notification_uri: Option<uri::Https>,
+    repository_bpki_ta: IdCert,
+}
+
+impl RepositoryResponse {
+    /// Creates a new repository response.
+    pub fn new(
+        publisher_handle: Handle,
+        service_uri: uri::Https,
+        sia_base: uri::Rsync,
+        rrdp_notification_uri: Option<uri::Https>,
+        repository_bpki_ta: IdCert,
+    ) -> Self {
+        RepositoryResponse {
+            tag: None, publisher_handle, service_uri, sia_base,
+            rrdp_notification_uri, repository_bpki_ta
+        }
+    }
+
+    /// Returns the optional tag of the response.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Sets the tag of the response.
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag
+    }
+
+    /// Returns the handle the repository assigned to the publisher.
+    pub fn publisher_handle(&self) -> &Handle {
+        &self.publisher_handle
+    }
+
+    /// Returns the URI of the repository’s publication service.
+    pub fn service_uri(&self) -> &uri::Https {
+        &self.service_uri
+    }
+
+    /// Returns the rsync URI under which the publisher may publish.
+    pub fn sia_base(&self) -> &uri::Rsync {
+        &self.sia_base
+    }
+
+    /// Returns the URI of the RRDP notification file if there is one.
+    pub fn rrdp_notification_uri(&self) -> Option<&uri::Https> {
+        self.rrdp_notification_uri.as_ref()
+    }
+
+    /// Returns the BPKI trust anchor certificate of the
+    /// repository.
+    pub fn repository_bpki_ta(&self) -> &IdCert {
+        &self.repository_bpki_ta
+    }
+
+    /// Parses a repository response from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut tag = None;
+        let mut publisher_handle = None;
+        let mut service_uri = None;
+        let mut sia_base = None;
+        let mut rrdp_notification_uri = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != REPOSITORY_RESPONSE {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => check_version(value.ascii_into()?),
+                b"tag" => {
+                    tag = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"publisher_handle" => {
+                    publisher_handle = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"service_uri" => {
+                    service_uri = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"sia_base" => {
+                    sia_base = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"rrdp_notification_uri" => {
+                    rrdp_notification_uri = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let repository_bpki_ta = take_bpki_ta(
+            &mut outer, &mut reader, REPOSITORY_BPKI_TA
+        )?;
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+        Ok(RepositoryResponse {
+            tag,
+            publisher_handle: publisher_handle.ok_or(Error::Malformed)?,
+            service_uri: service_uri.ok_or(Error::Malformed)?,
+            sia_base: sia_base.ok_or(Error::Malformed)?,
+            rrdp_notification_uri,
+            repository_bpki_ta,
+        })
+    }
+
+    /// Writes the repository response as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(REPOSITORY_RESPONSE.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .opt_attr(b"tag", self.tag.as_ref())?
+            .attr(b"publisher_handle", &self.publisher_handle)?
+            .attr(b"service_uri", &self.service_uri)?
+            .attr(b"sia_base", &self.sia_base)?
+            .opt_attr(
+                b"rrdp_notification_uri", self.rrdp_notification_uri.as_ref()
+            )?
+            .content(|content| {
+                content.element(REPOSITORY_BPKI_TA.local())?
+                    .content(|content| {
+                        content.base64(
+                            self.repository_bpki_ta.to_captured().as_slice()
+                        )
+                    })?
+                    .done()
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ InvalidHandle -------------------------------------------------
+
+/// A string was not a valid handle.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidHandle;
+
+impl fmt::Display for InvalidHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid handle")
+    }
+}
+
+impl error::Error for InvalidHandle { }
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Checks that the version attribute has the value 1.
+fn check_version(version: u8) -> Result<(), Error> {
+    if version != 1 {
+        Err(Error::Malformed)
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Takes an element containing a base64 encoded BPKI TA certificate.
+///
+/// The certificate is decoded and validated as a trust anchor. If either
+/// fails, the document is considered malformed.
+fn take_bpki_ta<R: io::BufRead>(
+    outer: &mut Content,
+    reader: &mut Reader<R>,
+    name: Name,
+) -> Result<IdCert, Error> {
+    let mut content = outer.take_element(reader, |element| {
+        if element.name() != name {
+            return Err(Error::Malformed)
+        }
+        element.attributes(|_, _| Err(Error::Malformed))
+    })?;
+    let res = content.take_text(reader, |text| text.base64_decode())?;
+    content.take_end(reader)?;
+    let cert = IdCert::decode(Bytes::from(res)).map_err(|_| {
+        Error::Malformed
+    })?;
+    cert.validate_ta().map_err(|_| Error::Malformed)?;
+    Ok(cert)
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS_STR: &str = "http://www.hactrn.net/uris/rpki/rpki-setup/";
+const NS: &[u8] = NS_STR.as_bytes();
+const CHILD_REQUEST: Name = Name::qualified(NS, b"child_request");
+const CHILD_BPKI_TA: Name = Name::qualified(NS, b"child_bpki_ta");
+const PARENT_RESPONSE: Name = Name::qualified(NS, b"parent_response");
+const PARENT_BPKI_TA: Name = Name::qualified(NS, b"parent_bpki_ta");
+const OFFER: Name = Name::qualified(NS, b"offer");
+const REFERRAL: Name = Name::qualified(NS, b"referral");
+const PUBLISHER_REQUEST: Name = Name::qualified(NS, b"publisher_request");
+const PUBLISHER_BPKI_TA: Name = Name::qualified(NS, b"publisher_bpki_ta");
+const REPOSITORY_RESPONSE: Name
+    = Name::qualified(NS, b"repository_response");
+const REPOSITORY_BPKI_TA: Name = Name::qualified(NS, b"repository_bpki_ta");
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn handle(s: &str) -> Handle {
+        Handle::from_str(s).unwrap()
+    }
+
+    fn ta() -> IdCert {
+        IdCert::decode(
+            include_bytes!("../test-data/id-ta.cer").as_ref()
+        ).unwrap()
+    }
+
+    fn ta_base64() -> String {
+        base64::encode(include_bytes!("../test-data/id-ta.cer").as_ref())
+    }
+
+    #[test]
+    fn handle_from_str() {
+        assert!(Handle::from_str("Bob-42/ca_1").is_ok());
+        assert!(Handle::from_str("").is_err());
+        assert!(Handle::from_str("Bob 42").is_err());
+        assert!(Handle::from_str(&"a".repeat(256)).is_err());
+    }
+
+    #[test]
+    fn parse_rfc8183_examples() {
+        // The examples from section 5.2 of RFC 8183 with the service URIs
+        // switched to HTTPS and our own BPKI certificate.
+        let ta_base64 = ta_base64();
+        let req = ChildRequest::parse(
+            format!(
+                "<child_request \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\" child_handle=\"Bob\">\n\
+                    <child_bpki_ta>\n  {ta}\n</child_bpki_ta>\n\
+                  </child_request>",
+                ta = ta_base64
+            ).as_bytes()
+        ).unwrap();
+        assert_eq!(req.child_handle(), &handle("Bob"));
+        assert_eq!(req.tag(), None);
+        assert_eq!(req.child_bpki_ta(), &ta());
+
+        let resp = ParentResponse::parse(
+            format!(
+                "<parent_response \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\" \
+                      service_uri=\"https://a.example/up-down/Alice/Bob-42\" \
+                      child_handle=\"Bob-42\" parent_handle=\"Alice\">\n\
+                    <parent_bpki_ta>{ta}</parent_bpki_ta>\n\
+                    <offer/>\n\
+                  </parent_response>",
+                ta = ta_base64
+            ).as_bytes()
+        ).unwrap();
+        assert_eq!(resp.parent_handle(), &handle("Alice"));
+        assert_eq!(resp.child_handle(), &handle("Bob-42"));
+        assert_eq!(
+            resp.service_uri().as_str(),
+            "https://a.example/up-down/Alice/Bob-42"
+        );
+
+        let req = PublisherRequest::parse(
+            format!(
+                "<publisher_request \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\" tag=\"A0001\" publisher_handle=\"Bob\">\n\
+                    <publisher_bpki_ta>{ta}</publisher_bpki_ta>\n\
+                  </publisher_request>",
+                ta = ta_base64
+            ).as_bytes()
+        ).unwrap();
+        assert_eq!(req.tag(), Some("A0001"));
+        assert_eq!(req.publisher_handle(), &handle("Bob"));
+
+        let resp = RepositoryResponse::parse(
+            format!(
+                "<repository_response \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\" tag=\"A0001\" \
+                      service_uri=\
+                          \"https://a.example/publication/Alice/Bob-42\" \
+                      publisher_handle=\"Alice/Bob-42\" \
+                      sia_base=\"rsync://a.example/rpki/Alice/Bob-42/\" \
+                      rrdp_notification_uri=\
+                          \"https://rrdp.example/notification.xml\">\n\
+                    <repository_bpki_ta>{ta}</repository_bpki_ta>\n\
+                  </repository_response>",
+                ta = ta_base64
+            ).as_bytes()
+        ).unwrap();
+        assert_eq!(resp.publisher_handle(), &handle("Alice/Bob-42"));
+        assert_eq!(
+            resp.sia_base().to_string(),
+            "rsync://a.example/rpki/Alice/Bob-42/"
+        );
+        assert_eq!(
+            resp.rrdp_notification_uri().unwrap().as_str(),
+            "https://rrdp.example/notification.xml"
+        );
+    }
+
+    #[test]
+    fn reject_malformed() {
+        let ta_base64 = ta_base64();
+
+        // Wrong version.
+        assert!(ChildRequest::parse(
+            format!(
+                "<child_request \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"2\" child_handle=\"Bob\">\
+                    <child_bpki_ta>{ta}</child_bpki_ta>\
+                  </child_request>",
+                ta = ta_base64
+            ).as_bytes()
+        ).is_err());
+
+        // Missing handle.
+        assert!(ChildRequest::parse(
+            format!(
+                "<child_request \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\">\
+                    <child_bpki_ta>{ta}</child_bpki_ta>\
+                  </child_request>",
+                ta = ta_base64
+            ).as_bytes()
+        ).is_err());
+
+        // Not a certificate.
+        assert!(ChildRequest::parse(
+            b"<child_request \
+                  xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                  version=\"1\" child_handle=\"Bob\">\
+                <child_bpki_ta>R29vZCB0aGluZ3M=</child_bpki_ta>\
+              </child_request>".as_ref()
+        ).is_err());
+
+        // Not a trust anchor certificate.
+        assert!(ChildRequest::parse(
+            format!(
+                "<child_request \
+                      xmlns=\"http://www.hactrn.net/uris/rpki/rpki-setup/\" \
+                      version=\"1\" child_handle=\"Bob\">\
+                    <child_bpki_ta>{ta}</child_bpki_ta>\
+                  </child_request>",
+                ta = base64::encode(
+                    include_bytes!("../test-data/id-ee.cer").as_ref()
+                )
+            ).as_bytes()
+        ).is_err());
+
+        // Wrong namespace.
+        assert!(PublisherRequest::parse(
+            format!(
+                "<publisher_request \
+                      xmlns=\"http://www.ripe.net/rpki/rrdp\" \
+                      version=\"1\" publisher_handle=\"Bob\">\
+                    <publisher_bpki_ta>{ta}</publisher_bpki_ta>\
+                  </publisher_request>",
+                ta = ta_base64
+            ).as_bytes()
+        ).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let ta = ta();
+
+        let mut req = ChildRequest::new(handle("Bob"), ta.clone());
+        req.set_tag(Some("x&y".into()));
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        assert_eq!(req, ChildRequest::parse(xml.as_slice()).unwrap());
+
+        let resp = ParentResponse::new(
+            handle("Alice"), handle("Bob"),
+            uri::Https::from_str("https://a.example/up-down/Alice/Bob")
+                .unwrap(),
+            ta.clone()
+        );
+        let mut xml = Vec::new();
+        resp.write_xml(&mut xml).unwrap();
+        assert_eq!(resp, ParentResponse::parse(xml.as_slice()).unwrap());
+
+        let req = PublisherRequest::new(handle("Bob"), ta.clone());
+        let mut xml = Vec::new();
+        req.write_xml(&mut xml).unwrap();
+        assert_eq!(req, PublisherRequest::parse(xml.as_slice()).unwrap());
+
+        let mut resp = RepositoryResponse::new(
+            handle("Alice/Bob"),
+            uri::Https::from_str("https://a.example/publication/Alice/Bob")
+                .unwrap(),
+            uri::Rsync::from_str("rsync://a.example/rpki/Alice/Bob/")
+                .unwrap(),
+            None,
+            ta
+        );
+        resp.set_tag(Some("A0001".into()));
+        let mut xml = Vec::new();
+        resp.write_xml(&mut xml).unwrap();
+        assert_eq!(resp, RepositoryResponse::parse(xml.as_slice()).unwrap());
+    }
+}
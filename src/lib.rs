@@ -15,18 +15,30 @@
 //! Documentation for the items in this crate is currently somewhat sparse.
 //! This will be rectified in upcoming releases.
 
+pub mod aspa;
 pub mod cert;
 pub mod crl;
 pub mod crypto;
 pub mod csr;
+pub mod gbr;
+pub mod idcert;
+pub mod idexchange;
 pub mod manifest;
+pub mod minroa;
+pub mod object;
 pub mod oid;
+pub mod protocms;
+pub mod publication;
+pub mod pubpoint;
 pub mod resources;
 pub mod roa;
 pub mod rrdp;
 pub mod sigobj;
 pub mod tal;
+pub mod updown;
 pub mod uri;
+pub mod validation;
+pub mod vrp;
 pub mod x509;
 pub mod xml;
 
@@ -7,10 +7,16 @@
 //! manifest and the type [`ManifestContent`] for the content of a validated
 //! manifest, as well as some helper types for accessing the content.
 //!
+//! In addition, the type [`ManifestHistory`] keeps track of the manifests
+//! accepted previously and allows detecting replayed or rolled back
+//! manifests.
+//!
 //! [`Manifest`]: struct.Manifest.html
 //! [`ManifestContent`]: struct.ManifestContent.html
+//! [`ManifestHistory`]: struct.ManifestHistory.html
 
 use std::{borrow, ops};
+use std::collections::HashMap;
 use bcder::{decode, encode};
 use bcder::{
     BitString, Captured, Ia5String, Mode, OctetString, Oid, Tag, xerr
@@ -22,7 +28,7 @@ use crate::{oid, uri};
 use crate::cert::{Cert, ResourceCert};
 use crate::crypto::{DigestAlgorithm, Signer, SigningError};
 use crate::sigobj::{SignedObject, SignedObjectBuilder};
-use crate::x509::{Serial, Time, ValidationError};
+use crate::x509::{Serial, Time, ValidationError, ValidationErrorKind};
 
 
 //------------ Manifest ------------------------------------------------------
@@ -485,8 +491,174 @@ impl ManifestHash {
         ring::constant_time::verify_slices_are_equal(
             self.hash.as_ref(),
             self.algorithm.digest(t.as_ref()).as_ref()
-        ).map_err(|_| ValidationError)
+        ).map_err(|_| {
+            ValidationError::new(
+                ValidationErrorKind::HashMismatch
+            ).with_rfc(9286, "6.5")
+        })
+    }
+}
+
+
+//------------ ManifestHistory -----------------------------------------------
+
+/// The manifests previously accepted for a set of publication points.
+///
+/// RFC 9286, section 4.2.1 requires both the manifest number and the
+/// thisUpdate time of each new manifest issued by a CA to be greater than
+/// those of the previous one. A relying party should therefore remember
+/// these values for the last manifest it accepted for a publication point
+/// and refuse any manifest that goes backwards in order to protect against
+/// rollback attacks.
+///
+/// This type keeps a [`ManifestRecord`] for each manifest keyed by its
+/// rsync URI. It can be serialized and deserialized so that it can be kept
+/// across validation runs.
+///
+/// [`ManifestRecord`]: struct.ManifestRecord.html
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ManifestHistory {
+    /// The records of the last accepted manifests.
+    manifests: HashMap<uri::Rsync, ManifestRecord>,
+}
+
+impl ManifestHistory {
+    /// Creates a new, empty history.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the record of the last manifest accepted for `uri`.
+    pub fn get(&self, uri: &uri::Rsync) -> Option<&ManifestRecord> {
+        self.manifests.get(uri)
+    }
+
+    /// Classifies a manifest relative to the last one accepted for `uri`.
+    ///
+    /// This does not change the history.
+    pub fn classify(
+        &self,
+        uri: &uri::Rsync,
+        content: &ManifestContent
+    ) -> ManifestUpdate {
+        ManifestRecord::from_content(content).classify(self.get(uri))
+    }
+
+    /// Classifies a manifest and records it if it is newer.
+    ///
+    /// If the manifest is classified as `ManifestUpdate::Newer`, it
+    /// replaces the record for `uri`. Otherwise, the history stays
+    /// unchanged.
+    pub fn update(
+        &mut self,
+        uri: uri::Rsync,
+        content: &ManifestContent
+    ) -> ManifestUpdate {
+        let record = ManifestRecord::from_content(content);
+        let res = record.classify(self.get(&uri));
+        if res == ManifestUpdate::Newer {
+            self.manifests.insert(uri, record);
+        }
+        res
+    }
+
+    /// Removes the record for `uri` from the history.
+    pub fn remove(&mut self, uri: &uri::Rsync) -> Option<ManifestRecord> {
+        self.manifests.remove(uri)
+    }
+
+    /// Returns the number of manifests in the history.
+    pub fn len(&self) -> usize {
+        self.manifests.len()
+    }
+
+    /// Returns whether the history is empty.
+    pub fn is_empty(&self) -> bool {
+        self.manifests.is_empty()
+    }
+}
+
+
+//------------ ManifestRecord ------------------------------------------------
+
+/// The information kept about an accepted manifest.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ManifestRecord {
+    /// The manifest number.
+    manifest_number: Serial,
+
+    /// The thisUpdate time of the manifest.
+    this_update: Time,
+}
+
+impl ManifestRecord {
+    /// Creates a new record from its components.
+    pub fn new(manifest_number: Serial, this_update: Time) -> Self {
+        ManifestRecord { manifest_number, this_update }
     }
+
+    /// Creates a new record for the given manifest content.
+    pub fn from_content(content: &ManifestContent) -> Self {
+        Self::new(content.manifest_number(), content.this_update())
+    }
+
+    /// Returns the manifest number.
+    pub fn manifest_number(&self) -> Serial {
+        self.manifest_number
+    }
+
+    /// Returns the thisUpdate time.
+    pub fn this_update(&self) -> Time {
+        self.this_update
+    }
+
+    /// Classifies the record relative to a previous record.
+    ///
+    /// If there is no previous record, the manifest is considered newer.
+    pub fn classify(
+        &self,
+        previous: Option<&ManifestRecord>
+    ) -> ManifestUpdate {
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return ManifestUpdate::Newer
+        };
+        if self == previous {
+            ManifestUpdate::Replay
+        }
+        else if self.manifest_number > previous.manifest_number
+            && self.this_update > previous.this_update
+        {
+            ManifestUpdate::Newer
+        }
+        else {
+            ManifestUpdate::Rollback
+        }
+    }
+}
+
+
+//------------ ManifestUpdate ------------------------------------------------
+
+/// How a manifest relates to the manifest previously accepted.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ManifestUpdate {
+    /// Both manifest number and thisUpdate time have increased.
+    ///
+    /// This is also the classification for the first manifest seen.
+    Newer,
+
+    /// Manifest number and thisUpdate time are unchanged.
+    ///
+    /// This is most likely the same manifest again which can be used if
+    /// it isn’t stale yet.
+    Replay,
+
+    /// The manifest number or thisUpdate time went backwards or only one
+    /// of them increased.
+    ///
+    /// The manifest must not be used (RFC 9286, section 4.2.1).
+    Rollback,
 }
 
 
@@ -517,6 +689,58 @@ mod test {
         ).unwrap();
         assert!(obj.validate_at(&issuer, false, at).is_err());
     }
+
+    #[test]
+    fn manifest_history() {
+        use std::str::FromStr;
+
+        fn content(number: u64, this_update: Time) -> ManifestContent {
+            ManifestContent::new(
+                number.into(), this_update, this_update,
+                DigestAlgorithm::default(),
+                None::<FileAndHash<Bytes, Bytes>>.iter()
+            )
+        }
+
+        let uri = uri::Rsync::from_str(
+            "rsync://example.com/repo/ca.mft"
+        ).unwrap();
+        let early = Time::utc(2019, 4, 6, 0, 0, 0);
+        let late = Time::utc(2019, 4, 7, 0, 0, 0);
+        let mut history = ManifestHistory::new();
+
+        assert_eq!(history.classify(&uri, &content(2, early)),
+                   ManifestUpdate::Newer);
+        assert!(history.is_empty());
+        assert_eq!(history.update(uri.clone(), &content(2, early)),
+                   ManifestUpdate::Newer);
+        assert_eq!(history.update(uri.clone(), &content(2, early)),
+                   ManifestUpdate::Replay);
+        assert_eq!(history.update(uri.clone(), &content(1, late)),
+                   ManifestUpdate::Rollback);
+        assert_eq!(history.update(uri.clone(), &content(3, early)),
+                   ManifestUpdate::Rollback);
+        assert_eq!(history.update(uri.clone(), &content(2, late)),
+                   ManifestUpdate::Rollback);
+        assert_eq!(
+            history.get(&uri),
+            Some(&ManifestRecord::new(2u64.into(), early))
+        );
+        assert_eq!(history.update(uri.clone(), &content(3, late)),
+                   ManifestUpdate::Newer);
+
+        let serialized = serde_json::to_string(&history).unwrap();
+        let deser: ManifestHistory = serde_json::from_str(
+            &serialized
+        ).unwrap();
+        assert_eq!(deser.len(), 1);
+        assert_eq!(
+            deser.get(&uri),
+            Some(&ManifestRecord::new(3u64.into(), late))
+        );
+        assert_eq!(deser.classify(&uri, &content(2, late)),
+                   ManifestUpdate::Rollback);
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
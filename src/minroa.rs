@@ -0,0 +1,475 @@
+//! Max-length analysis and minimal ROAs.
+//!
+//! A ROA entry with a maximum length larger than its prefix length
+//! authorizes the origin AS to announce all more-specific prefixes up to
+//! that length. If not all of these are actually announced, an attacker
+//! can announce one of the remaining more-specifics with a forged AS path
+//! ending in the authorized origin and the route will be considered valid.
+//! [RFC 9319] therefore recommends to avoid the maximum length unless all
+//! the prefixes it covers are indeed announced and to instead use
+//! _minimal ROAs_ that only contain the prefixes actually announced.
+//!
+//! The type [`RoaAnalysis`] compares the entries of a set of ROAs – given
+//! either as [`Vrp`]s, ROAs, or ROA builders – with a list of
+//! [`Announcement`]s. It reports the state of each entry and can produce
+//! the content of minimal ROAs for all announcements that are currently
+//! valid.
+//!
+//! [`Announcement`]: struct.Announcement.html
+//! [`RoaAnalysis`]: struct.RoaAnalysis.html
+//! [`Vrp`]: ../vrp/struct.Vrp.html
+//! [RFC 9319]: https://tools.ietf.org/html/rfc9319
+
+use std::fmt;
+use std::collections::HashMap;
+use crate::resources::{AddressFamily, AsId, Prefix};
+use crate::roa::{RoaBuilder, RoaIpAddress, RouteOriginAttestation};
+use crate::vrp::{Vrp, VrpIndex};
+
+
+//------------ Announcement --------------------------------------------------
+
+/// A route announced in BGP.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Announcement {
+    /// The address family of the prefix.
+    family: AddressFamily,
+
+    /// The announced prefix.
+    prefix: Prefix,
+
+    /// The origin AS of the route.
+    origin: AsId,
+}
+
+impl Announcement {
+    /// Creates a new announcement from its components.
+    pub fn new(family: AddressFamily, prefix: Prefix, origin: AsId) -> Self {
+        Announcement { family, prefix, origin }
+    }
+
+    /// Returns the address family of the announced prefix.
+    pub fn family(&self) -> AddressFamily {
+        self.family
+    }
+
+    /// Returns the announced prefix.
+    pub fn prefix(&self) -> Prefix {
+        self.prefix
+    }
+
+    /// Returns the origin AS of the announcement.
+    pub fn origin(&self) -> AsId {
+        self.origin
+    }
+
+    /// Returns a key for sorting announcements.
+    fn sort_key(&self) -> (bool, u128, u8, u32) {
+        (
+            self.family == AddressFamily::Ipv6,
+            self.prefix.addr().to_bits(), self.prefix.addr_len(),
+            self.origin.into()
+        )
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for Announcement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family {
+            AddressFamily::Ipv4 => self.prefix.fmt_v4(f)?,
+            AddressFamily::Ipv6 => self.prefix.fmt_v6(f)?,
+        }
+        write!(f, " => {}", self.origin)
+    }
+}
+
+
+//------------ RoaAnalysis ---------------------------------------------------
+
+/// The result of comparing ROA entries with announced routes.
+#[derive(Clone, Debug)]
+pub struct RoaAnalysis {
+    /// The analysis of each ROA entry.
+    entries: Vec<EntryAnalysis>,
+
+    /// The announcements matched by at least one entry.
+    valid: Vec<Announcement>,
+
+    /// The announcements not matched by any entry.
+    unauthorized: Vec<Announcement>,
+}
+
+impl RoaAnalysis {
+    /// Analyses a set of VRPs against a set of announcements.
+    ///
+    /// Duplicate VRPs and announcements are only considered once.
+    pub fn new<V, A>(vrps: V, announcements: A) -> Self
+    where
+        V: IntoIterator<Item=Vrp>,
+        A: IntoIterator<Item=Announcement>
+    {
+        let index: VrpIndex = vrps.into_iter().collect();
+        let mut announcements: Vec<_> = announcements.into_iter().collect();
+        announcements.sort_unstable_by_key(Announcement::sort_key);
+        announcements.dedup();
+
+        let mut announced = HashMap::<Vrp, Vec<Prefix>>::new();
+        let mut valid = Vec::new();
+        let mut unauthorized = Vec::new();
+        for item in announcements {
+            let validation = index.validate(
+                item.family, item.prefix, Some(item.origin)
+            );
+            let mut matched = false;
+            for vrp in validation.matching() {
+                announced.entry(*vrp).or_default().push(item.prefix);
+                matched = true;
+            }
+            if matched {
+                valid.push(item)
+            }
+            else {
+                unauthorized.push(item)
+            }
+        }
+
+        let entries = index.iter().map(|vrp| {
+            EntryAnalysis::new(
+                *vrp, announced.remove(vrp).unwrap_or_default()
+            )
+        }).collect();
+
+        RoaAnalysis { entries, valid, unauthorized }
+    }
+
+    /// Analyses the entries of a set of ROAs.
+    pub fn from_roas<'a, R, A>(roas: R, announcements: A) -> Self
+    where
+        R: IntoIterator<Item=&'a RouteOriginAttestation>,
+        A: IntoIterator<Item=Announcement>
+    {
+        Self::new(roas.into_iter().flat_map(Vrp::iter_roa), announcements)
+    }
+
+    /// Analyses the entries of a set of ROA builders.
+    pub fn from_builders<'a, B, A>(builders: B, announcements: A) -> Self
+    where
+        B: IntoIterator<Item=&'a RoaBuilder>,
+        A: IntoIterator<Item=Announcement>
+    {
+        let roas: Vec<_> = builders.into_iter().map(|builder| {
+            builder.to_attestation()
+        }).collect();
+        Self::from_roas(&roas, announcements)
+    }
+
+    /// Returns the analysis of all ROA entries.
+    ///
+    /// The entries are ordered by prefix with IPv4 entries first.
+    pub fn entries(&self) -> &[EntryAnalysis] {
+        &self.entries
+    }
+
+    /// Returns an iterator over the entries with a loose maximum length.
+    pub fn loose(&self) -> impl Iterator<Item=&EntryAnalysis> {
+        self.entries.iter().filter(|entry| {
+            entry.state() == EntryState::Loose
+        })
+    }
+
+    /// Returns an iterator over the entries not matching any announcement.
+    pub fn unused(&self) -> impl Iterator<Item=&EntryAnalysis> {
+        self.entries.iter().filter(|entry| {
+            entry.state() == EntryState::Unused
+        })
+    }
+
+    /// Returns whether all entries are minimal.
+    pub fn is_minimal(&self) -> bool {
+        self.entries.iter().all(|entry| {
+            entry.state() == EntryState::Minimal
+        })
+    }
+
+    /// Returns the announcements that are valid under the analysed ROAs.
+    pub fn valid(&self) -> &[Announcement] {
+        &self.valid
+    }
+
+    /// Returns the announcements that are not valid under the ROAs.
+    ///
+    /// These are either invalid or not found and will not be covered by
+    /// the minimal ROAs.
+    pub fn unauthorized(&self) -> &[Announcement] {
+        &self.unauthorized
+    }
+
+    /// Returns the content of minimal ROAs for the valid announcements.
+    ///
+    /// There will be one builder for each origin AS, ordered by AS number.
+    /// Each builder contains exactly the valid announced prefixes of its
+    /// AS without a maximum length.
+    pub fn minimal_roas(&self) -> Vec<RoaBuilder> {
+        let mut valid: Vec<_> = self.valid.iter().collect();
+        valid.sort_by_key(|item| item.origin);
+        let mut res: Vec<RoaBuilder> = Vec::new();
+        for item in valid {
+            if res.last().map(|last| last.as_id()) != Some(item.origin) {
+                res.push(RoaBuilder::new(item.origin))
+            }
+            let builder = res.last_mut().unwrap();
+            let addr = RoaIpAddress::new(item.prefix, None);
+            match item.family {
+                AddressFamily::Ipv4 => builder.push_v4(addr),
+                AddressFamily::Ipv6 => builder.push_v6(addr),
+            }
+        }
+        res
+    }
+}
+
+
+//------------ EntryAnalysis -------------------------------------------------
+
+/// The analysis of a single ROA entry.
+#[derive(Clone, Debug)]
+pub struct EntryAnalysis {
+    /// The entry as a VRP.
+    vrp: Vrp,
+
+    /// The state of the entry.
+    state: EntryState,
+
+    /// The announced prefixes matched by the entry.
+    announced: Vec<Prefix>,
+}
+
+impl EntryAnalysis {
+    fn new(vrp: Vrp, announced: Vec<Prefix>) -> Self {
+        let state = if announced.is_empty() {
+            EntryState::Unused
+        }
+        else if authorized_count(&vrp) > announced.len() as u128 {
+            EntryState::Loose
+        }
+        else {
+            EntryState::Minimal
+        };
+        EntryAnalysis { vrp, state, announced }
+    }
+
+    /// Returns the entry as a VRP.
+    pub fn vrp(&self) -> Vrp {
+        self.vrp
+    }
+
+    /// Returns the state of the entry.
+    pub fn state(&self) -> EntryState {
+        self.state
+    }
+
+    /// Returns the announced prefixes matched by the entry.
+    ///
+    /// The prefixes are ordered by address and then prefix length.
+    pub fn announced(&self) -> &[Prefix] {
+        &self.announced
+    }
+
+    /// Returns the number of authorized prefixes that are not announced.
+    ///
+    /// The number saturates at `std::u128::MAX` for very loose entries.
+    pub fn unannounced_count(&self) -> u128 {
+        authorized_count(&self.vrp) - self.announced.len() as u128
+    }
+}
+
+
+//------------ EntryState ----------------------------------------------------
+
+/// The state of a ROA entry with respect to the announced routes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EntryState {
+    /// All prefixes authorized by the entry are announced.
+    Minimal,
+
+    /// The maximum length authorizes prefixes that are not announced.
+    Loose,
+
+    /// The entry does not match any announcement.
+    ///
+    /// This is always the case for entries for AS 0.
+    Unused,
+}
+
+
+//--- Display
+
+impl fmt::Display for EntryState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            EntryState::Minimal => "minimal",
+            EntryState::Loose => "loose",
+            EntryState::Unused => "unused",
+        })
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Returns the number of prefixes authorized by a VRP.
+///
+/// This is the number of prefixes covered by the VRP’s prefix with a
+/// length up to its maximum length, saturated at `std::u128::MAX`.
+fn authorized_count(vrp: &Vrp) -> u128 {
+    let len = vrp.prefix().addr_len();
+    if vrp.max_length() < len {
+        return 0
+    }
+    match 2u128.checked_pow(u32::from(vrp.max_length() - len) + 1) {
+        Some(count) => count - 1,
+        None => std::u128::MAX
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v4(asn: u32, prefix: &str, max_length: u8) -> Vrp {
+        Vrp::new(
+            asn.into(), AddressFamily::Ipv4,
+            Prefix::from_v4_str(prefix).unwrap(), max_length
+        )
+    }
+
+    fn route(prefix: &str, origin: u32) -> Announcement {
+        if prefix.contains(':') {
+            Announcement::new(
+                AddressFamily::Ipv6, Prefix::from_v6_str(prefix).unwrap(),
+                origin.into()
+            )
+        }
+        else {
+            Announcement::new(
+                AddressFamily::Ipv4, Prefix::from_v4_str(prefix).unwrap(),
+                origin.into()
+            )
+        }
+    }
+
+    #[test]
+    fn entry_states() {
+        let analysis = RoaAnalysis::new(
+            vec![
+                v4(64496, "192.0.2.0/24", 24),
+                v4(64497, "198.51.100.0/24", 25),
+                v4(64498, "203.0.113.0/24", 25),
+                v4(64499, "10.0.0.0/8", 24),
+                v4(0, "172.16.0.0/12", 12),
+            ],
+            vec![
+                route("192.0.2.0/24", 64496),
+                route("192.0.2.0/24", 64496),
+                route("198.51.100.0/24", 64497),
+                route("198.51.100.0/25", 64497),
+                route("203.0.113.0/24", 64498),
+                route("203.0.113.0/25", 64498),
+                route("203.0.113.128/25", 64498),
+                route("172.16.0.0/12", 64500),
+            ]
+        );
+        let states: Vec<_> = analysis.entries().iter().map(|entry| {
+            (entry.vrp(), entry.state())
+        }).collect();
+        assert_eq!(
+            states,
+            vec![
+                (v4(64499, "10.0.0.0/8", 24), EntryState::Unused),
+                (v4(0, "172.16.0.0/12", 12), EntryState::Unused),
+                (v4(64496, "192.0.2.0/24", 24), EntryState::Minimal),
+                (v4(64497, "198.51.100.0/24", 25), EntryState::Loose),
+                (v4(64498, "203.0.113.0/24", 25), EntryState::Minimal),
+            ]
+        );
+        assert!(!analysis.is_minimal());
+
+        let loose: Vec<_> = analysis.loose().collect();
+        assert_eq!(loose.len(), 1);
+        assert_eq!(loose[0].unannounced_count(), 1);
+        assert_eq!(
+            loose[0].announced(),
+            &[
+                Prefix::from_v4_str("198.51.100.0/24").unwrap(),
+                Prefix::from_v4_str("198.51.100.0/25").unwrap(),
+            ]
+        );
+        assert_eq!(analysis.unused().count(), 2);
+        assert_eq!(
+            analysis.entries()[0].unannounced_count(), (1 << 17) - 1
+        );
+        assert_eq!(analysis.valid().len(), 6);
+        assert_eq!(
+            analysis.unauthorized(), &[route("172.16.0.0/12", 64500)]
+        );
+    }
+
+    #[test]
+    fn minimal_roas() {
+        let mut builder = RoaBuilder::new(64496.into());
+        builder.push_v4_addr("192.0.2.0".parse().unwrap(), 24, Some(26));
+        builder.push_v6_addr("2001:db8::".parse().unwrap(), 32, Some(48));
+        let analysis = RoaAnalysis::from_builders(
+            &[builder],
+            vec![
+                route("192.0.2.0/24", 64496),
+                route("192.0.2.64/26", 64496),
+                route("192.0.2.128/25", 64497),
+                route("2001:db8:1::/48", 64496),
+            ]
+        );
+        assert_eq!(analysis.loose().count(), 2);
+        assert_eq!(
+            analysis.unauthorized(), &[route("192.0.2.128/25", 64497)]
+        );
+
+        let minimal = analysis.minimal_roas();
+        assert_eq!(minimal.len(), 1);
+        assert_eq!(minimal[0].as_id(), 64496.into());
+        let vrps: Vec<_> = Vrp::iter_roa(
+            &minimal[0].to_attestation()
+        ).collect();
+        assert_eq!(
+            vrps,
+            vec![
+                v4(64496, "192.0.2.0/24", 24),
+                v4(64496, "192.0.2.64/26", 26),
+                Vrp::new(
+                    64496.into(), AddressFamily::Ipv6,
+                    Prefix::from_v6_str("2001:db8:1::/48").unwrap(), 48
+                ),
+            ]
+        );
+
+        let analysis = RoaAnalysis::from_roas(
+            &[minimal[0].to_attestation()], analysis.valid().iter().cloned()
+        );
+        assert!(analysis.is_minimal());
+    }
+
+    #[test]
+    fn saturated_count() {
+        let vrp = Vrp::new(
+            64496.into(), AddressFamily::Ipv6,
+            Prefix::from_v6_str("::/0").unwrap(), 128
+        );
+        assert_eq!(authorized_count(&vrp), std::u128::MAX);
+        assert_eq!(authorized_count(&v4(64496, "10.0.0.0/8", 7)), 0);
+    }
+}
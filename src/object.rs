@@ -0,0 +1,348 @@
+//! Decoding RPKI objects of any type.
+//!
+//! Objects in an RPKI repository are either certificates, certificate
+//! revocation lists, or signed objects whose type is determined by the
+//! eContentType of their CMS structure. When walking a repository, the
+//! type of an object is typically derived from its file name’s extension
+//! only.
+//!
+//! This module provides the type [`RpkiObject`] that determines the type
+//! of an object from its encoded structure and decodes it into the right
+//! type. Optionally, it can also check that the object type matches a
+//! given file name’s extension.
+//!
+//! [`RpkiObject`]: enum.RpkiObject.html
+
+use std::{error, fmt};
+use bcder::{decode, Mode, Oid, Tag};
+use bytes::Bytes;
+use crate::oid;
+use crate::aspa::Aspa;
+use crate::cert::Cert;
+use crate::crl::Crl;
+use crate::gbr::Gbr;
+use crate::manifest::Manifest;
+use crate::pubpoint::FileType;
+use crate::roa::Roa;
+
+
+//------------ RpkiObject ----------------------------------------------------
+
+/// An RPKI object of any of the supported types.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum RpkiObject {
+    /// A resource or router certificate.
+    Cert(Cert),
+
+    /// A certificate revocation list.
+    Crl(Crl),
+
+    /// A manifest.
+    Manifest(Manifest),
+
+    /// A route origin authorization.
+    Roa(Roa),
+
+    /// A Ghostbusters record.
+    Gbr(Gbr),
+
+    /// An autonomous system provider authorization.
+    Aspa(Aspa),
+}
+
+impl RpkiObject {
+    /// Decodes an object of unknown type.
+    ///
+    /// The type is determined by the structure of the encoded object: a
+    /// certificate and a CRL are distinguished by the content of their
+    /// to-be-signed part, while for signed objects the eContentType of the
+    /// CMS structure determines the type. If the eContentType is not one
+    /// of the supported types, `ObjectError::UnknownContentType` is
+    /// returned.
+    pub fn decode(bytes: Bytes, strict: bool) -> Result<Self, ObjectError> {
+        match Structure::sniff(bytes.clone())? {
+            Structure::Cert => {
+                Ok(RpkiObject::Cert(Cert::decode(bytes)?))
+            }
+            Structure::Crl => {
+                Ok(RpkiObject::Crl(Crl::decode(bytes)?))
+            }
+            Structure::Cms(content_type) => {
+                if content_type == oid::CT_RPKI_MANIFEST {
+                    Ok(RpkiObject::Manifest(Manifest::decode(bytes, strict)?))
+                }
+                else if content_type == oid::ROUTE_ORIGIN_AUTHZ {
+                    Ok(RpkiObject::Roa(Roa::decode(bytes, strict)?))
+                }
+                else if content_type == oid::CT_RPKI_GHOSTBUSTERS {
+                    Ok(RpkiObject::Gbr(Gbr::decode(bytes, strict)?))
+                }
+                else if content_type == oid::CT_ASPA {
+                    Ok(RpkiObject::Aspa(Aspa::decode(bytes, strict)?))
+                }
+                else {
+                    Err(ObjectError::UnknownContentType(content_type))
+                }
+            }
+        }
+    }
+
+    /// Decodes an object and checks it against its file name.
+    ///
+    /// This is the same as `decode` but additionally checks that the
+    /// extension of `name` is the one registered for the object’s type.
+    pub fn decode_file(
+        name: &str,
+        bytes: Bytes,
+        strict: bool
+    ) -> Result<Self, ObjectError> {
+        let res = Self::decode(bytes, strict)?;
+        res.check_extension(name)?;
+        Ok(res)
+    }
+
+    /// Returns the file type for the object.
+    pub fn file_type(&self) -> FileType {
+        match *self {
+            RpkiObject::Cert(_) => FileType::Cer,
+            RpkiObject::Crl(_) => FileType::Crl,
+            RpkiObject::Manifest(_) => FileType::Mft,
+            RpkiObject::Roa(_) => FileType::Roa,
+            RpkiObject::Gbr(_) => FileType::Gbr,
+            RpkiObject::Aspa(_) => FileType::Asa,
+        }
+    }
+
+    /// Checks that the extension of `name` fits the object’s type.
+    pub fn check_extension(&self, name: &str) -> Result<(), ObjectError> {
+        let expected = self.file_type();
+        if FileType::from_name(name) == expected {
+            Ok(())
+        }
+        else {
+            Err(ObjectError::ExtensionMismatch(expected))
+        }
+    }
+}
+
+
+//------------ Structure -----------------------------------------------------
+
+/// The basic structure of an encoded object.
+enum Structure {
+    /// The object is a certificate.
+    Cert,
+
+    /// The object is a certificate revocation list.
+    Crl,
+
+    /// The object is CMS signed data with the given eContentType.
+    Cms(Oid<Bytes>),
+}
+
+impl Structure {
+    /// Determines the structure of an encoded object.
+    ///
+    /// Certificates and CRLs both are a sequence of the to-be-signed part,
+    /// the signature algorithm, and the signature. The to-be-signed part
+    /// of a certificate starts with the explicitly tagged version, that of
+    /// a CRL with the version as a plain integer. A CMS object is a
+    /// sequence of the content type, which needs to be signed data, and
+    /// the signed data itself which contains the eContentType.
+    fn sniff(bytes: Bytes) -> Result<Self, decode::Error> {
+        Mode::Ber.decode(bytes, |cons| {
+            cons.take_sequence(|cons| {
+                let content_type = match Oid::take_opt_from(cons)? {
+                    Some(content_type) => content_type,
+                    None => {
+                        let res = cons.take_sequence(|cons| {
+                            let res = match cons.take_opt_constructed_if(
+                                Tag::CTX_0, skip_values
+                            )? {
+                                Some(()) => Structure::Cert,
+                                None => Structure::Crl,
+                            };
+                            skip_values(cons)?;
+                            Ok(res)
+                        })?;
+                        skip_values(cons)?;
+                        return Ok(res)
+                    }
+                };
+                if content_type != oid::SIGNED_DATA {
+                    return Err(decode::Malformed)
+                }
+                cons.take_constructed_if(Tag::CTX_0, |cons| {
+                    cons.take_sequence(|cons| {
+                        cons.take_value(skip_content)?; // version
+                        cons.take_value(skip_content)?; // digestAlgorithms
+                        let res = cons.take_sequence(|cons| {
+                            let res = Oid::take_from(cons)?;
+                            skip_values(cons)?;
+                            Ok(res)
+                        })?;
+                        skip_values(cons)?;
+                        Ok(Structure::Cms(res))
+                    })
+                })
+            })
+        })
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Skips over the content of a value.
+//
+//  This walks the value explicitly rather than using
+//  `Constructed::skip_all` which loses track of the end of nested
+//  definite-length values in some cases.
+fn skip_content<S: decode::Source>(
+    _tag: Tag,
+    content: &mut decode::Content<S>
+) -> Result<(), S::Err> {
+    match *content {
+        decode::Content::Primitive(ref mut prim) => prim.skip_all(),
+        decode::Content::Constructed(ref mut cons) => skip_values(cons),
+    }
+}
+
+/// Skips over all remaining values of a constructed value.
+fn skip_values<S: decode::Source>(
+    cons: &mut decode::Constructed<S>
+) -> Result<(), S::Err> {
+    while let Some(()) = cons.take_opt_value(skip_content)? { }
+    Ok(())
+}
+
+
+//------------ ObjectError ---------------------------------------------------
+
+/// An error happened while decoding an object.
+#[derive(Debug)]
+pub enum ObjectError {
+    /// The object is not correctly encoded.
+    Malformed(decode::Error),
+
+    /// The object is a signed object of an unsupported type.
+    UnknownContentType(Oid<Bytes>),
+
+    /// The file extension doesn’t match the object type.
+    ///
+    /// The value is the type of the decoded object.
+    ExtensionMismatch(FileType),
+}
+
+impl From<decode::Error> for ObjectError {
+    fn from(err: decode::Error) -> Self {
+        ObjectError::Malformed(err)
+    }
+}
+
+impl fmt::Display for ObjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ObjectError::Malformed(ref err) => {
+                write!(f, "malformed object: {}", err)
+            }
+            ObjectError::UnknownContentType(ref oid) => {
+                write!(f, "unknown content type {}", oid)
+            }
+            ObjectError::ExtensionMismatch(file_type) => {
+                match file_type.extension() {
+                    Some(ext) => {
+                        write!(f, "file extension should be .{}", ext)
+                    }
+                    None => f.write_str("file extension mismatch")
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for ObjectError { }
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(data: &'static [u8]) -> RpkiObject {
+        RpkiObject::decode(Bytes::from_static(data), false).unwrap()
+    }
+
+    #[test]
+    fn decode_test_data() {
+        match decode(include_bytes!("../test-data/ta.cer")) {
+            RpkiObject::Cert(_) => { }
+            obj => panic!("expected certificate, got {:?}", obj),
+        }
+        match decode(include_bytes!("../test-data/ta.crl")) {
+            RpkiObject::Crl(_) => { }
+            obj => panic!("expected CRL, got {:?}", obj),
+        }
+        match decode(include_bytes!("../test-data/ta.mft")) {
+            RpkiObject::Manifest(_) => { }
+            obj => panic!("expected manifest, got {:?}", obj),
+        }
+        match decode(include_bytes!("../test-data/example-ripe.roa")) {
+            RpkiObject::Roa(_) => { }
+            obj => panic!("expected ROA, got {:?}", obj),
+        }
+    }
+
+    #[test]
+    fn check_extension() {
+        let data = Bytes::from_static(include_bytes!("../test-data/ta.mft"));
+        RpkiObject::decode_file("ta.mft", data.clone(), false).unwrap();
+        for name in &["ta.roa", "ta"] {
+            match RpkiObject::decode_file(name, data.clone(), false) {
+                Err(ObjectError::ExtensionMismatch(FileType::Mft)) => { }
+                res => panic!("unexpected result for {}: {:?}", name, res),
+            }
+        }
+    }
+
+    #[test]
+    fn reject_garbage() {
+        for data in &[
+            b"\x30\x03\x02\x01".as_ref(),
+            include_bytes!("../test-data/drl-csr.der").as_ref(),
+        ] {
+            match RpkiObject::decode(Bytes::from_static(data), false) {
+                Err(ObjectError::Malformed(_)) => { }
+                res => panic!("expected malformed object, got {:?}", res),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use bcder::encode::Values;
+    use crate::crypto::{PublicKeyFormat, Signer};
+    use crate::crypto::softsigner::OpenSslSigner;
+    use crate::protocms::ProtocolCmsBuilder;
+    use crate::x509::Validity;
+    use super::*;
+
+    #[test]
+    fn unknown_content_type() {
+        let mut signer = OpenSslSigner::new();
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let msg = ProtocolCmsBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        ).finalize(Bytes::from_static(b"<msg/>"), &signer, &key).unwrap();
+        let msg = msg.encode_ref().to_captured(Mode::Der).into_bytes();
+        match RpkiObject::decode(msg, false) {
+            Err(ObjectError::UnknownContentType(oid)) => {
+                assert_eq!(oid, oid::PROTOCOL_CONTENT_TYPE)
+            }
+            _ => panic!("protocol message accepted")
+        }
+    }
+}
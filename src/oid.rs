@@ -25,6 +25,25 @@ pub const RSA_ENCRYPTION: ConstOid
 pub const SHA256_WITH_RSA_ENCRYPTION: ConstOid
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 1, 11]);
 
+/// [RFC 5480](https://tools.ietf.org/html/rfc5480) `id-ecPublicKey`
+///
+/// Identifies an elliptic curve public key. The curve is given by the
+/// parameters of the algorithm identifier.
+pub const EC_PUBLIC_KEY: ConstOid
+    = Oid(&[42, 134, 72, 206, 61, 2, 1]);
+
+/// [RFC 5480](https://tools.ietf.org/html/rfc5480) `secp256r1`
+///
+/// Identifies the NIST P-256 elliptic curve.
+pub const SECP256R1: ConstOid
+    = Oid(&[42, 134, 72, 206, 61, 3, 1, 7]);
+
+/// [RFC 5758](https://tools.ietf.org/html/rfc5758) `ecdsa-with-SHA256`
+///
+/// Identifies the ECDSA signature algorithm with SHA-256.
+pub const ECDSA_WITH_SHA256: ConstOid
+    = Oid(&[42, 134, 72, 206, 61, 4, 3, 2]);
+
 
 pub const SIGNED_DATA: Oid<&[u8]>
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 7, 2]);
@@ -61,9 +80,15 @@ pub const CE_SUBJECT_KEY_IDENTIFIER: Oid<&[u8]> = Oid(&[85, 29, 14]);
 pub const CP_IPADDR_ASNUMBER: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 14, 2]);
 pub const CP_IPADDR_ASNUMBER_V2: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 14, 3]);
 
+pub const CT_ASPA: ConstOid
+    = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 49]);
+pub const CT_RPKI_GHOSTBUSTERS: ConstOid
+    = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 35]);
 pub const CT_RPKI_MANIFEST: ConstOid
     = Oid(&[42, 134, 72, 134, 247, 13, 1, 9, 16, 1, 26]);
 
+pub const KP_BGPSEC_ROUTER: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 3, 30]);
+
 pub const PE_AUTHORITY_INFO_ACCESS: Oid<&[u8]>
     = Oid(&[43, 6, 1, 5, 5, 7, 1, 1]);
 pub const PE_IP_ADDR_BLOCK: Oid<&[u8]> = Oid(&[43, 6, 1, 5, 5, 7, 1, 7]);
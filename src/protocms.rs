@@ -0,0 +1,582 @@
+//! CMS wrapping for protocol messages.
+//!
+//! The messages of the provisioning protocol defined in RFC 6492 and the
+//! publication protocol defined in RFC 8181 are exchanged as CMS signed
+//! data. This is similar to RPKI signed objects but the profile differs:
+//! the content type is the dedicated protocol content type, the EE
+//! certificate is a BPKI identity certificate rather than a resource
+//! certificate, and a CRL of the issuer may be included.
+//!
+//! The profile is defined in section 3.1 of RFC 6492 and referenced by
+//! RFC 8181. This module implements it via [`ProtocolCms`] for parsing
+//! and validating and [`ProtocolCmsBuilder`] for creating messages.
+//!
+//! [`ProtocolCms`]: struct.ProtocolCms.html
+//! [`ProtocolCmsBuilder`]: struct.ProtocolCmsBuilder.html
+
+use bcder::{decode, encode};
+use bcder::{Mode, OctetString, Oid, Tag};
+use bcder::encode::{PrimitiveContent, Values};
+use bytes::Bytes;
+use crate::oid;
+use crate::crl::Crl;
+use crate::crypto::{
+    DigestAlgorithm, KeyIdentifier, Signature, SignatureAlgorithm, Signer,
+    SigningError
+};
+use crate::idcert::{IdCert, IdCertBuilder};
+use crate::sigobj::{MessageDigest, SignedAttrs};
+use crate::x509::{
+    Name, Serial, Time, ValidationError, ValidationErrorKind, Validity
+};
+
+
+//------------ ProtocolCms ---------------------------------------------------
+
+/// A CMS signed protocol message.
+///
+/// The content of the message is kept as is. Normally, it will be the XML
+/// of an up-down or publication protocol message.
+#[derive(Clone, Debug)]
+pub struct ProtocolCms {
+    //--- From SignedData
+    //
+    digest_algorithm: DigestAlgorithm,
+    content: OctetString,
+    cert: IdCert,
+    crl: Option<Crl>,
+
+    //--- From SignerInfo
+    //
+    sid: KeyIdentifier,
+    signed_attrs: SignedAttrs,
+    signature: Signature,
+
+    //--- SignedAttributes
+    //
+    message_digest: MessageDigest,
+    signing_time: Option<Time>,
+    binary_signing_time: Option<u64>,
+}
+
+/// # Data Access
+///
+impl ProtocolCms {
+    /// Returns a reference to the message’s content.
+    pub fn content(&self) -> &OctetString {
+        &self.content
+    }
+
+    /// Returns the message’s content as a bytes value.
+    ///
+    /// This is the raw XML of the wrapped message.
+    pub fn to_bytes(&self) -> Bytes {
+        self.content.to_bytes()
+    }
+
+    /// Returns a reference to the EE certificate the message is signed with.
+    pub fn cert(&self) -> &IdCert {
+        &self.cert
+    }
+
+    /// Returns a reference to the CRL included in the message if present.
+    pub fn crl(&self) -> Option<&Crl> {
+        self.crl.as_ref()
+    }
+
+    /// Returns the signing time if present.
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
+
+    /// Returns the binary signing time if present.
+    pub fn binary_signing_time(&self) -> Option<u64> {
+        self.binary_signing_time
+    }
+}
+
+/// # Decoding, Validation, and Encoding
+///
+impl ProtocolCms {
+    /// Decodes a protocol message from the given source.
+    ///
+    /// If `strict` is `false`, BER encoding is accepted, too.
+    pub fn decode<S: decode::Source>(
+        source: S,
+        strict: bool
+    ) -> Result<Self, S::Err> {
+        if strict { Mode::Der }
+        else { Mode::Ber }
+            .decode(source, Self::take_from)
+    }
+
+    /// Takes a protocol message from an encoded constructed value.
+    pub fn take_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Self, S::Err> {
+        cons.take_sequence(|cons| { // ContentInfo
+            oid::SIGNED_DATA.skip_if(cons)?; // contentType
+            cons.take_constructed_if(Tag::CTX_0, |cons| { // content
+                cons.take_sequence(|cons| { // SignedData
+                    cons.skip_u8_if(3)?; // version -- must be 3
+                    let digest_algorithm =
+                        DigestAlgorithm::take_set_from(cons)?;
+                    let content = {
+                        cons.take_sequence(|cons| { // encapContentInfo
+                            oid::PROTOCOL_CONTENT_TYPE.skip_if(cons)?;
+                            cons.take_constructed_if(
+                                Tag::CTX_0,
+                                OctetString::take_from
+                            )
+                        })?
+                    };
+                    let cert = cons.take_constructed_if( // certificates
+                        Tag::CTX_0,
+                        IdCert::take_from
+                    )?;
+                    let crl = cons.take_opt_constructed_if( // crls
+                        Tag::CTX_1,
+                        Crl::take_from
+                    )?;
+                    let (sid, attrs, signature) = { // signerInfos
+                        cons.take_set(|cons| {
+                            cons.take_sequence(|cons| {
+                                cons.skip_u8_if(3)?;
+                                let sid = cons.take_value_if(
+                                    Tag::CTX_0, |content| {
+                                        KeyIdentifier::from_content(content)
+                                    }
+                                )?;
+                                let alg = DigestAlgorithm::take_from(cons)?;
+                                if alg != digest_algorithm {
+                                    return Err(decode::Malformed.into())
+                                }
+                                let attrs =
+                                    SignedAttrs::take_from_signed_message(
+                                        cons
+                                    )?;
+                                if attrs.2 != oid::PROTOCOL_CONTENT_TYPE {
+                                    return Err(decode::Malformed.into())
+                                }
+                                let signature = Signature::new(
+                                    SignatureAlgorithm::cms_take_from(cons)?,
+                                    OctetString::take_from(cons)?.into_bytes()
+                                );
+                                // no unsignedAttributes
+                                Ok((sid, attrs, signature))
+                            })
+                        })?
+                    };
+                    Ok(Self {
+                        digest_algorithm,
+                        content,
+                        cert,
+                        crl,
+                        sid,
+                        signed_attrs: attrs.0,
+                        signature,
+                        message_digest: attrs.1,
+                        signing_time: attrs.3,
+                        binary_signing_time: attrs.4
+                    })
+                })
+            })
+        })
+    }
+
+    /// Validates the message against the given BPKI issuer certificate.
+    ///
+    /// The EE certificate included in the message must have been issued by
+    /// `issuer` and, if a CRL is included, it must have been issued by
+    /// `issuer`, too, and must not list the EE certificate.
+    pub fn validate(&self, issuer: &IdCert) -> Result<(), ValidationError> {
+        self.validate_at(issuer, Time::now())
+    }
+
+    /// Validates the message at the given time.
+    pub fn validate_at(
+        &self,
+        issuer: &IdCert,
+        now: Time,
+    ) -> Result<(), ValidationError> {
+        self.verify_signature()?;
+        self.cert.validate_ee_at(issuer, now)?;
+        self.verify_crl(issuer)
+    }
+
+    /// Verifies the signature of the message against the EE certificate.
+    fn verify_signature(&self) -> Result<(), ValidationError> {
+        if self.sid != self.cert.subject_key_identifier() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignerIdentifierMismatch
+            ).with_rfc(6492, "3.1.1.7.1"))
+        }
+        let digest = {
+            let mut context = self.digest_algorithm.start();
+            self.content.iter().for_each(|x| context.update(x));
+            context.finish()
+        };
+        if digest.as_ref() != self.message_digest.as_ref() {
+            return Err(ValidationError::new(
+                ValidationErrorKind::MessageDigestMismatch
+            ).with_rfc(6492, "3.1.1.7.4"))
+        }
+        let msg = self.signed_attrs.encode_verify();
+        self.cert.subject_public_key_info().verify(
+            &msg,
+            &self.signature
+        ).map_err(Into::into)
+    }
+
+    /// Verifies the included CRL, if any, and checks the EE certificate.
+    fn verify_crl(&self, issuer: &IdCert) -> Result<(), ValidationError> {
+        let crl = match self.crl {
+            Some(ref crl) => crl,
+            None => return Ok(())
+        };
+        if *crl.authority_key_identifier() != issuer.subject_key_identifier()
+        {
+            return Err(ValidationError::new(
+                ValidationErrorKind::AuthorityKeyIdentifierMismatch
+            ))
+        }
+        crl.validate(issuer.subject_public_key_info())?;
+        if crl.contains(self.cert.serial_number()) {
+            return Err(ValidationError::new(ValidationErrorKind::Revoked))
+        }
+        Ok(())
+    }
+
+    /// Returns a value encoder for a reference to a protocol message.
+    pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
+        encode::sequence((
+            oid::SIGNED_DATA.encode(), // contentType
+            encode::sequence_as(Tag::CTX_0, // content
+                encode::sequence((
+                    3u8.encode(), // version
+                    self.digest_algorithm.encode_set(), // digestAlgorithms
+                    encode::sequence(( // encapContentInfo
+                        oid::PROTOCOL_CONTENT_TYPE.encode(),
+                        encode::sequence_as(Tag::CTX_0,
+                            self.content.encode_ref()
+                        ),
+                    )),
+                    encode::sequence_as(Tag::CTX_0, // certificates
+                        self.cert.encode_ref(),
+                    ),
+                    self.crl.as_ref().map(|crl| {
+                        encode::sequence_as(Tag::CTX_1, // crls
+                            crl.encode_ref()
+                        )
+                    }),
+                    encode::set( // signerInfo
+                        encode::sequence(( // SignerInfo
+                            3u8.encode(), // version
+                            self.sid.encode_ref_as(Tag::CTX_0),
+                            self.digest_algorithm.encode(), // digestAlgorithm
+                            self.signed_attrs.encode_ref(), // signedAttrs
+                            self.signature.algorithm().cms_encode(),
+                                                        // signatureAlgorithm
+                            OctetString::encode_slice( // signature
+                                self.signature.value().as_ref()
+                            ),
+                            // unsignedAttrs omitted
+                        ))
+                    )
+                ))
+            )
+        ))
+    }
+
+    /// Returns the DER encoding of the message.
+    pub fn to_bytes_der(&self) -> Bytes {
+        self.encode_ref().to_captured(Mode::Der).into_bytes()
+    }
+}
+
+
+//------------ ProtocolCmsBuilder --------------------------------------------
+
+/// A builder for protocol messages.
+///
+/// Each message is signed with a one-off key. The EE certificate for that
+/// key is issued by the BPKI identity key given to `finalize`.
+#[derive(Clone, Debug)]
+pub struct ProtocolCmsBuilder {
+    /// The digest algorithm to be used for the message digest attribute.
+    ///
+    /// By default, this will be the default algorithm.
+    digest_algorithm: DigestAlgorithm,
+
+    /// The serial number of the EE certificate.
+    ///
+    /// Must be provided.
+    serial_number: Serial,
+
+    /// The validity of the EE certificate.
+    ///
+    /// Must be provided.
+    validity: Validity,
+
+    /// The issuer name of the EE certificate.
+    ///
+    /// If this is `None` (the default), it will be generated from the
+    /// issuer’s public key.
+    issuer: Option<Name>,
+
+    /// The subject name of the EE certificate.
+    ///
+    /// If this is `None` (the default), it will be generated from the key
+    /// identifier of the EE certificate’s key.
+    subject: Option<Name>,
+
+    /// The CRL of the issuer to be included.
+    ///
+    /// Defaults to none.
+    crl: Option<Crl>,
+
+    /// The signing time attribute of the message.
+    ///
+    /// RFC 6492 requires this attribute, so it defaults to the time the
+    /// builder was created.
+    signing_time: Option<Time>,
+
+    /// The binary signing time attribute of the message.
+    ///
+    /// This is optional and by default omitted.
+    binary_signing_time: Option<u64>,
+}
+
+impl ProtocolCmsBuilder {
+    /// Creates a new builder.
+    pub fn new(serial_number: Serial, validity: Validity) -> Self {
+        Self {
+            digest_algorithm: DigestAlgorithm::default(),
+            serial_number,
+            validity,
+            issuer: None,
+            subject: None,
+            crl: None,
+            signing_time: Some(Time::now()),
+            binary_signing_time: None,
+        }
+    }
+
+    pub fn digest_algorithm(&self) -> DigestAlgorithm {
+        self.digest_algorithm
+    }
+
+    pub fn set_digest_algorithm(&mut self, algorithm: DigestAlgorithm) {
+        self.digest_algorithm = algorithm
+    }
+
+    pub fn serial_number(&self) -> Serial {
+        self.serial_number
+    }
+
+    pub fn set_serial_number(&mut self, serial: Serial) {
+        self.serial_number = serial
+    }
+
+    pub fn validity(&self) -> Validity {
+        self.validity
+    }
+
+    pub fn set_validity(&mut self, validity: Validity) {
+        self.validity = validity
+    }
+
+    pub fn issuer(&self) -> Option<&Name> {
+        self.issuer.as_ref()
+    }
+
+    pub fn set_issuer(&mut self, name: Option<Name>) {
+        self.issuer = name
+    }
+
+    pub fn subject(&self) -> Option<&Name> {
+        self.subject.as_ref()
+    }
+
+    pub fn set_subject(&mut self, name: Option<Name>) {
+        self.subject = name
+    }
+
+    /// Returns the CRL to be included.
+    pub fn crl(&self) -> Option<&Crl> {
+        self.crl.as_ref()
+    }
+
+    /// Sets the CRL to be included.
+    pub fn set_crl(&mut self, crl: Option<Crl>) {
+        self.crl = crl
+    }
+
+    /// Returns the signing time attribute.
+    pub fn signing_time(&self) -> Option<Time> {
+        self.signing_time
+    }
+
+    /// Sets the signing time attribute.
+    pub fn set_signing_time(&mut self, signing_time: Option<Time>) {
+        self.signing_time = signing_time
+    }
+
+    /// Returns the binary signing time attribute.
+    pub fn binary_signing_time(&self) -> Option<u64> {
+        self.binary_signing_time
+    }
+
+    /// Sets the binary signing time attribute.
+    pub fn set_binary_signing_time(&mut self, time: Option<u64>) {
+        self.binary_signing_time = time
+    }
+
+    /// Signs `content` and produces the protocol message.
+    ///
+    /// The EE certificate is issued by `issuer_key` which should be the
+    /// key of the BPKI identity certificate the receiver knows.
+    pub fn finalize<S: Signer>(
+        self,
+        content: Bytes,
+        signer: &S,
+        issuer_key: &S::KeyId,
+    ) -> Result<ProtocolCms, SigningError<S::Error>> {
+        let content_type = Oid(oid::PROTOCOL_CONTENT_TYPE.0.into());
+
+        // Produce signed attributes.
+        let message_digest = self.digest_algorithm.digest(&content).into();
+        let signed_attrs = SignedAttrs::new(
+            &content_type,
+            &message_digest,
+            self.signing_time,
+            self.binary_signing_time
+        );
+
+        // Sign signed attributes with a one-off key.
+        let (signature, key_info) = signer.sign_one_off(
+            SignatureAlgorithm::default(), &signed_attrs.encode_verify()
+        )?;
+        let sid = KeyIdentifier::from_public_key(&key_info);
+
+        // Make the certificate.
+        let mut cert = IdCertBuilder::new(self.serial_number, self.validity);
+        cert.set_issuer(self.issuer);
+        cert.set_subject(self.subject);
+        let cert = cert.build_ee(key_info, signer, issuer_key)?;
+
+        Ok(ProtocolCms {
+            digest_algorithm: self.digest_algorithm,
+            content: OctetString::new(content),
+            cert,
+            crl: self.crl,
+            sid,
+            signed_attrs,
+            signature,
+            message_digest,
+            signing_time: self.signing_time,
+            binary_signing_time: self.binary_signing_time,
+        })
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use crate::crl::{CrlEntry, TbsCertList};
+    use crate::crypto::PublicKeyFormat;
+    use crate::crypto::softsigner::{KeyId, OpenSslSigner};
+    use crate::publication::{Message, Query};
+    use super::*;
+
+    fn make_issuer(signer: &mut OpenSslSigner) -> (IdCert, KeyId) {
+        let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+        let cert = IdCertBuilder::new(
+            1u64.into(), Validity::from_secs(86400)
+        ).build_ta(&*signer, &key).unwrap();
+        (cert, key)
+    }
+
+    fn make_crl(
+        signer: &OpenSslSigner,
+        key: &KeyId,
+        issuer: &IdCert,
+        revoked: Vec<CrlEntry>,
+    ) -> Crl {
+        TbsCertList::new(
+            SignatureAlgorithm::default(),
+            issuer.subject().clone(),
+            Time::now(),
+            Time::tomorrow(),
+            revoked,
+            issuer.subject_key_identifier(),
+            1u64.into()
+        ).into_crl(signer, key).unwrap()
+    }
+
+    fn xml() -> Bytes {
+        let mut xml = Vec::new();
+        Message::Query(Query::List).write_xml(&mut xml).unwrap();
+        xml.into()
+    }
+
+    #[test]
+    fn encode_and_validate() {
+        let mut signer = OpenSslSigner::new();
+        let (issuer, key) = make_issuer(&mut signer);
+
+        let mut builder = ProtocolCmsBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        );
+        builder.set_crl(Some(make_crl(&signer, &key, &issuer, Vec::new())));
+        let msg = builder.finalize(xml(), &signer, &key).unwrap();
+        let msg = msg.encode_ref().to_captured(Mode::Der);
+
+        let msg = ProtocolCms::decode(msg.as_slice(), true).unwrap();
+        msg.validate(&issuer).unwrap();
+        assert!(msg.crl().is_some());
+        assert_eq!(
+            Message::parse(msg.to_bytes().as_ref()).unwrap(),
+            Message::Query(Query::List)
+        );
+
+        // A different issuer must fail.
+        let (other, _) = make_issuer(&mut signer);
+        assert!(msg.validate(&other).is_err());
+    }
+
+    #[test]
+    fn encode_without_crl() {
+        let mut signer = OpenSslSigner::new();
+        let (issuer, key) = make_issuer(&mut signer);
+        let msg = ProtocolCmsBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        ).finalize(xml(), &signer, &key).unwrap();
+        let msg = ProtocolCms::decode(msg.to_bytes_der().as_ref(), true)
+            .unwrap();
+        assert!(msg.crl().is_none());
+        msg.validate(&issuer).unwrap();
+    }
+
+    #[test]
+    fn reject_revoked() {
+        let mut signer = OpenSslSigner::new();
+        let (issuer, key) = make_issuer(&mut signer);
+        let mut builder = ProtocolCmsBuilder::new(
+            12u64.into(), Validity::from_secs(3600)
+        );
+        builder.set_crl(Some(make_crl(
+            &signer, &key, &issuer,
+            vec![CrlEntry::new(12u64.into(), Time::now())]
+        )));
+        let msg = builder.finalize(xml(), &signer, &key).unwrap();
+        let msg = ProtocolCms::decode(msg.to_bytes_der().as_ref(), true)
+            .unwrap();
+        assert_eq!(
+            msg.validate(&issuer).unwrap_err().kind(),
+            ValidationErrorKind::Revoked
+        );
+    }
+}
@@ -0,0 +1,886 @@
+//! The publication protocol.
+//!
+//! RFC 8181 defines the protocol used by a CA to publish its objects in a
+//! repository. The CA sends a query and the repository answers with a
+//! reply. Both are XML messages represented by [`Message`]. They are
+//! exchanged wrapped in CMS signed data.
+//!
+//! A query either asks for a list of all objects currently published by
+//! the CA or contains a sequence of objects to be published or withdrawn.
+//! The reply to the former lists the objects, the reply to the latter
+//! signals success or reports one or more errors.
+//!
+//! [`Message`]: enum.Message.html
+
+use std::{fmt, io, str};
+use bytes::Bytes;
+use crate::uri;
+use crate::rrdp::DigestHex;
+use crate::xml::decode::{Content, Error, Name, Reader};
+use crate::xml::encode;
+
+
+//------------ Message -------------------------------------------------------
+
+/// A message of the publication protocol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Message {
+    /// A query sent by the publisher.
+    Query(Query),
+
+    /// A reply sent by the repository.
+    Reply(Reply),
+}
+
+impl Message {
+    /// Parses a message from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut query = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != MSG {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => {
+                    if value.ascii_into::<u8>()? != 3 {
+                        return Err(Error::Malformed)
+                    }
+                    Ok(())
+                }
+                b"type" => {
+                    query = match value.ascii_into::<String>()?.as_str() {
+                        "query" => Some(true),
+                        "reply" => Some(false),
+                        _ => return Err(Error::Malformed)
+                    };
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let res = match query {
+            Some(true) => {
+                Message::Query(Query::take_from(&mut outer, &mut reader)?)
+            }
+            Some(false) => {
+                Message::Reply(Reply::take_from(&mut outer, &mut reader)?)
+            }
+            None => return Err(Error::Malformed)
+        };
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+        Ok(res)
+    }
+
+    /// Writes the message as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        let element = writer.element(MSG.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "3")?;
+        match *self {
+            Message::Query(ref query) => {
+                element.attr(b"type", "query")?
+                    .content(|content| query.write_xml(content))?
+            }
+            Message::Reply(ref reply) => {
+                element.attr(b"type", "reply")?
+                    .content(|content| reply.write_xml(content))?
+            }
+        }.done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ Query ---------------------------------------------------------
+
+/// A query sent by a publisher to the repository.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Query {
+    /// A request for a list of all currently published objects.
+    ///
+    /// See section 2.3 of RFC 8181.
+    List,
+
+    /// Objects to be published or withdrawn.
+    ///
+    /// See sections 3.1 and 3.2 of RFC 8181.
+    Delta(Vec<QueryElement>),
+}
+
+impl Query {
+    /// Takes the content of a query message.
+    fn take_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut elements = Vec::new();
+        loop {
+            let mut list = false;
+            let mut attrs = PduAttrs::default();
+            let inner = outer.take_opt_element(reader, |element| {
+                if element.name() == LIST {
+                    list = true;
+                    return element.attributes(|_, _| Err(Error::Malformed))
+                }
+                attrs.take_element(element)
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            if list {
+                // The list element must be the only element.
+                inner.take_end(reader)?;
+                if !elements.is_empty() {
+                    return Err(Error::Malformed)
+                }
+                outer.take_opt_element(reader, |_| Err(Error::Malformed))?;
+                return Ok(Query::List)
+            }
+            elements.push(attrs.into_query_element(&mut inner, reader)?);
+        }
+        Ok(Query::Delta(elements))
+    }
+
+    /// Writes the content of a query message.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        match *self {
+            Query::List => content.element(LIST.local())?.done(),
+            Query::Delta(ref elements) => {
+                for element in elements {
+                    element.write_xml(content)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+
+//------------ QueryElement --------------------------------------------------
+
+/// A request to publish or withdraw an object.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum QueryElement {
+    /// Publish a new object or replace an existing one.
+    ///
+    /// If an existing object is replaced, `hash` contains the SHA-256 hash
+    /// of that object.
+    Publish {
+        tag: Option<String>,
+        uri: uri::Rsync,
+        hash: Option<DigestHex>,
+        data: Bytes,
+    },
+
+    /// Withdraw the object with the given hash.
+    Withdraw {
+        tag: Option<String>,
+        uri: uri::Rsync,
+        hash: DigestHex,
+    },
+}
+
+impl QueryElement {
+    /// Returns the tag of the element if present.
+    pub fn tag(&self) -> Option<&str> {
+        match *self {
+            QueryElement::Publish { ref tag, .. } => tag.as_deref(),
+            QueryElement::Withdraw { ref tag, .. } => tag.as_deref(),
+        }
+    }
+
+    /// Returns the URI of the object.
+    pub fn uri(&self) -> &uri::Rsync {
+        match *self {
+            QueryElement::Publish { ref uri, .. } => uri,
+            QueryElement::Withdraw { ref uri, .. } => uri,
+        }
+    }
+
+    /// Writes the element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        match *self {
+            QueryElement::Publish {
+                ref tag, ref uri, ref hash, ref data
+            } => {
+                content.element(PUBLISH.local())?
+                    .opt_attr(b"tag", tag.as_ref())?
+                    .attr(b"uri", uri)?
+                    .opt_attr(b"hash", hash.as_ref())?
+                    .content(|content| content.base64(data))?
+                    .done()
+            }
+            QueryElement::Withdraw { ref tag, ref uri, ref hash } => {
+                content.element(WITHDRAW.local())?
+                    .opt_attr(b"tag", tag.as_ref())?
+                    .attr(b"uri", uri)?
+                    .attr(b"hash", hash)?
+                    .done()
+            }
+        }
+    }
+}
+
+
+//------------ Reply ---------------------------------------------------------
+
+/// A reply sent by the repository to a publisher.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Reply {
+    /// All published and withdrawn objects have been processed.
+    ///
+    /// See section 3.4 of RFC 8181.
+    Success,
+
+    /// The list of currently published objects.
+    ///
+    /// See section 2.3 of RFC 8181.
+    List(Vec<ListElement>),
+
+    /// Processing the query failed.
+    ///
+    /// See section 3.5 of RFC 8181.
+    Errors(Vec<ReportError>),
+}
+
+impl Reply {
+    /// Takes the content of a reply message.
+    fn take_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut res = None;
+        loop {
+            let mut kind = None;
+            let mut uri = None;
+            let mut hash = None;
+            let mut code = None;
+            let mut tag = None;
+            let inner = outer.take_opt_element(reader, |element| {
+                match element.name() {
+                    SUCCESS => {
+                        kind = Some(ReplyKind::Success);
+                        element.attributes(|_, _| Err(Error::Malformed))
+                    }
+                    LIST => {
+                        kind = Some(ReplyKind::List);
+                        element.attributes(|name, value| match name {
+                            b"uri" => {
+                                uri = Some(value.ascii_into()?);
+                                Ok(())
+                            }
+                            b"hash" => {
+                                hash = Some(value.ascii_into()?);
+                                Ok(())
+                            }
+                            _ => Err(Error::Malformed)
+                        })
+                    }
+                    REPORT_ERROR => {
+                        kind = Some(ReplyKind::Errors);
+                        element.attributes(|name, value| match name {
+                            b"error_code" => {
+                                code = Some(value.ascii_into()?);
+                                Ok(())
+                            }
+                            b"tag" => {
+                                tag = Some(value.ascii_into()?);
+                                Ok(())
+                            }
+                            _ => Err(Error::Malformed)
+                        })
+                    }
+                    _ => Err(Error::Malformed)
+                }
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            match (kind, res.as_mut()) {
+                (Some(ReplyKind::Success), None) => {
+                    res = Some(Reply::Success)
+                }
+                (Some(ReplyKind::List), None) => {
+                    res = Some(Reply::List(Vec::new()))
+                }
+                (Some(ReplyKind::Errors), None) => {
+                    res = Some(Reply::Errors(Vec::new()))
+                }
+                (Some(ReplyKind::List), Some(Reply::List(_))) => { }
+                (Some(ReplyKind::Errors), Some(Reply::Errors(_))) => { }
+                _ => return Err(Error::Malformed)
+            }
+            match res.as_mut() {
+                Some(Reply::List(ref mut list)) => {
+                    list.push(ListElement {
+                        uri: uri.ok_or(Error::Malformed)?,
+                        hash: hash.ok_or(Error::Malformed)?,
+                    });
+                    inner.take_end(reader)?;
+                }
+                Some(Reply::Errors(ref mut errors)) => {
+                    errors.push(ReportError::take_content_from(
+                        code.ok_or(Error::Malformed)?, tag,
+                        &mut inner, reader
+                    )?);
+                }
+                _ => inner.take_end(reader)?
+            }
+        }
+
+        // A reply without any elements is a list reply with no objects.
+        Ok(res.unwrap_or_else(|| Reply::List(Vec::new())))
+    }
+
+    /// Writes the content of a reply message.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        match *self {
+            Reply::Success => content.element(SUCCESS.local())?.done(),
+            Reply::List(ref list) => {
+                for item in list {
+                    content.element(LIST.local())?
+                        .attr(b"uri", &item.uri)?
+                        .attr(b"hash", &item.hash)?
+                        .done()?;
+                }
+                Ok(())
+            }
+            Reply::Errors(ref errors) => {
+                for error in errors {
+                    error.write_xml(content)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The kind of a reply element.
+#[derive(Clone, Copy)]
+enum ReplyKind {
+    Success,
+    List,
+    Errors,
+}
+
+
+//------------ ListElement ---------------------------------------------------
+
+/// An object currently published by the publisher.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ListElement {
+    uri: uri::Rsync,
+    hash: DigestHex,
+}
+
+impl ListElement {
+    /// Creates a new list element.
+    pub fn new(uri: uri::Rsync, hash: DigestHex) -> Self {
+        ListElement { uri, hash }
+    }
+
+    /// Returns the URI of the object.
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns the SHA-256 hash of the object.
+    pub fn hash(&self) -> &DigestHex {
+        &self.hash
+    }
+}
+
+
+//------------ ReportError ---------------------------------------------------
+
+/// An error that happened while processing a query.
+///
+/// See section 3.5 of RFC 8181.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportError {
+    code: ErrorCode,
+    tag: Option<String>,
+    text: Option<String>,
+    failed_pdu: Option<QueryElement>,
+}
+
+impl ReportError {
+    /// Creates a new error report.
+    pub fn new(code: ErrorCode) -> Self {
+        ReportError { code, tag: None, text: None, failed_pdu: None }
+    }
+
+    /// Returns the error code.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Returns the tag of the query element that caused the error.
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Sets the tag of the query element that caused the error.
+    pub fn set_tag(&mut self, tag: Option<String>) {
+        self.tag = tag
+    }
+
+    /// Returns the human-readable error text if present.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Sets the human-readable error text.
+    pub fn set_text(&mut self, text: Option<String>) {
+        self.text = text
+    }
+
+    /// Returns the query element that caused the error if present.
+    pub fn failed_pdu(&self) -> Option<&QueryElement> {
+        self.failed_pdu.as_ref()
+    }
+
+    /// Sets the query element that caused the error.
+    pub fn set_failed_pdu(&mut self, pdu: Option<QueryElement>) {
+        self.failed_pdu = pdu
+    }
+
+    /// Takes the content of a report_error element.
+    fn take_content_from<R: io::BufRead>(
+        code: ErrorCode,
+        tag: Option<String>,
+        content: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut res = ReportError { code, tag, text: None, failed_pdu: None };
+        loop {
+            let mut is_text = false;
+            let inner = content.take_opt_element(reader, |element| {
+                match element.name() {
+                    ERROR_TEXT => is_text = true,
+                    FAILED_PDU => { }
+                    _ => return Err(Error::Malformed)
+                }
+                element.attributes(|_, _| Err(Error::Malformed))
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            if is_text {
+                if res.text.is_some() {
+                    return Err(Error::Malformed)
+                }
+                res.text = Some(inner.take_text(reader, |text| {
+                    text.to_ascii().map(|text| text.into_owned())
+                })?);
+            }
+            else {
+                if res.failed_pdu.is_some() {
+                    return Err(Error::Malformed)
+                }
+                let mut attrs = PduAttrs::default();
+                let mut pdu = inner.take_element(reader, |element| {
+                    attrs.take_element(element)
+                })?;
+                res.failed_pdu = Some(
+                    attrs.into_query_element(&mut pdu, reader)?
+                );
+            }
+            inner.take_end(reader)?;
+        }
+        Ok(res)
+    }
+
+    /// Writes the report_error element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        content.element(REPORT_ERROR.local())?
+            .attr(b"error_code", &self.code)?
+            .opt_attr(b"tag", self.tag.as_ref())?
+            .content(|content| {
+                if let Some(ref text) = self.text {
+                    content.element(ERROR_TEXT.local())?
+                        .content(|content| content.pcdata(text))?
+                        .done()?;
+                }
+                if let Some(ref pdu) = self.failed_pdu {
+                    content.element(FAILED_PDU.local())?
+                        .content(|content| pdu.write_xml(content))?
+                        .done()?;
+                }
+                Ok(())
+            })?
+            .done()
+    }
+}
+
+
+//------------ ErrorCode -----------------------------------------------------
+
+/// The error codes defined in section 2.5 of RFC 8181.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ErrorCode {
+    /// The received message was not well-formed XML.
+    XmlError,
+
+    /// The publisher is not allowed to publish at the given URI.
+    PermissionFailure,
+
+    /// The CMS signature of the message is invalid.
+    BadCmsSignature,
+
+    /// An object already exists at the URI of a new object.
+    ObjectAlreadyPresent,
+
+    /// There is no object to replace or withdraw at the URI.
+    NoObjectPresent,
+
+    /// The hash of the object to replace or withdraw doesn’t match.
+    NoObjectMatchingHash,
+
+    /// The query would leave the repository in an inconsistent state.
+    ConsistencyProblem,
+
+    /// Any other error.
+    OtherError,
+}
+
+impl ErrorCode {
+    /// Returns the value of the error_code attribute for this code.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::XmlError => "xml_error",
+            ErrorCode::PermissionFailure => "permission_failure",
+            ErrorCode::BadCmsSignature => "bad_cms_signature",
+            ErrorCode::ObjectAlreadyPresent => "object_already_present",
+            ErrorCode::NoObjectPresent => "no_object_present",
+            ErrorCode::NoObjectMatchingHash => "no_object_matching_hash",
+            ErrorCode::ConsistencyProblem => "consistency_problem",
+            ErrorCode::OtherError => "other_error",
+        }
+    }
+}
+
+impl str::FromStr for ErrorCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xml_error" => Ok(ErrorCode::XmlError),
+            "permission_failure" => Ok(ErrorCode::PermissionFailure),
+            "bad_cms_signature" => Ok(ErrorCode::BadCmsSignature),
+            "object_already_present" => Ok(ErrorCode::ObjectAlreadyPresent),
+            "no_object_present" => Ok(ErrorCode::NoObjectPresent),
+            "no_object_matching_hash" => Ok(ErrorCode::NoObjectMatchingHash),
+            "consistency_problem" => Ok(ErrorCode::ConsistencyProblem),
+            "other_error" => Ok(ErrorCode::OtherError),
+            _ => Err(Error::Malformed)
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+
+//------------ PduAttrs ------------------------------------------------------
+
+/// Collects the attributes of a publish or withdraw element.
+#[derive(Default)]
+struct PduAttrs {
+    withdraw: bool,
+    tag: Option<String>,
+    uri: Option<uri::Rsync>,
+    hash: Option<DigestHex>,
+}
+
+impl PduAttrs {
+    /// Processes the start of a publish or withdraw element.
+    fn take_element(
+        &mut self, element: crate::xml::decode::Element
+    ) -> Result<(), Error> {
+        match element.name() {
+            PUBLISH => self.withdraw = false,
+            WITHDRAW => self.withdraw = true,
+            _ => return Err(Error::Malformed)
+        }
+        element.attributes(|name, value| match name {
+            b"tag" => {
+                self.tag = Some(value.ascii_into()?);
+                Ok(())
+            }
+            b"uri" => {
+                self.uri = Some(value.ascii_into()?);
+                Ok(())
+            }
+            b"hash" => {
+                self.hash = Some(value.ascii_into()?);
+                Ok(())
+            }
+            _ => Err(Error::Malformed)
+        })
+    }
+
+    /// Takes the content of the element and creates the query element.
+    fn into_query_element<R: io::BufRead>(
+        self,
+        content: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<QueryElement, Error> {
+        let uri = self.uri.ok_or(Error::Malformed)?;
+        let res = if self.withdraw {
+            QueryElement::Withdraw {
+                tag: self.tag,
+                uri,
+                hash: self.hash.ok_or(Error::Malformed)?,
+            }
+        }
+        else {
+            let data = content.take_text(reader, |text| {
+                text.base64_decode()
+            })?;
+            QueryElement::Publish {
+                tag: self.tag,
+                uri,
+                hash: self.hash,
+                data: data.into(),
+            }
+        };
+        content.take_end(reader)?;
+        Ok(res)
+    }
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS_STR: &str = "http://www.hactrn.net/uris/rpki/publication-spec/";
+const NS: &[u8] = NS_STR.as_bytes();
+const MSG: Name = Name::qualified(NS, b"msg");
+const PUBLISH: Name = Name::qualified(NS, b"publish");
+const WITHDRAW: Name = Name::qualified(NS, b"withdraw");
+const LIST: Name = Name::qualified(NS, b"list");
+const SUCCESS: Name = Name::qualified(NS, b"success");
+const REPORT_ERROR: Name = Name::qualified(NS, b"report_error");
+const ERROR_TEXT: Name = Name::qualified(NS, b"error_text");
+const FAILED_PDU: Name = Name::qualified(NS, b"failed_pdu");
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::str::FromStr;
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
+
+    fn hash(s: &str) -> DigestHex {
+        DigestHex::from_str(s).unwrap()
+    }
+
+    fn round_trip(message: Message) {
+        let mut xml = Vec::new();
+        message.write_xml(&mut xml).unwrap();
+        assert_eq!(message, Message::parse(xml.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn parse_rfc8181_examples() {
+        // Based on the examples in section 2 of RFC 8181.
+        let message = Message::parse(
+            b"<msg type=\"query\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <!-- PDUs -->\n\
+                <publish tag=\"1\" \
+                    uri=\"rsync://wombat.example/Alice/a.cer\">\n\
+                    SGVsbG8gd29ybGQ=\n\
+                </publish>\n\
+                <publish \
+                    hash=\"DEADF00D\" \
+                    uri=\"rsync://wombat.example/Alice/b.crl\">\
+                    SGVsbG8=\
+                </publish>\n\
+                <withdraw \
+                    hash=\"deadf00d\" \
+                    uri=\"rsync://wombat.example/Alice/c.roa\"/>\n\
+              </msg>".as_ref()
+        ).unwrap();
+        assert_eq!(
+            message,
+            Message::Query(Query::Delta(vec![
+                QueryElement::Publish {
+                    tag: Some("1".into()),
+                    uri: rsync(
+                        "rsync://wombat.example/Alice/a.cer"
+                    ),
+                    hash: None,
+                    data: Bytes::from_static(b"Hello world"),
+                },
+                QueryElement::Publish {
+                    tag: None,
+                    uri: rsync(
+                        "rsync://wombat.example/Alice/b.crl"
+                    ),
+                    hash: Some(hash("deadf00d")),
+                    data: Bytes::from_static(b"Hello"),
+                },
+                QueryElement::Withdraw {
+                    tag: None,
+                    uri: rsync(
+                        "rsync://wombat.example/Alice/c.roa"
+                    ),
+                    hash: hash("deadf00d"),
+                },
+            ]))
+        );
+
+        let message = Message::parse(
+            b"<msg type=\"query\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <list/>\
+              </msg>".as_ref()
+        ).unwrap();
+        assert_eq!(message, Message::Query(Query::List));
+
+        let message = Message::parse(
+            b"<msg type=\"reply\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <report_error error_code=\"no_object_present\" tag=\"1\">\
+                  <error_text>no such object</error_text>\
+                  <failed_pdu>\
+                    <withdraw uri=\"rsync://wombat.example/Alice/a.roa\" \
+                        hash=\"deadf00d\"/>\
+                  </failed_pdu>\
+                </report_error>\
+              </msg>".as_ref()
+        ).unwrap();
+        match message {
+            Message::Reply(Reply::Errors(errors)) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].code(), ErrorCode::NoObjectPresent);
+                assert_eq!(errors[0].tag(), Some("1"));
+                assert_eq!(errors[0].text(), Some("no such object"));
+                assert_eq!(
+                    errors[0].failed_pdu().unwrap().uri(),
+                    &rsync("rsync://wombat.example/Alice/a.roa")
+                );
+            }
+            _ => panic!("not an error reply")
+        }
+    }
+
+    #[test]
+    fn reject_malformed() {
+        // Wrong version.
+        assert!(Message::parse(
+            b"<msg type=\"query\" version=\"4\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <list/>\
+              </msg>".as_ref()
+        ).is_err());
+
+        // List mixed with publish.
+        assert!(Message::parse(
+            b"<msg type=\"query\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <list/>\
+                <publish uri=\"rsync://wombat.example/Alice/a.roa\">\
+                  SGVsbG8=\
+                </publish>\
+              </msg>".as_ref()
+        ).is_err());
+
+        // Success mixed with errors.
+        assert!(Message::parse(
+            b"<msg type=\"reply\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <success/>\
+                <report_error error_code=\"other_error\"/>\
+              </msg>".as_ref()
+        ).is_err());
+
+        // Withdraw without hash.
+        assert!(Message::parse(
+            b"<msg type=\"query\" version=\"3\" \
+                xmlns=\"http://www.hactrn.net/uris/rpki/publication-spec/\">\
+                <withdraw uri=\"rsync://wombat.example/Alice/a.roa\"/>\
+              </msg>".as_ref()
+        ).is_err());
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        round_trip(Message::Query(Query::List));
+        round_trip(Message::Query(Query::Delta(Vec::new())));
+        round_trip(Message::Query(Query::Delta(vec![
+            QueryElement::Publish {
+                tag: Some("a&b".into()),
+                uri: rsync("rsync://wombat.example/Alice/a.cer"),
+                hash: Some(hash("0123abcd")),
+                data: Bytes::from_static(b"some data"),
+            },
+            QueryElement::Withdraw {
+                tag: None,
+                uri: rsync("rsync://wombat.example/Alice/b.cer"),
+                hash: hash("0123abcd"),
+            },
+        ])));
+        round_trip(Message::Reply(Reply::Success));
+        round_trip(Message::Reply(Reply::List(Vec::new())));
+        round_trip(Message::Reply(Reply::List(vec![
+            ListElement::new(
+                rsync("rsync://wombat.example/Alice/a.cer"), hash("0123abcd")
+            ),
+            ListElement::new(
+                rsync("rsync://wombat.example/Alice/b.cer"), hash("4567")
+            ),
+        ])));
+        let mut error = ReportError::new(ErrorCode::NoObjectMatchingHash);
+        error.set_tag(Some("12".into()));
+        error.set_text(Some("hash <mismatch>".into()));
+        error.set_failed_pdu(Some(QueryElement::Publish {
+            tag: Some("12".into()),
+            uri: rsync("rsync://wombat.example/Alice/a.cer"),
+            hash: Some(hash("0123abcd")),
+            data: Bytes::from_static(b"some data"),
+        }));
+        round_trip(Message::Reply(Reply::Errors(vec![
+            error, ReportError::new(ErrorCode::OtherError)
+        ])));
+    }
+
+    #[test]
+    fn error_codes() {
+        for code in &[
+            ErrorCode::XmlError, ErrorCode::PermissionFailure,
+            ErrorCode::BadCmsSignature, ErrorCode::ObjectAlreadyPresent,
+            ErrorCode::NoObjectPresent, ErrorCode::NoObjectMatchingHash,
+            ErrorCode::ConsistencyProblem, ErrorCode::OtherError,
+        ] {
+            assert_eq!(ErrorCode::from_str(code.as_str()).unwrap(), *code);
+        }
+        assert!(ErrorCode::from_str("bad_error").is_err());
+    }
+}
@@ -0,0 +1,600 @@
+//! Checking the content of a publication point against its manifest.
+//!
+//! The manifest of an RPKI CA lists all the files the CA currently
+//! publishes together with their hashes. RFC 9286 requires a relying party
+//! to check that the files it actually retrieved from the CA’s publication
+//! point are exactly those listed on the manifest before using any of them.
+//!
+//! This module provides the type [`PublicationPoint`] that performs these
+//! checks for a validated manifest and the files available via a
+//! [`FileSource`]. The result is a [`PointFile`] for every file listed on
+//! the manifest or found at the publication point with the outcome of the
+//! check for that file and the type of object as derived from the file
+//! name’s extension.
+//!
+//! [`PublicationPoint`]: struct.PublicationPoint.html
+//! [`FileSource`]: trait.FileSource.html
+//! [`PointFile`]: struct.PointFile.html
+
+use std::{fmt, fs, io};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use bytes::Bytes;
+use crate::cert::ResourceCert;
+use crate::manifest::{ManifestContent, ManifestHash};
+use crate::x509::{Serial, ValidationError, ValidationErrorKind};
+
+
+//------------ FileSource ----------------------------------------------------
+
+/// Access to the files of a single publication point.
+///
+/// Files are identified by their names relative to the publication point,
+/// i.e., the names as they appear on the manifest.
+pub trait FileSource {
+    /// Returns the names of all files present at the publication point.
+    fn list(&self) -> Result<Vec<String>, io::Error>;
+
+    /// Loads the content of the file with the given name.
+    ///
+    /// Returns `Ok(None)` if there is no such file.
+    fn load(&self, name: &str) -> Result<Option<Bytes>, io::Error>;
+}
+
+impl<S: FileSource + ?Sized> FileSource for &S {
+    fn list(&self) -> Result<Vec<String>, io::Error> {
+        (*self).list()
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Bytes>, io::Error> {
+        (*self).load(name)
+    }
+}
+
+impl FileSource for HashMap<String, Bytes> {
+    fn list(&self) -> Result<Vec<String>, io::Error> {
+        Ok(self.keys().cloned().collect())
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Bytes>, io::Error> {
+        Ok(self.get(name).cloned())
+    }
+}
+
+
+//------------ DirectorySource -----------------------------------------------
+
+/// A file source atop a local directory.
+///
+/// All regular files directly within the directory are considered part
+/// of the publication point.
+#[derive(Clone, Debug)]
+pub struct DirectorySource {
+    path: PathBuf,
+}
+
+impl DirectorySource {
+    /// Creates a new file source for the directory at `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        DirectorySource { path: path.into() }
+    }
+
+    /// Returns the path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl FileSource for DirectorySource {
+    fn list(&self) -> Result<Vec<String>, io::Error> {
+        let mut res = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                res.push(entry.file_name().to_string_lossy().into_owned())
+            }
+        }
+        Ok(res)
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Bytes>, io::Error> {
+        match fs::read(self.path.join(name)) {
+            Ok(data) => Ok(Some(data.into())),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(None)
+            }
+            Err(err) => Err(err)
+        }
+    }
+}
+
+
+//------------ PublicationPoint ----------------------------------------------
+
+/// The content of a publication point checked against its manifest.
+#[derive(Clone, Debug)]
+pub struct PublicationPoint {
+    /// The manifest number of the manifest used.
+    manifest_number: Serial,
+
+    /// The file name of the manifest itself.
+    manifest_name: String,
+
+    /// The index of the CRL in `files`.
+    crl: usize,
+
+    /// The files listed on the manifest or present at the point.
+    ///
+    /// Listed files come first in manifest order, followed by the unlisted
+    /// files ordered by name.
+    files: Vec<PointFile>,
+}
+
+impl PublicationPoint {
+    /// Checks the files of a publication point against its manifest.
+    ///
+    /// The manifest is given via its validated EE certificate `ee_cert`
+    /// and its `content` as returned by `Manifest::validate`. The files of
+    /// the publication point are taken from `source`. If the manifest
+    /// number of the last manifest used for the publication point is known,
+    /// it can be given via `previous`.
+    ///
+    /// The method fails if the manifest doesn’t list exactly one CRL which
+    /// also needs to be the one referenced by the EE certificate, if any of
+    /// the listed file names isn’t allowed, or if the manifest number is
+    /// lower than `previous`. All other problems are reported via the
+    /// status of the individual files.
+    pub fn process<S: FileSource + ?Sized>(
+        ee_cert: &ResourceCert,
+        content: &ManifestContent,
+        source: &S,
+        previous: Option<Serial>,
+    ) -> Result<Self, PublicationPointError> {
+        // RFC 9286, section 4.2.1: the manifest number must increase.
+        if let Some(previous) = previous {
+            if content.manifest_number() < previous {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::ManifestNumberRollback
+                ).with_rfc(9286, "4.2.1").into())
+            }
+        }
+
+        let manifest_name = ee_cert.signed_object().map(|uri| {
+            file_name(uri.path()).to_string()
+        }).unwrap_or_default();
+        let crl_name = ee_cert.crl_uri().map(|uri| file_name(uri.path()));
+
+        let mut files = Vec::new();
+        let mut listed = HashSet::new();
+        let mut crl = None;
+        for item in content.iter() {
+            let (name, hash) = item.into_pair();
+            let name = match std::str::from_utf8(name.as_ref()) {
+                Ok(name) if is_valid_file_name(name) => name.to_string(),
+                _ => {
+                    return Err(ValidationError::new(
+                        ValidationErrorKind::Malformed
+                    ).with_rfc(9286, "4.2.2").into())
+                }
+            };
+            let hash = ManifestHash::new(hash, content.file_hash_alg());
+            let file_type = FileType::from_name(&name);
+
+            // RFC 9286, section 6.4: exactly one CRL which must be the one
+            // of the manifest’s EE certificate.
+            if file_type == FileType::Crl {
+                if crl.is_some() || crl_name != Some(name.as_str()) {
+                    return Err(ValidationError::new(
+                        ValidationErrorKind::ManifestCrlMismatch
+                    ).with_rfc(9286, "6.4").into())
+                }
+                crl = Some(files.len());
+            }
+
+            let (status, data) = match source.load(&name)? {
+                Some(data) => {
+                    if hash.verify(&data).is_ok() {
+                        (FileStatus::Ok, Some(data))
+                    }
+                    else {
+                        (FileStatus::HashMismatch, None)
+                    }
+                }
+                None => (FileStatus::Missing, None)
+            };
+            listed.insert(name.clone());
+            files.push(PointFile { name, file_type, status, data });
+        }
+        let crl = match crl {
+            Some(crl) => crl,
+            None => {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::ManifestCrlMismatch
+                ).with_rfc(9286, "6.4").into())
+            }
+        };
+
+        let mut unlisted: Vec<_> = source.list()?.into_iter().filter(|name| {
+            *name != manifest_name && !listed.contains(name)
+        }).collect();
+        unlisted.sort();
+        files.extend(unlisted.into_iter().map(|name| {
+            PointFile {
+                file_type: FileType::from_name(&name),
+                name,
+                status: FileStatus::Unlisted,
+                data: None,
+            }
+        }));
+
+        Ok(PublicationPoint {
+            manifest_number: content.manifest_number(),
+            manifest_name,
+            crl,
+            files,
+        })
+    }
+
+    /// Returns the manifest number of the manifest used.
+    pub fn manifest_number(&self) -> Serial {
+        self.manifest_number
+    }
+
+    /// Returns the file name of the manifest.
+    pub fn manifest_name(&self) -> &str {
+        &self.manifest_name
+    }
+
+    /// Returns the CRL listed on the manifest.
+    ///
+    /// Note that the file may be missing or have the wrong hash.
+    pub fn crl(&self) -> &PointFile {
+        &self.files[self.crl]
+    }
+
+    /// Returns a slice with all the files of the publication point.
+    ///
+    /// This includes files listed on the manifest in manifest order
+    /// followed by files present but not listed.
+    pub fn files(&self) -> &[PointFile] {
+        &self.files
+    }
+
+    /// Returns an iterator over the listed files that passed the checks.
+    pub fn iter_ok(&self) -> impl Iterator<Item = &PointFile> {
+        self.files.iter().filter(|file| file.status == FileStatus::Ok)
+    }
+
+    /// Returns whether all files listed on the manifest passed the checks.
+    ///
+    /// If this is not the case, the publication point must not be used
+    /// according to RFC 9286, section 6.4. Unlisted files are ignored.
+    pub fn is_complete(&self) -> bool {
+        self.files.iter().all(|file| {
+            match file.status {
+                FileStatus::Ok | FileStatus::Unlisted => true,
+                _ => false
+            }
+        })
+    }
+}
+
+
+//------------ PointFile -----------------------------------------------------
+
+/// A file of a publication point.
+#[derive(Clone, Debug)]
+pub struct PointFile {
+    /// The name of the file relative to the publication point.
+    name: String,
+
+    /// The type of the file as derived from its extension.
+    file_type: FileType,
+
+    /// The result of checking the file.
+    status: FileStatus,
+
+    /// The content of the file if it passed the checks.
+    data: Option<Bytes>,
+}
+
+impl PointFile {
+    /// Returns the name of the file.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the type of the file.
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// Returns the result of checking the file.
+    pub fn status(&self) -> FileStatus {
+        self.status
+    }
+
+    /// Returns the content of the file if it passed the checks.
+    pub fn data(&self) -> Option<&Bytes> {
+        self.data.as_ref()
+    }
+}
+
+
+//------------ FileStatus ----------------------------------------------------
+
+/// The outcome of checking a file of a publication point.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileStatus {
+    /// The file is listed on the manifest and its hash matches.
+    Ok,
+
+    /// The file is listed on the manifest but its hash doesn’t match.
+    HashMismatch,
+
+    /// The file is listed on the manifest but not present.
+    Missing,
+
+    /// The file is present but not listed on the manifest.
+    Unlisted,
+}
+
+impl fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            FileStatus::Ok => "ok",
+            FileStatus::HashMismatch => "hash mismatch",
+            FileStatus::Missing => "missing",
+            FileStatus::Unlisted => "not listed on manifest",
+        })
+    }
+}
+
+
+//------------ FileType ------------------------------------------------------
+
+/// The type of an RPKI object as derived from its file name’s extension.
+///
+/// The extensions are registered in the “RPKI Repository Name Schemes”
+/// IANA registry.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FileType {
+    /// A certificate, extension `.cer`.
+    Cer,
+
+    /// A route origin authorization, extension `.roa`.
+    Roa,
+
+    /// A certificate revocation list, extension `.crl`.
+    Crl,
+
+    /// A manifest, extension `.mft`.
+    Mft,
+
+    /// A Ghostbusters record, extension `.gbr`.
+    Gbr,
+
+    /// An autonomous system provider authorization, extension `.asa`.
+    Asa,
+
+    /// Any other extension.
+    Other,
+}
+
+impl FileType {
+    /// Determines the type from a file name.
+    pub fn from_name(name: &str) -> Self {
+        match name.rfind('.').map(|pos| &name[pos + 1..]) {
+            Some("cer") => FileType::Cer,
+            Some("roa") => FileType::Roa,
+            Some("crl") => FileType::Crl,
+            Some("mft") => FileType::Mft,
+            Some("gbr") => FileType::Gbr,
+            Some("asa") => FileType::Asa,
+            _ => FileType::Other,
+        }
+    }
+
+    /// Returns the extension for the type.
+    ///
+    /// Returns `None` for `FileType::Other`.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            FileType::Cer => Some("cer"),
+            FileType::Roa => Some("roa"),
+            FileType::Crl => Some("crl"),
+            FileType::Mft => Some("mft"),
+            FileType::Gbr => Some("gbr"),
+            FileType::Asa => Some("asa"),
+            FileType::Other => None,
+        }
+    }
+}
+
+
+//------------ PublicationPointError -----------------------------------------
+
+/// An error happened while processing a publication point.
+#[derive(Debug)]
+pub enum PublicationPointError {
+    /// Accessing the file source failed.
+    Io(io::Error),
+
+    /// The manifest can’t be used for the publication point.
+    Invalid(ValidationError),
+}
+
+impl From<io::Error> for PublicationPointError {
+    fn from(err: io::Error) -> Self {
+        PublicationPointError::Io(err)
+    }
+}
+
+impl From<ValidationError> for PublicationPointError {
+    fn from(err: ValidationError) -> Self {
+        PublicationPointError::Invalid(err)
+    }
+}
+
+impl fmt::Display for PublicationPointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PublicationPointError::Io(ref err) => err.fmt(f),
+            PublicationPointError::Invalid(ref err) => err.fmt(f),
+        }
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Returns the last component of a path.
+fn file_name(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(pos) => &path[pos + 1..],
+        None => path
+    }
+}
+
+/// Returns whether a file name on a manifest is allowed.
+///
+/// RFC 9286, section 4.2.2 limits names to a non-empty sequence of ASCII
+/// letters, digits, hyphens, and underscores, followed by a dot and a
+/// three letter lower case extension.
+fn is_valid_file_name(name: &str) -> bool {
+    let (stem, ext) = match name.rfind('.') {
+        Some(pos) => (&name[..pos], &name[pos + 1..]),
+        None => return false
+    };
+    !stem.is_empty()
+        && stem.bytes().all(|ch| {
+            ch.is_ascii_alphanumeric() || ch == b'-' || ch == b'_'
+        })
+        && ext.len() == 3
+        && ext.bytes().all(|ch| ch.is_ascii_lowercase())
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cert::Cert;
+    use crate::manifest::Manifest;
+    use crate::tal::TalInfo;
+    use crate::x509::Time;
+
+    const CA_NAME: &str = "2a7dd1d787d793e4c8af56e197d4eed92af6ba13.cer";
+
+    fn ta_manifest() -> (ResourceCert, ManifestContent) {
+        let at = Time::utc(2019, 4, 6, 12, 0, 0);
+        let ta = Cert::decode(
+            include_bytes!("../test-data/ta.cer").as_ref()
+        ).unwrap().validate_ta_at(
+            TalInfo::from_name("foo".into()).into_arc(), false, at
+        ).unwrap();
+        Manifest::decode(
+            include_bytes!("../test-data/ta.mft").as_ref(), false
+        ).unwrap().validate_at(&ta, false, at).unwrap()
+    }
+
+    fn ta_source() -> HashMap<String, Bytes> {
+        let mut res = HashMap::new();
+        res.insert(
+            "ripe-ncc-ta.mft".into(),
+            Bytes::from_static(include_bytes!("../test-data/ta.mft"))
+        );
+        res.insert(
+            "ripe-ncc-ta.crl".into(),
+            Bytes::from_static(include_bytes!("../test-data/ta.crl"))
+        );
+        res.insert(
+            CA_NAME.into(),
+            Bytes::from_static(include_bytes!("../test-data/ca1.cer"))
+        );
+        res
+    }
+
+    fn status(point: &PublicationPoint, name: &str) -> FileStatus {
+        point.files().iter().find(|file| file.name() == name).unwrap()
+            .status()
+    }
+
+    #[test]
+    fn complete_point() {
+        let (ee, content) = ta_manifest();
+        let point = PublicationPoint::process(
+            &ee, &content, &ta_source(), None
+        ).unwrap();
+        assert!(point.is_complete());
+        assert_eq!(point.manifest_name(), "ripe-ncc-ta.mft");
+        assert_eq!(point.crl().name(), "ripe-ncc-ta.crl");
+        assert_eq!(point.crl().file_type(), FileType::Crl);
+        assert_eq!(point.files().len(), content.len());
+        let ca = point.iter_ok().find(|file| file.name() == CA_NAME);
+        assert_eq!(ca.unwrap().file_type(), FileType::Cer);
+    }
+
+    #[test]
+    fn file_problems() {
+        let (ee, content) = ta_manifest();
+        let mut source = ta_source();
+        source.insert(
+            CA_NAME.into(),
+            Bytes::from_static(include_bytes!("../test-data/ca1.crl"))
+        );
+        source.insert("extra.roa".into(), Bytes::from_static(b"foo"));
+        let point = PublicationPoint::process(
+            &ee, &content, &source, None
+        ).unwrap();
+        assert!(!point.is_complete());
+        assert_eq!(status(&point, CA_NAME), FileStatus::HashMismatch);
+        assert_eq!(status(&point, "extra.roa"), FileStatus::Unlisted);
+        assert_eq!(point.files().last().unwrap().file_type(), FileType::Roa);
+        assert_eq!(status(&point, "ripe-ncc-ta.crl"), FileStatus::Ok);
+
+        source.remove(CA_NAME);
+        source.remove("extra.roa");
+        let point = PublicationPoint::process(
+            &ee, &content, &source, None
+        ).unwrap();
+        assert!(!point.is_complete());
+        assert_eq!(status(&point, CA_NAME), FileStatus::Missing);
+    }
+
+    #[test]
+    fn manifest_number() {
+        let (ee, content) = ta_manifest();
+        let number = content.manifest_number();
+        assert!(PublicationPoint::process(
+            &ee, &content, &ta_source(), Some(number)
+        ).is_ok());
+        match PublicationPoint::process(
+            &ee, &content, &ta_source(), Some(Serial::from(std::u64::MAX))
+        ) {
+            Err(PublicationPointError::Invalid(err)) => {
+                assert_eq!(
+                    err.kind(), ValidationErrorKind::ManifestNumberRollback
+                )
+            }
+            _ => panic!("manifest number rollback accepted")
+        }
+    }
+
+    #[test]
+    fn file_types() {
+        assert_eq!(FileType::from_name("foo.cer"), FileType::Cer);
+        assert_eq!(FileType::from_name("foo.roa"), FileType::Roa);
+        assert_eq!(FileType::from_name("foo.crl"), FileType::Crl);
+        assert_eq!(FileType::from_name("foo.gbr"), FileType::Gbr);
+        assert_eq!(FileType::from_name("foo.asa"), FileType::Asa);
+        assert_eq!(FileType::from_name("foo.mft"), FileType::Mft);
+        assert_eq!(FileType::from_name("foo.der"), FileType::Other);
+        assert_eq!(FileType::from_name("foo"), FileType::Other);
+        assert!(is_valid_file_name("a-b_C9.roa"));
+        assert!(!is_valid_file_name("../foo.roa"));
+        assert!(!is_valid_file_name(".roa"));
+        assert!(!is_valid_file_name("foo.ROA"));
+    }
+}
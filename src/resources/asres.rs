@@ -22,8 +22,8 @@ use bcder::{Tag, xerr};
 use bcder::encode::PrimitiveContent;
 use serde::de;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use crate::cert::Overclaim;
-use crate::x509::ValidationError;
+use crate::cert::{Overclaim, OverclaimReport};
+use crate::x509::{ValidationError, ValidationErrorKind};
 use super::chain::{Block, SharedChain};
 use super::choice::ResourcesChoice;
 
@@ -243,7 +243,9 @@ impl AsBlocks {
         res: Option<&AsResources>
     ) -> Result<Self, ValidationError> {
         match res.map(|res| &res.0) {
-            Some(ResourcesChoice::Inherit) => Err(ValidationError),
+            Some(ResourcesChoice::Inherit) => {
+                Err(ValidationErrorKind::InheritedResources.into())
+            }
             Some(ResourcesChoice::Blocks(ref some)) => Ok(some.clone()),
             None => Ok(AsBlocks::empty())
         }
@@ -260,6 +262,13 @@ impl AsBlocks {
     }
 
     /// Validates AS resources issued under these blocks.
+    ///
+    /// If the issued resources are not covered by these blocks and `mode`
+    /// is [`Overclaim::Refuse`], the returned error carries an
+    /// [`OverclaimReport`] with the excess AS numbers.
+    ///
+    /// [`Overclaim::Refuse`]: ../cert/enum.Overclaim.html#variant.Refuse
+    /// [`OverclaimReport`]: ../cert/struct.OverclaimReport.html
     pub fn validate_issued(
         &self,
         res: Option<&AsResources>,
@@ -274,7 +283,13 @@ impl AsBlocks {
                             Ok(blocks.clone())
                         }
                         else {
-                            Err(ValidationError)
+                            Err(ValidationError::new(
+                                ValidationErrorKind::Overclaim
+                            ).with_rfc(6487, "4.8.11").with_overclaim(
+                                OverclaimReport::from_as_blocks(
+                                    blocks.difference(self)
+                                )
+                            ))
                         }
                     }
                     Overclaim::Trim => {
@@ -288,6 +303,19 @@ impl AsBlocks {
             None => Ok(Self::empty()),
         }
     }
+
+    /// Returns the issued AS resources not covered by these blocks.
+    ///
+    /// Inherited resources are always covered, so the result is empty for
+    /// them.
+    pub fn overclaimed(&self, res: Option<&AsResources>) -> AsBlocks {
+        match res.map(|res| &res.0) {
+            Some(ResourcesChoice::Blocks(ref blocks)) => {
+                blocks.difference(self)
+            }
+            _ => Self::empty()
+        }
+    }
 }
 
 /// # Set operations
@@ -318,8 +346,23 @@ impl AsBlocks {
         )
     }
 
+    /// Returns a new AsBlocks with the difference of this and the other
+    /// AsBlocks.
+    ///
+    /// i.e. all resources found in this but not the other AsBlocks.
+    pub fn difference(&self, other: &Self) -> Self {
+        AsBlocks(SharedChain::from_owned(self.0.difference(&other.0)))
+    }
 
-
+    /// Returns a new AsBlocks with the symmetric difference of this and the
+    /// other AsBlocks.
+    ///
+    /// i.e. all resources found in exactly one of the two AsBlocks.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        AsBlocks(SharedChain::from_owned(
+            self.0.symmetric_difference(&other.0)
+        ))
+    }
 }
 
 /// # Decoding and Encoding
@@ -651,6 +694,10 @@ impl Block for AsBlock {
     fn next(item: Self::Item) -> Option<Self::Item> {
         item.0.checked_add(1).map(AsId)
     }
+
+    fn prev(item: Self::Item) -> Option<Self::Item> {
+        item.0.checked_sub(1).map(AsId)
+    }
 }
 
 //--- Display
@@ -751,6 +798,10 @@ impl Block for AsRange {
     fn next(item: Self::Item) -> Option<Self::Item> {
         item.0.checked_add(1).map(AsId)
     }
+
+    fn prev(item: Self::Item) -> Option<Self::Item> {
+        item.0.checked_sub(1).map(AsId)
+    }
 }
 
 //--- Display
@@ -779,6 +830,13 @@ impl AsId {
         cons.take_u32().map(AsId)
     }
 
+    /// Takes an optional AS number from the beginning of an encoded value.
+    pub fn take_opt_from<S: decode::Source>(
+        cons: &mut decode::Constructed<S>
+    ) -> Result<Option<Self>, S::Err> {
+        cons.take_opt_u32().map(|res| res.map(AsId))
+    }
+
     /*
     /// Skips over the AS number at the beginning of an encoded value.
     fn skip_in<S: decode::Source>(
@@ -927,6 +985,56 @@ mod test {
         assert_eq!(expected_str, &block.to_string())
     }
 
+    #[test]
+    fn as_blocks_difference() {
+        let this = AsBlocks::from_str("AS1-AS10, AS20").unwrap();
+        let other = AsBlocks::from_str("AS0, AS5, AS10-AS15").unwrap();
+        assert_eq!(
+            this.difference(&other).to_string(),
+            "AS1-AS4, AS6-AS9, AS20"
+        );
+        assert_eq!(
+            other.difference(&this).to_string(),
+            "AS0, AS11-AS15"
+        );
+        assert_eq!(
+            this.symmetric_difference(&other).to_string(),
+            "AS0-AS4, AS6-AS9, AS11-AS15, AS20"
+        );
+        assert_eq!(
+            this.difference(&other).union(&this.intersection(&other)),
+            this
+        );
+        assert_eq!(
+            AsBlocks::from_str("AS0-AS4294967295").unwrap().difference(
+                &AsBlocks::from_str("AS0, AS4294967295").unwrap()
+            ).to_string(),
+            "AS1-AS4294967294"
+        );
+    }
+
+    #[test]
+    fn as_blocks_validate_issued() {
+        let issuer = AsBlocks::from_str("AS1-AS10").unwrap();
+        let res = AsResources::blocks(
+            AsBlocks::from_str("AS5, AS8-AS12").unwrap()
+        );
+        assert_eq!(
+            issuer.validate_issued(
+                Some(&res), Overclaim::Trim
+            ).unwrap().to_string(),
+            "AS5, AS8-AS10"
+        );
+        let err = issuer.validate_issued(
+            Some(&res), Overclaim::Refuse
+        ).unwrap_err();
+        assert_eq!(err.kind(), ValidationErrorKind::Overclaim);
+        assert_eq!(
+            err.overclaim_report().unwrap().as_resources().to_string(),
+            "AS11-AS12"
+        );
+    }
+
     #[test]
     fn as_blocks_from_str() {
         let expected_str = "AS1, AS3-AS7";
@@ -31,6 +31,9 @@ pub trait Block: Clone {
     /// Returns the item immediately following the given item.
     fn next(item: Self::Item) -> Option<Self::Item>;
 
+    /// Returns the item immediately preceding the given item.
+    fn prev(item: Self::Item) -> Option<Self::Item>;
+
     /// Returns a pair of the smallest and largest item in the block.
     fn bounds(&self) -> (Self::Item, Self::Item) {
         (self.min(), self.max())
@@ -280,6 +283,82 @@ impl<T: Block> Chain<T> {
         Err(unsafe { OwnedChain::from_vec_unchecked(res) })
     }
 
+    /// Returns the difference between `self` and `other`.
+    ///
+    /// The resulting chain contains all items that are part of `self` but
+    /// not of `other`.
+    pub fn difference<C: AsRef<Chain<T>>>(
+        &self, other: &C
+    ) -> OwnedChain<T> {
+        let mut other = other.as_ref().iter().peekable();
+        let mut res = Vec::new();
+
+        for block in self.iter() {
+            // The start of the part of block we still need to process. If
+            // it is None, we are done with the block.
+            let mut start = Some(block.min());
+            while let Some(item) = start {
+                // Skip over other blocks that end before the current start.
+                // They can’t affect this block nor any later one.
+                let other_block = loop {
+                    match other.peek() {
+                        Some(other_block) if other_block.max() < item => {
+                            other.next();
+                        }
+                        other_block => break other_block
+                    }
+                };
+                match other_block {
+                    Some(other_block) if other_block.min() <= block.max() => {
+                        // Other block overlaps. Keep the part before it (if
+                        // any) and continue after it unless it reaches past
+                        // the end of block. In this case we need to keep it
+                        // around since it may overlap the next block, too.
+                        if other_block.min() > item {
+                            // prev can’t be None since other_block.min() is
+                            // larger than some other item.
+                            res.push(T::new(
+                                item, T::prev(other_block.min()).unwrap()
+                            ));
+                        }
+                        if other_block.max() >= block.max() {
+                            start = None
+                        }
+                        else {
+                            start = T::next(other_block.max());
+                            other.next();
+                        }
+                    }
+                    _ => {
+                        // No more other blocks overlapping this block.
+                        res.push(T::new(item, block.max()));
+                        start = None
+                    }
+                }
+            }
+        }
+
+        // The blocks are ordered and no two of them can be continuous:
+        // blocks from the same self block have at least an other block
+        // between them and self blocks aren’t continuous either.
+        unsafe { OwnedChain::from_vec_unchecked(res) }
+    }
+
+    /// Returns the symmetric difference between `self` and `other`.
+    ///
+    /// The resulting chain contains all items that are part of either
+    /// `self` or `other` but not both.
+    pub fn symmetric_difference<C: AsRef<Chain<T>>>(
+        &self, other: &C
+    ) -> OwnedChain<T> {
+        let other = other.as_ref();
+        let left = self.difference(&other);
+        let right = other.difference(&self);
+
+        // The two differences don’t overlap but can contain continuous
+        // blocks, so we need to go through FromIterator.
+        left.0.into_iter().chain(right.0).collect()
+    }
 }
 
 
@@ -293,6 +372,12 @@ impl<T: Block> ops::Deref for Chain<T> {
     }
 }
 
+impl<T: Block> AsRef<Chain<T>> for Chain<T> {
+    fn as_ref(&self) -> &Chain<T> {
+        self
+    }
+}
+
 impl<T: Block> AsRef<[T]> for Chain<T> {
     fn as_ref(&self) -> &[T] {
         self.as_slice()
@@ -411,17 +496,22 @@ fn from_iter_unsorted<T: Block, I: Iterator<Item=T>>(
     }
     res.sort_unstable_by_key(|block| block.min());
 
-    // The strategy may may lead to consecutive blocks. We can’t have those,
-    // so we need to merge them. This is a bit ugly. Not sure if there is a
-    // cleaner way?
+    // The strategy may may lead to consecutive or, since merged blocks can
+    // grow into blocks added earlier, even overlapping blocks. We can’t
+    // have those, so we need to merge them. This is a bit ugly. Not sure if
+    // there is a cleaner way?
     if res.len() > 1 {
         let mut tail = 0;
         let mut tail_next = T::next(res[0].max());
         for j in 1..res.len() {
-            if Some(res[j].min()) == tail_next {
-                // Neighbouring. Merge j into tail and continue.
-                res[tail] = T::new(res[tail].min(), res[j].max());
-                tail_next = T::next(res[j].max());
+            if res[j].min() <= res[tail].max()
+                        || Some(res[j].min()) == tail_next {
+                // Overlapping or neighbouring. Merge j into tail and
+                // continue.
+                res[tail] = T::new(
+                    res[tail].min(), max(res[tail].max(), res[j].max())
+                );
+                tail_next = T::next(res[tail].max());
             }
             else {
                 // Not neighbouring. Move to the next tail. If this isn’t j,
@@ -598,6 +688,7 @@ mod test {
         fn min(&self) -> u8 { self.0 }
         fn max(&self) -> u8 { self.1 }
         fn next(item: u8) -> Option<u8> { item.checked_add(1) }
+        fn prev(item: u8) -> Option<u8> { item.checked_sub(1) }
     }
 
     #[test]
@@ -646,6 +737,13 @@ mod test {
             ).as_slice(),
             &[(0, 1), (3, 9), (20, 22)][..]
         );
+        // Unsorted blocks that overlap only after merging
+        assert_eq!(
+            OwnedChain::from(
+                [(0,0), (2,2), (1,1), (2,2), (3,3)].as_ref()
+            ).as_slice(),
+            &[(0, 3)][..]
+        );
     }
 
     #[test]
@@ -802,6 +900,96 @@ mod test {
         assert_eq!(smaller, intersection);
     }
 
+    #[test]
+    fn difference() {
+        assert_eq!(
+            OwnedChain::from([(10,15), (20,25)].as_ref()).difference(
+                &OwnedChain::from([(2,4), (12,13), (15,21), (30,40)].as_ref())
+            ),
+            OwnedChain::from([(10,11), (14,14), (22,25)].as_ref())
+        );
+        assert_eq!(
+            OwnedChain::from([(0,255)].as_ref()).difference(
+                &OwnedChain::from([(0,0), (255,255)].as_ref())
+            ),
+            OwnedChain::from([(1,254)].as_ref())
+        );
+        assert_eq!(
+            OwnedChain::from([(10,15)].as_ref()).difference(
+                &OwnedChain::<(u8, u8)>::empty()
+            ),
+            OwnedChain::from([(10,15)].as_ref())
+        );
+        assert_eq!(
+            OwnedChain::from([(10,15), (20,25)].as_ref()).difference(
+                &OwnedChain::from([(8,30)].as_ref())
+            ),
+            OwnedChain::empty()
+        );
+    }
+
+    #[test]
+    fn symmetric_difference() {
+        assert_eq!(
+            OwnedChain::from([(10,15)].as_ref()).symmetric_difference(
+                &OwnedChain::from([(16,20)].as_ref())
+            ),
+            OwnedChain::from([(10,20)].as_ref())
+        );
+        assert_eq!(
+            OwnedChain::from([(10,15)].as_ref()).symmetric_difference(
+                &OwnedChain::from([(12,20)].as_ref())
+            ),
+            OwnedChain::from([(10,11), (16,20)].as_ref())
+        );
+    }
+
+    /// Creates a chain from the bits set in `mask`.
+    ///
+    /// Bit _n_ represents the item `base + n`.
+    fn chain_from_mask(mask: u8, base: u8) -> OwnedChain<(u8, u8)> {
+        (0..8u8).filter(|bit| mask & (1 << bit) != 0).map(|bit| {
+            (base + bit, base + bit)
+        }).collect()
+    }
+
+    #[test]
+    fn difference_properties() {
+        // Checks all pairs of chains over a universe of eight items both
+        // at the start and the end of the item range.
+        for &base in &[0, 248] {
+            for left in 0..=255u8 {
+                let left_chain = chain_from_mask(left, base);
+                for right in 0..=255u8 {
+                    let right_chain = chain_from_mask(right, base);
+                    let diff = left_chain.difference(&right_chain);
+                    let sym = left_chain.symmetric_difference(&right_chain);
+                    let intersection = match left_chain.trim(&right_chain) {
+                        Ok(()) => left_chain.clone(),
+                        Err(owned) => owned
+                    };
+                    let union: OwnedChain<_> = left_chain.iter().chain(
+                        right_chain.iter()
+                    ).cloned().collect();
+
+                    assert_eq!(diff, chain_from_mask(left & !right, base));
+                    assert_eq!(sym, chain_from_mask(left ^ right, base));
+
+                    // Difference and intersection partition self.
+                    assert!(diff.is_encompassed(&left_chain));
+                    assert!(diff.difference(&intersection).eq(&diff));
+                    let joined: OwnedChain<_> = diff.iter().chain(
+                        intersection.iter()
+                    ).cloned().collect();
+                    assert_eq!(joined, left_chain);
+
+                    // Symmetric difference is union minus intersection.
+                    assert_eq!(sym, union.difference(&intersection));
+                }
+            }
+        }
+    }
+
     #[test]
     fn eq() {
         let empty = OwnedChain::<(u8, u8)>::empty();
@@ -5,7 +5,7 @@
 use std::{error, fmt};
 use std::str::FromStr;
 use serde::{Deserialize, Serialize};
-use crate::x509::ValidationError;
+use crate::x509::{ValidationError, ValidationErrorKind};
 
 
 //------------ ResourcesChoice -----------------------------------------------
@@ -51,7 +51,9 @@ impl<T> ResourcesChoice<T> {
     pub fn to_blocks(&self) -> Result<T, ValidationError>
     where T: Clone {
         match self {
-            ResourcesChoice::Inherit => Err(ValidationError),
+            ResourcesChoice::Inherit => {
+                Err(ValidationErrorKind::InheritedResources.into())
+            }
             ResourcesChoice::Blocks(ref some) => Ok(some.clone()),
         }
     }
@@ -16,9 +16,9 @@ use std::str::FromStr;
 use bcder::{decode, encode};
 use bcder::{BitString, Mode, OctetString, Tag, xerr};
 use bcder::encode::PrimitiveContent;
-use crate::cert::Overclaim;
+use crate::cert::{Overclaim, OverclaimReport};
 use crate::roa::RoaIpAddress;
-use crate::x509::ValidationError;
+use crate::x509::{ValidationError, ValidationErrorKind};
 use super::chain::{Block, SharedChain};
 use super::choice::ResourcesChoice;
 
@@ -218,6 +218,22 @@ impl<'a> IpBlocksForFamily<'a> {
             blocks
         }
     }
+
+    /// Returns the blocks that are in these blocks but not in `other`.
+    ///
+    /// The result is owned and thus returned as plain `IpBlocks`. Use
+    /// `as_v4` or `as_v6` on it to format it again.
+    pub fn difference(&self, other: &IpBlocks) -> IpBlocks {
+        self.blocks.difference(other)
+    }
+
+    /// Returns the blocks that are in exactly one of these and `other`.
+    ///
+    /// The result is owned and thus returned as plain `IpBlocks`. Use
+    /// `as_v4` or `as_v6` on it to format it again.
+    pub fn symmetric_difference(&self, other: &IpBlocks) -> IpBlocks {
+        self.blocks.symmetric_difference(other)
+    }
 }
 
 impl<'a> fmt::Display for IpBlocksForFamily<'a> {
@@ -262,7 +278,9 @@ impl IpBlocks {
         res: Option<&IpResources>
     ) -> Result<Self, ValidationError> {
         match res.map(|res| &res.0) {
-            Some(ResourcesChoice::Inherit) => Err(ValidationError),
+            Some(ResourcesChoice::Inherit) => {
+                Err(ValidationErrorKind::InheritedResources.into())
+            }
             Some(ResourcesChoice::Blocks(ref some)) => Ok(some.clone()),
             None => Ok(IpBlocks::empty())
         }
@@ -278,7 +296,25 @@ impl IpBlocks {
         self.0.iter()
     }
 
+    /// Returns an iterator over the prefixes making up the blocks.
+    ///
+    /// The iterator returns the smallest possible set of prefixes that
+    /// together cover exactly the blocks, ordered by address.
+    pub fn prefixes(&self) -> impl Iterator<Item=Prefix> + '_ {
+        self.0.iter().flat_map(|block| block.prefixes())
+    }
+
     /// Validates IP resources issued under these blocks.
+    ///
+    /// If the issued resources are not covered by these blocks and `mode`
+    /// is [`Overclaim::Refuse`], the returned error carries an
+    /// [`OverclaimReport`] with the excess resources. Since the blocks
+    /// don't know their address family, these are reported via
+    /// [`OverclaimReport::ip_resources`].
+    ///
+    /// [`Overclaim::Refuse`]: ../cert/enum.Overclaim.html#variant.Refuse
+    /// [`OverclaimReport`]: ../cert/struct.OverclaimReport.html
+    /// [`OverclaimReport::ip_resources`]: ../cert/struct.OverclaimReport.html#method.ip_resources
     pub fn validate_issued(
         &self,
         res: Option<&IpResources>,
@@ -293,7 +329,13 @@ impl IpBlocks {
                             Ok(blocks.clone())
                         }
                         else {
-                            Err(ValidationError)
+                            Err(ValidationError::new(
+                                ValidationErrorKind::Overclaim
+                            ).with_rfc(6487, "4.8.10").with_overclaim(
+                                OverclaimReport::from_ip_blocks(
+                                    blocks.difference(self)
+                                )
+                            ))
                         }
                     }
                     Overclaim::Trim => {
@@ -305,6 +347,18 @@ impl IpBlocks {
         }
     }
 
+    /// Returns the issued IP resources not covered by these blocks.
+    ///
+    /// Inherited resources are always covered, so the result is empty for
+    /// them.
+    pub fn overclaimed(&self, res: Option<&IpResources>) -> IpBlocks {
+        match res.map(|res| &res.0) {
+            Some(ResourcesChoice::Blocks(ref blocks)) => {
+                blocks.difference(self)
+            }
+            _ => Self::empty()
+        }
+    }
 
     /// Returns whether the address blocks cover the given ROA address prefix.
     pub fn contains_roa(&self, addr: &RoaIpAddress) -> bool {
@@ -345,6 +399,24 @@ impl IpBlocks {
             )
         )
     }
+
+    /// Returns a new IpBlocks with the difference of this and the other
+    /// IpBlocks.
+    ///
+    /// i.e. all resources found in this but not the other IpBlocks.
+    pub fn difference(&self, other: &Self) -> Self {
+        IpBlocks(SharedChain::from_owned(self.0.difference(&other.0)))
+    }
+
+    /// Returns a new IpBlocks with the symmetric difference of this and the
+    /// other IpBlocks.
+    ///
+    /// i.e. all resources found in exactly one of the two IpBlocks.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        IpBlocks(SharedChain::from_owned(
+            self.0.symmetric_difference(&other.0)
+        ))
+    }
 }
 
 impl IpBlocks {
@@ -529,6 +601,14 @@ impl IpBlock {
         }
     }
 
+    /// Returns an iterator over the prefixes making up the block.
+    ///
+    /// The iterator returns the smallest possible set of prefixes that
+    /// together cover exactly the block, ordered by address.
+    pub fn prefixes(self) -> PrefixIter {
+        PrefixIter::new(self.min(), self.max())
+    }
+
     /// Formats the block as a IPv4 block.
     pub fn fmt_v4(self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -661,6 +741,10 @@ impl Block for IpBlock {
     fn next(item: Self::Item) -> Option<Self::Item> {
         item.0.checked_add(1).map(Addr)
     }
+
+    fn prev(item: Self::Item) -> Option<Self::Item> {
+        item.0.checked_sub(1).map(Addr)
+    }
 }
 
 
@@ -792,6 +876,14 @@ impl AddressRange {
         }
     }
 
+    /// Returns an iterator over the prefixes making up the range.
+    ///
+    /// The iterator returns the smallest possible set of prefixes that
+    /// together cover exactly the range, ordered by address.
+    pub fn prefixes(self) -> PrefixIter {
+        PrefixIter::new(self.min, self.max)
+    }
+
     /// Formats the range as an IPv4 range.
     pub fn fmt_v4(self, f: &mut fmt::Formatter) -> fmt::Result {
         let min = self.min.to_v4();
@@ -907,6 +999,10 @@ impl Block for AddressRange {
     fn next(item: Self::Item) -> Option<Self::Item> {
         item.0.checked_add(1).map(Addr)
     }
+
+    fn prev(item: Self::Item) -> Option<Self::Item> {
+        item.0.checked_sub(1).map(Addr)
+    }
 }
 
 
@@ -1135,6 +1231,59 @@ impl encode::PrimitiveContent for Prefix {
 }
 
 
+//------------ PrefixIter ----------------------------------------------------
+
+/// An iterator over the prefixes making up an address range.
+///
+/// The iterator is returned by the `prefixes` methods of [`IpBlock`] and
+/// [`IpBlocks`] as well as address ranges.
+///
+/// [`IpBlock`]: enum.IpBlock.html
+/// [`IpBlocks`]: struct.IpBlocks.html
+#[derive(Clone, Debug)]
+pub struct PrefixIter {
+    /// The part of the range not yet returned.
+    ///
+    /// This is `None` when the whole range has been returned.
+    range: Option<(Addr, Addr)>,
+}
+
+impl PrefixIter {
+    /// Creates a new iterator for the range from `min` to `max`.
+    fn new(min: Addr, max: Addr) -> Self {
+        PrefixIter { range: Some((min, max)) }
+    }
+}
+
+impl Iterator for PrefixIter {
+    type Item = Prefix;
+
+    fn next(&mut self) -> Option<Prefix> {
+        let (min, max) = self.range?;
+
+        // The largest prefix starting at min is determined by the number of
+        // trailing zeros in min. We have to shorten it until it doesn’t go
+        // beyond max anymore.
+        let mut len = 128 - min.0.trailing_zeros() as u8;
+        while min.to_max(len) > max {
+            len += 1;
+        }
+        let prefix_max = min.to_max(len);
+        self.range = if prefix_max < max {
+            // prefix_max is smaller than some other address, so we can
+            // always add one.
+            Some((Addr(prefix_max.0 + 1), max))
+        }
+        else {
+            None
+        };
+        Some(Prefix::new(min, len))
+    }
+}
+
+impl iter::FusedIterator for PrefixIter { }
+
+
 //------------ Addr ----------------------------------------------------------
 
 /// An adddress.
@@ -1295,7 +1444,7 @@ impl FromStr for Addr {
 //------------ AddressFamily -------------------------------------------------
 
 /// The address family of an IP resources value.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum AddressFamily {
     /// IPv4.
     ///
@@ -1575,6 +1724,174 @@ mod test {
         assert_eq!(expected, other.intersection(&this));
     }
 
+    #[test]
+    fn ip_blocks_validate_issued() {
+        let issuer = IpBlocks::from_str("10.0.0.0/8").unwrap();
+        let res = IpResources::blocks(
+            IpBlocks::from_str("10.1.0.0/16, 192.168.0.0/16").unwrap()
+        );
+        assert_eq!(
+            issuer.validate_issued(
+                Some(&res), Overclaim::Trim
+            ).unwrap().as_v4().to_string(),
+            "10.1.0.0/16"
+        );
+        assert_eq!(
+            issuer.overclaimed(Some(&res)).as_v4().to_string(),
+            "192.168.0.0/16"
+        );
+        assert!(issuer.overclaimed(Some(&IpResources::inherit())).is_empty());
+        let err = issuer.validate_issued(
+            Some(&res), Overclaim::Refuse
+        ).unwrap_err();
+        assert_eq!(err.kind(), ValidationErrorKind::Overclaim);
+        let report = err.overclaim_report().unwrap().clone();
+        assert!(report.v4_resources().is_empty());
+        assert_eq!(
+            report.ip_resources().as_v4().to_string(), "192.168.0.0/16"
+        );
+        let report = report.with_family(AddressFamily::Ipv4);
+        assert!(report.ip_resources().is_empty());
+        assert_eq!(
+            report.v4_resources().as_v4().to_string(), "192.168.0.0/16"
+        );
+    }
+
+    #[test]
+    fn ip_blocks_difference() {
+        let this = IpBlocks::from_str("10.0.0.0/16, 192.168.0.0/24").unwrap();
+        let other = IpBlocks::from_str(
+            "10.0.1.0/24, 192.168.0.0-192.168.0.127, 172.16.0.0/12"
+        ).unwrap();
+        assert_eq!(
+            this.difference(&other).as_v4().to_string(),
+            "10.0.0.0/24, 10.0.2.0-10.0.255.255, 192.168.0.128/25"
+        );
+        assert_eq!(
+            other.difference(&this).as_v4().to_string(),
+            "172.16.0.0/12"
+        );
+        assert_eq!(
+            this.symmetric_difference(&other).as_v4().to_string(),
+            "10.0.0.0/24, 10.0.2.0-10.0.255.255, 172.16.0.0/12, \
+             192.168.0.128/25"
+        );
+        assert_eq!(
+            this.as_v4().difference(&other).as_v4().to_string(),
+            this.difference(&other).as_v4().to_string(),
+        );
+        assert!(this.difference(&this).is_empty());
+        assert!(this.symmetric_difference(&this).is_empty());
+        assert_eq!(this.difference(&IpBlocks::empty()), this);
+
+        // The difference and the intersection make up the whole thing.
+        assert_eq!(
+            this.difference(&other).union(&this.intersection(&other)),
+            this
+        );
+        assert_eq!(
+            this.symmetric_difference(&other),
+            this.union(&other).difference(&this.intersection(&other))
+        );
+    }
+
+    #[test]
+    fn address_range_prefixes() {
+        fn v4(range: &str) -> Vec<Prefix> {
+            AddressRange::from_v4_str(range).unwrap().prefixes().collect()
+        }
+        fn v6(range: &str) -> Vec<Prefix> {
+            AddressRange::from_v6_str(range).unwrap().prefixes().collect()
+        }
+        fn v4_prefixes(prefixes: &[&str]) -> Vec<Prefix> {
+            prefixes.iter().map(|s| Prefix::from_v4_str(s).unwrap()).collect()
+        }
+        fn v6_prefixes(prefixes: &[&str]) -> Vec<Prefix> {
+            prefixes.iter().map(|s| Prefix::from_v6_str(s).unwrap()).collect()
+        }
+
+        assert_eq!(
+            v4("10.0.0.1-10.0.0.6"),
+            v4_prefixes(&[
+                "10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/31", "10.0.0.6/32"
+            ])
+        );
+        assert_eq!(
+            v4("10.0.0.0-10.0.255.255"), v4_prefixes(&["10.0.0.0/16"])
+        );
+        assert_eq!(
+            v4("10.0.0.0-10.2.255.255"),
+            v4_prefixes(&["10.0.0.0/15", "10.2.0.0/16"])
+        );
+        assert_eq!(
+            v4("0.0.0.0-255.255.255.255"), v4_prefixes(&["0.0.0.0/0"])
+        );
+        assert_eq!(
+            v4("255.255.255.254-255.255.255.255"),
+            v4_prefixes(&["255.255.255.254/31"])
+        );
+        assert_eq!(
+            v6("2001:db8::-2001:db8:2::ffff"),
+            v6_prefixes(&["2001:db8::/47", "2001:db8:2::/112"])
+        );
+        assert_eq!(
+            v6("::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"),
+            v6_prefixes(&["::/0"])
+        );
+        assert_eq!(v6("::1-::1"), v6_prefixes(&["::1/128"]));
+    }
+
+    #[test]
+    fn address_range_prefixes_minimal() {
+        // For all ranges within a small address space, check that the
+        // prefixes cover the range without gaps and that no two of them
+        // could be merged into a shorter prefix.
+        let base = Addr::from(Ipv4Addr::new(192, 0, 2, 0));
+        for min in 0..64u32 {
+            for max in min..64 {
+                let range = AddressRange::new(
+                    Addr::from_bits(base.to_bits() + (u128::from(min) << 96)),
+                    Addr::from_bits(
+                        base.to_bits() + (u128::from(max) << 96)
+                    ).to_max(32)
+                );
+                let prefixes = range.prefixes().collect::<Vec<_>>();
+                assert_eq!(prefixes.first().unwrap().min(), range.min());
+                assert_eq!(prefixes.last().unwrap().max(), range.max());
+                for pair in prefixes.windows(2) {
+                    assert_eq!(
+                        pair[0].max().to_bits() + 1, pair[1].min().to_bits()
+                    );
+                    if pair[0].addr_len() == pair[1].addr_len() {
+                        assert_ne!(
+                            pair[0].addr().to_min(pair[0].addr_len() - 1),
+                            pair[1].addr().to_min(pair[1].addr_len() - 1),
+                        );
+                    }
+                }
+                for prefix in &prefixes {
+                    assert!(prefix.addr_len() <= 32);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ip_blocks_prefixes() {
+        let blocks = IpBlocks::from_str(
+            "10.0.0.0/8, 192.168.0.0-192.168.2.255"
+        ).unwrap();
+        assert_eq!(
+            blocks.prefixes().collect::<Vec<_>>(),
+            vec![
+                Prefix::from_v4_str("10.0.0.0/8").unwrap(),
+                Prefix::from_v4_str("192.168.0.0/23").unwrap(),
+                Prefix::from_v4_str("192.168.2.0/24").unwrap(),
+            ]
+        );
+        assert_eq!(IpBlocks::empty().prefixes().count(), 0);
+    }
+
     #[test]
     fn ip_block_from_v4_str() {
         fn check(s: &str, prefix: bool, min: &str, max: &str) {
@@ -19,7 +19,7 @@ use crate::resources::{
 };
 use crate::sigobj::{SignedObject, SignedObjectBuilder};
 use crate::tal::TalInfo;
-use crate::x509::ValidationError;
+use crate::x509::{Time, ValidationError, ValidationErrorKind};
 
 
 //------------ Roa -----------------------------------------------------------
@@ -46,13 +46,24 @@ impl Roa {
     }
 
     pub fn process<F>(
+        self,
+        issuer: &ResourceCert,
+        strict: bool,
+        check_crl: F
+    ) -> Result<RouteOriginAttestation, ValidationError>
+    where F: FnOnce(&Cert) -> Result<(), ValidationError> {
+        self.process_at(issuer, strict, Time::now(), check_crl)
+    }
+
+    pub fn process_at<F>(
         mut self,
         issuer: &ResourceCert,
         strict: bool,
+        now: Time,
         check_crl: F
     ) -> Result<RouteOriginAttestation, ValidationError>
     where F: FnOnce(&Cert) -> Result<(), ValidationError> {
-        let cert = self.signed.validate(issuer, strict)?;
+        let cert = self.signed.validate_at(issuer, strict, now)?;
         check_crl(cert.as_ref())?;
         self.content.validate(cert)?;
         Ok(self.content)
@@ -196,22 +207,30 @@ impl RouteOriginAttestation {
         if !self.v4_addrs.is_empty() {
             let blocks = cert.v4_resources();
             if blocks.is_empty() {
-                return Err(ValidationError)
+                return Err(ValidationError::new(
+                    ValidationErrorKind::ResourcesNotCovered
+                ).with_rfc(6482, "4"))
             }
             for addr in self.v4_addrs.iter() {
                 if !blocks.contains_roa(&addr) {
-                    return Err(ValidationError)
+                    return Err(ValidationError::new(
+                    ValidationErrorKind::ResourcesNotCovered
+                ).with_rfc(6482, "4"))
                 }
             }
         }
         if !self.v6_addrs.is_empty() {
             let blocks = cert.v6_resources();
             if blocks.is_empty() {
-                return Err(ValidationError)
+                return Err(ValidationError::new(
+                    ValidationErrorKind::ResourcesNotCovered
+                ).with_rfc(6482, "4"))
             }
             for addr in self.v6_addrs.iter() {
                 if !blocks.contains_roa(&addr) {
-                    return Err(ValidationError)
+                    return Err(ValidationError::new(
+                    ValidationErrorKind::ResourcesNotCovered
+                ).with_rfc(6482, "4"))
                 }
             }
         }
@@ -268,7 +287,7 @@ impl RoaIpAddresses {
         else {
             Some(encode::sequence((
                 OctetString::encode_slice(family),
-                &self.0
+                encode::sequence(&self.0)
             )))
         }
     }
@@ -590,7 +609,7 @@ impl RoaIpAddressesBuilder {
                 Captured::empty(Mode::Der)
             }
             else {
-                Captured::from_values(Mode::Der, self.encode_ref())
+                Captured::from_values(Mode::Der, self.encode_content())
             }
        )
     }
@@ -608,9 +627,11 @@ impl RoaIpAddressesBuilder {
     }
 
     pub fn encode_ref<'a>(&'a self) -> impl encode::Values + 'a {
-        encode::sequence(
-            encode::slice(self.addrs.as_slice(), |v: &RoaIpAddress| v.encode())
-        )
+        encode::sequence(self.encode_content())
+    }
+
+    fn encode_content<'a>(&'a self) -> impl encode::Values + 'a {
+        encode::slice(self.addrs.as_slice(), |v: &RoaIpAddress| v.encode())
     }
 }
 
@@ -643,6 +664,43 @@ mod test {
             ).is_ok()
         )
     }
+
+    fn addrs(roa: &RouteOriginAttestation) -> Vec<(IpAddr, u8, u8)> {
+        roa.iter().map(|addr| {
+            (addr.address(), addr.address_length(), addr.max_length())
+        }).collect()
+    }
+
+    fn reencode(roa: &RouteOriginAttestation) -> RouteOriginAttestation {
+        let captured = Captured::from_values(Mode::Der, roa.encode_ref());
+        Mode::Der.decode(
+            captured.as_ref(), RouteOriginAttestation::take_from
+        ).unwrap()
+    }
+
+    #[test]
+    fn reencode_attestation() {
+        let roa = Roa::decode(
+            include_bytes!("../test-data/example-ripe.roa").as_ref(),
+            false
+        ).unwrap();
+        assert_eq!(addrs(roa.content()), addrs(&reencode(roa.content())));
+
+        let mut builder = RoaBuilder::new(64496.into());
+        builder.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, Some(26));
+        builder.push_v6_addr(
+            Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, None
+        );
+        let roa = builder.to_attestation();
+        assert_eq!(
+            addrs(&roa),
+            vec![
+                ("192.0.2.0".parse().unwrap(), 24, 26),
+                ("2001:db8::".parse().unwrap(), 32, 32),
+            ]
+        );
+        assert_eq!(addrs(&roa), addrs(&reencode(&roa)));
+    }
 }
 
 #[cfg(all(test, feature="softkeys"))]
@@ -1,11 +1,14 @@
-//! Parsing the XML representations.
+//! Parsing and writing the XML representations.
 
-use std::{fmt, io, ops, str};
+use std::{error, fmt, io, ops, slice, str};
+use std::collections::HashMap;
+use bytes::Bytes;
 use log::info;
 use ring::digest;
 use uuid::Uuid;
 use crate::uri;
 use crate::xml::decode::{Reader, Name, Error};
+use crate::xml::encode;
 
 
 //------------ NotificationFile ----------------------------------------------
@@ -120,9 +123,683 @@ impl NotificationFile {
             _ => Err(Error::Malformed)
         }
     }
+
+    /// Determines what needs to be fetched to update local state.
+    ///
+    /// The local state is given by the session ID and serial number of
+    /// the last snapshot or delta successfully applied. The method
+    /// returns whether the local state is up to date, which deltas need
+    /// to be applied in order, or whether the snapshot needs to be
+    /// fetched and why.
+    ///
+    /// The method fails if the notification file lists the same serial
+    /// number twice or if the serial numbers of the deltas aren’t
+    /// contiguous.
+    pub fn plan_update(
+        &self,
+        session_id: Uuid,
+        serial: usize,
+    ) -> Result<UpdatePlan<'_>, DeltaListError> {
+        let deltas = self.sorted_deltas()?;
+        if session_id != self.session_id {
+            return Ok(UpdatePlan::Snapshot(SnapshotReason::SessionChanged))
+        }
+        if serial > self.serial {
+            return Ok(UpdatePlan::Snapshot(SnapshotReason::LocalNewer))
+        }
+        if serial == self.serial {
+            return Ok(UpdatePlan::UpToDate)
+        }
+        match deltas.last() {
+            Some(&(last, _)) if last == self.serial => { }
+            Some(_) => {
+                return Ok(UpdatePlan::Snapshot(SnapshotReason::DeltaGap))
+            }
+            None => {
+                return Ok(UpdatePlan::Snapshot(SnapshotReason::Truncated))
+            }
+        }
+        // deltas isn’t empty or we’d have returned above.
+        if deltas[0].0 > serial + 1 {
+            return Ok(UpdatePlan::Snapshot(SnapshotReason::Truncated))
+        }
+        Ok(UpdatePlan::Deltas(
+            deltas.into_iter().filter(|item| item.0 > serial).collect()
+        ))
+    }
+
+    /// Returns the deltas ordered by serial number.
+    ///
+    /// Checks that the serial numbers are unique and contiguous.
+    fn sorted_deltas(
+        &self
+    ) -> Result<Vec<(usize, &UriAndHash)>, DeltaListError> {
+        let mut res: Vec<_> = self.deltas.iter().map(|(serial, delta)| {
+            (*serial, delta)
+        }).collect();
+        res.sort_by_key(|item| item.0);
+        for pair in res.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                return Err(DeltaListError::Duplicate(pair[0].0))
+            }
+            if pair[0].0 + 1 != pair[1].0 {
+                return Err(DeltaListError::NonContiguous(pair[0].0))
+            }
+        }
+        Ok(res)
+    }
+
+    /// Writes the notification file as RFC 8182 XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(NOTIFICATION.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .attr(b"session_id", &self.session_id)?
+            .attr(b"serial", &self.serial)?
+            .content(|content| {
+                content.element(SNAPSHOT.local())?
+                    .attr(b"uri", self.snapshot.uri())?
+                    .attr(b"hash", self.snapshot.hash())?
+                    .done()?;
+                for (serial, delta) in &self.deltas {
+                    content.element(DELTA.local())?
+                        .attr(b"serial", serial)?
+                        .attr(b"uri", delta.uri())?
+                        .attr(b"hash", delta.hash())?
+                        .done()?;
+                }
+                Ok(())
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ UpdatePlan ----------------------------------------------------
+
+/// What needs to be done to bring local state up to date.
+///
+/// This is returned by `NotificationFile::plan_update`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum UpdatePlan<'a> {
+    /// The local state is current.
+    UpToDate,
+
+    /// The deltas need to be applied in the given order.
+    ///
+    /// Each element contains the serial number of a delta and its URI and
+    /// hash.
+    Deltas(Vec<(usize, &'a UriAndHash)>),
+
+    /// The snapshot needs to be fetched.
+    Snapshot(SnapshotReason),
+}
+
+
+//------------ SnapshotReason ------------------------------------------------
+
+/// The reason why the snapshot needs to be fetched.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SnapshotReason {
+    /// The session ID has changed.
+    SessionChanged,
+
+    /// The deltas don’t lead up to the notification file’s serial number.
+    DeltaGap,
+
+    /// The local serial number is newer than the remote one.
+    LocalNewer,
+
+    /// The deltas needed to update the local state aren’t listed anymore.
+    Truncated,
+}
+
+impl fmt::Display for SnapshotReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            SnapshotReason::SessionChanged => "session ID changed",
+            SnapshotReason::DeltaGap => "deltas don’t reach current serial",
+            SnapshotReason::LocalNewer => "local serial newer than remote",
+            SnapshotReason::Truncated => "required deltas not available",
+        })
+    }
+}
+
+
+//------------ DeltaListError ------------------------------------------------
+
+/// The list of deltas in a notification file is invalid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeltaListError {
+    /// The given serial number appears more than once.
+    Duplicate(usize),
+
+    /// The delta following the given serial number is missing.
+    NonContiguous(usize),
+}
+
+impl fmt::Display for DeltaListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DeltaListError::Duplicate(serial) => {
+                write!(f, "duplicate delta for serial {}", serial)
+            }
+            DeltaListError::NonContiguous(serial) => {
+                write!(f, "missing delta after serial {}", serial)
+            }
+        }
+    }
+}
+
+impl error::Error for DeltaListError { }
+
+
+//------------ Snapshot ------------------------------------------------------
+
+/// The content of an RRDP snapshot file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    session_id: Uuid,
+    serial: usize,
+    elements: Vec<PublishElement>,
+}
+
+impl Snapshot {
+    /// Creates a new, empty snapshot for the given session and serial.
+    pub fn new(session_id: Uuid, serial: usize) -> Self {
+        Snapshot { session_id, serial, elements: Vec::new() }
+    }
+
+    /// Parses a snapshot file.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut res = Snapshot::new(Uuid::nil(), 0);
+        res.process(reader)?;
+        Ok(res)
+    }
+
+    /// Parses a snapshot file and verifies its SHA-256 hash.
+    ///
+    /// The snapshot is only returned if the file’s hash matches `hash`.
+    pub fn parse_verified<R: io::BufRead>(
+        reader: R, hash: &DigestHex
+    ) -> Result<Self, ProcessError> {
+        let mut reader = HashRead::new(reader);
+        let res = Self::parse(&mut reader)?;
+        reader.verify(hash)?;
+        Ok(res)
+    }
+
+    /// Returns the session ID of the snapshot.
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Returns the serial number of the snapshot.
+    pub fn serial(&self) -> usize {
+        self.serial
+    }
+
+    /// Returns the published objects of the snapshot.
+    pub fn elements(&self) -> &[PublishElement] {
+        &self.elements
+    }
+
+    /// Returns an iterator over the published objects.
+    pub fn iter(&self) -> slice::Iter<'_, PublishElement> {
+        self.elements.iter()
+    }
+
+    /// Converts the snapshot into its published objects.
+    pub fn into_elements(self) -> Vec<PublishElement> {
+        self.elements
+    }
+
+    /// Adds a published object to the snapshot.
+    pub fn push_publish(&mut self, uri: uri::Rsync, data: Bytes) {
+        self.elements.push(PublishElement::new(uri, data))
+    }
+
+    /// Applies a delta to the snapshot, returning the next snapshot.
+    ///
+    /// The delta must be for the same session and for the serial number
+    /// directly following that of the snapshot. Objects published without
+    /// a hash must not exist yet, while for updated and withdrawn objects
+    /// the hash must match that of the object currently in the snapshot.
+    ///
+    /// The order of objects is preserved with newly published objects
+    /// added at the end.
+    pub fn apply_delta(&self, delta: &Delta) -> Result<Self, DeltaError> {
+        if delta.session_id != self.session_id {
+            return Err(DeltaError::SessionMismatch {
+                expected: self.session_id, found: delta.session_id
+            })
+        }
+        if delta.serial != self.serial.wrapping_add(1) {
+            return Err(DeltaError::SerialMismatch {
+                expected: self.serial.wrapping_add(1), found: delta.serial
+            })
+        }
+
+        let mut elements: Vec<_> = self.elements.iter().cloned().map(Some)
+            .collect();
+        let mut index: HashMap<_, _> = self.elements.iter().enumerate()
+            .map(|(idx, item)| (item.uri.clone(), idx)).collect();
+
+        for item in delta.iter() {
+            match *item {
+                DeltaElement::Publish { ref uri, ref hash, ref data } => {
+                    match hash {
+                        Some(hash) => {
+                            let idx = Self::check_hash(
+                                &elements, &index, uri, hash
+                            )?;
+                            elements[idx] = Some(
+                                PublishElement::new(uri.clone(), data.clone())
+                            );
+                        }
+                        None => {
+                            if index.contains_key(uri) {
+                                return Err(
+                                    DeltaError::ObjectAlreadyPresent(
+                                        uri.clone()
+                                    )
+                                )
+                            }
+                            index.insert(uri.clone(), elements.len());
+                            elements.push(Some(
+                                PublishElement::new(uri.clone(), data.clone())
+                            ));
+                        }
+                    }
+                }
+                DeltaElement::Withdraw { ref uri, ref hash } => {
+                    let idx = Self::check_hash(&elements, &index, uri, hash)?;
+                    elements[idx] = None;
+                    index.remove(uri);
+                }
+            }
+        }
+
+        Ok(Snapshot {
+            session_id: self.session_id,
+            serial: delta.serial,
+            elements: elements.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Checks that the current object for `uri` has the given hash.
+    ///
+    /// Returns the index of the object in `elements`.
+    fn check_hash(
+        elements: &[Option<PublishElement>],
+        index: &HashMap<uri::Rsync, usize>,
+        uri: &uri::Rsync,
+        hash: &DigestHex,
+    ) -> Result<usize, DeltaError> {
+        let idx = match index.get(uri) {
+            Some(idx) => *idx,
+            None => return Err(DeltaError::MissingObject(uri.clone()))
+        };
+        let data = match elements[idx] {
+            Some(ref item) => item.data(),
+            None => return Err(DeltaError::MissingObject(uri.clone()))
+        };
+        if digest::digest(&digest::SHA256, data).as_ref() != hash.as_ref() {
+            return Err(DeltaError::ObjectHashMismatch(uri.clone()))
+        }
+        Ok(idx)
+    }
+
+    /// Writes the snapshot as RFC 8182 XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(SNAPSHOT.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .attr(b"session_id", &self.session_id)?
+            .attr(b"serial", &self.serial)?
+            .content(|content| {
+                for item in &self.elements {
+                    content.element(PUBLISH.local())?
+                        .attr(b"uri", item.uri())?
+                        .content(|content| content.base64(item.data()))?
+                        .done()?;
+                }
+                Ok(())
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//--- ProcessSnapshot
+
+impl ProcessSnapshot for Snapshot {
+    type Err = Error;
+
+    fn meta(
+        &mut self,
+        session_id: Uuid,
+        serial: usize
+    ) -> Result<(), Self::Err> {
+        self.session_id = session_id;
+        self.serial = serial;
+        self.elements.clear();
+        Ok(())
+    }
+
+    fn publish(
+        &mut self,
+        uri: uri::Rsync,
+        data: Vec<u8>,
+    ) -> Result<(), Self::Err> {
+        self.push_publish(uri, data.into());
+        Ok(())
+    }
+}
+
+
+//--- IntoIterator
+
+impl<'a> IntoIterator for &'a Snapshot {
+    type Item = &'a PublishElement;
+    type IntoIter = slice::Iter<'a, PublishElement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+
+//------------ PublishElement ------------------------------------------------
+
+/// A published object in a snapshot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublishElement {
+    uri: uri::Rsync,
+    data: Bytes,
+}
+
+impl PublishElement {
+    pub fn new(uri: uri::Rsync, data: Bytes) -> Self {
+        PublishElement { uri, data }
+    }
+
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+}
+
+
+//------------ Delta ---------------------------------------------------------
+
+/// The content of an RRDP delta file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Delta {
+    session_id: Uuid,
+    serial: usize,
+    elements: Vec<DeltaElement>,
+}
+
+impl Delta {
+    /// Creates a new, empty delta for the given session and serial.
+    pub fn new(session_id: Uuid, serial: usize) -> Self {
+        Delta { session_id, serial, elements: Vec::new() }
+    }
+
+    /// Parses a delta file.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut res = Delta::new(Uuid::nil(), 0);
+        res.process(reader)?;
+        Ok(res)
+    }
+
+    /// Parses a delta file and verifies its SHA-256 hash.
+    ///
+    /// The delta is only returned if the file’s hash matches `hash`.
+    pub fn parse_verified<R: io::BufRead>(
+        reader: R, hash: &DigestHex
+    ) -> Result<Self, ProcessError> {
+        let mut reader = HashRead::new(reader);
+        let res = Self::parse(&mut reader)?;
+        reader.verify(hash)?;
+        Ok(res)
+    }
+
+    /// Returns the session ID of the delta.
+    pub fn session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Returns the serial number of the delta.
+    pub fn serial(&self) -> usize {
+        self.serial
+    }
+
+    /// Returns the changes contained in the delta.
+    pub fn elements(&self) -> &[DeltaElement] {
+        &self.elements
+    }
+
+    /// Returns an iterator over the changes in the delta.
+    pub fn iter(&self) -> slice::Iter<'_, DeltaElement> {
+        self.elements.iter()
+    }
+
+    /// Converts the delta into its changes.
+    pub fn into_elements(self) -> Vec<DeltaElement> {
+        self.elements
+    }
+
+    /// Adds a new or updated object to the delta.
+    ///
+    /// If the object replaces an existing object, `hash` has to contain
+    /// the SHA-256 hash of the object being replaced.
+    pub fn push_publish(
+        &mut self, uri: uri::Rsync, hash: Option<DigestHex>, data: Bytes
+    ) {
+        self.elements.push(DeltaElement::Publish { uri, hash, data })
+    }
+
+    /// Adds the withdrawal of an object with the given hash to the delta.
+    pub fn push_withdraw(&mut self, uri: uri::Rsync, hash: DigestHex) {
+        self.elements.push(DeltaElement::Withdraw { uri, hash })
+    }
+
+    /// Writes the delta as RFC 8182 XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        writer.element(DELTA.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .attr(b"session_id", &self.session_id)?
+            .attr(b"serial", &self.serial)?
+            .content(|content| {
+                for item in &self.elements {
+                    match *item {
+                        DeltaElement::Publish {
+                            ref uri, ref hash, ref data
+                        } => {
+                            content.element(PUBLISH.local())?
+                                .attr(b"uri", uri)?
+                                .opt_attr(b"hash", hash.as_ref())?
+                                .content(|content| content.base64(data))?
+                                .done()?;
+                        }
+                        DeltaElement::Withdraw { ref uri, ref hash } => {
+                            content.element(WITHDRAW.local())?
+                                .attr(b"uri", uri)?
+                                .attr(b"hash", hash)?
+                                .done()?;
+                        }
+                    }
+                }
+                Ok(())
+            })?
+            .done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//--- ProcessDelta
+
+impl ProcessDelta for Delta {
+    type Err = Error;
+
+    fn meta(
+        &mut self,
+        session_id: Uuid,
+        serial: usize
+    ) -> Result<(), Self::Err> {
+        self.session_id = session_id;
+        self.serial = serial;
+        self.elements.clear();
+        Ok(())
+    }
+
+    fn publish(
+        &mut self,
+        uri: uri::Rsync,
+        hash: Option<DigestHex>,
+        data: Vec<u8>,
+    ) -> Result<(), Self::Err> {
+        self.push_publish(uri, hash, data.into());
+        Ok(())
+    }
+
+    fn withdraw(
+        &mut self,
+        uri: uri::Rsync,
+        hash: DigestHex,
+    ) -> Result<(), Self::Err> {
+        self.push_withdraw(uri, hash);
+        Ok(())
+    }
+}
+
+
+//--- IntoIterator
+
+impl<'a> IntoIterator for &'a Delta {
+    type Item = &'a DeltaElement;
+    type IntoIter = slice::Iter<'a, DeltaElement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 
+//------------ DeltaElement --------------------------------------------------
+
+/// A single change contained in a delta.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeltaElement {
+    /// A new object or an update to an existing object.
+    ///
+    /// If the object is updated, `hash` contains the hash of the object
+    /// being replaced.
+    Publish {
+        uri: uri::Rsync,
+        hash: Option<DigestHex>,
+        data: Bytes,
+    },
+
+    /// An object is withdrawn.
+    Withdraw {
+        uri: uri::Rsync,
+        hash: DigestHex,
+    },
+}
+
+impl DeltaElement {
+    /// Returns the URI of the object affected by the change.
+    pub fn uri(&self) -> &uri::Rsync {
+        match *self {
+            DeltaElement::Publish { ref uri, .. } => uri,
+            DeltaElement::Withdraw { ref uri, .. } => uri,
+        }
+    }
+
+    /// Returns the hash of the object being replaced or withdrawn.
+    pub fn hash(&self) -> Option<&DigestHex> {
+        match *self {
+            DeltaElement::Publish { ref hash, .. } => hash.as_ref(),
+            DeltaElement::Withdraw { ref hash, .. } => Some(hash),
+        }
+    }
+}
+
+
+//------------ DeltaError ----------------------------------------------------
+
+/// A delta could not be applied to a snapshot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DeltaError {
+    /// The delta is for a different session.
+    SessionMismatch { expected: Uuid, found: Uuid },
+
+    /// The delta doesn’t follow the snapshot’s serial number.
+    SerialMismatch { expected: usize, found: usize },
+
+    /// A new object was published for a URI already present.
+    ObjectAlreadyPresent(uri::Rsync),
+
+    /// An object to be updated or withdrawn is not present.
+    MissingObject(uri::Rsync),
+
+    /// An object to be updated or withdrawn has a different hash.
+    ObjectHashMismatch(uri::Rsync),
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DeltaError::SessionMismatch { expected, found } => {
+                write!(
+                    f, "session ID mismatch (expected {}, found {})",
+                    expected, found
+                )
+            }
+            DeltaError::SerialMismatch { expected, found } => {
+                write!(
+                    f, "serial number mismatch (expected {}, found {})",
+                    expected, found
+                )
+            }
+            DeltaError::ObjectAlreadyPresent(ref uri) => {
+                write!(f, "object {} already present", uri)
+            }
+            DeltaError::MissingObject(ref uri) => {
+                write!(f, "object {} not present", uri)
+            }
+            DeltaError::ObjectHashMismatch(ref uri) => {
+                write!(f, "hash mismatch for object {}", uri)
+            }
+        }
+    }
+}
+
+impl error::Error for DeltaError { }
+
+
 //------------ ProcessSnapshot -----------------------------------------------
 
 pub trait ProcessSnapshot {
@@ -230,6 +907,32 @@ pub trait ProcessSnapshot {
         reader.end()?;
         Ok(())
     }
+
+    /// Processes a snapshot file and verifies its SHA-256 hash.
+    ///
+    /// The file is parsed into a [`Snapshot`] first while calculating the
+    /// hash. Only if it matches `hash` are the `meta` and `publish`
+    /// callbacks invoked for its content. Otherwise, a `DigestMismatch`
+    /// error is returned and no callback is invoked at all.
+    ///
+    /// [`Snapshot`]: struct.Snapshot.html
+    fn process_verified<R: io::BufRead>(
+        &mut self,
+        reader: R,
+        hash: &DigestHex,
+    ) -> Result<(), Self::Err>
+    where Self::Err: From<DigestMismatch> {
+        let snapshot = match Snapshot::parse_verified(reader, hash) {
+            Ok(snapshot) => snapshot,
+            Err(ProcessError::Xml(err)) => return Err(err.into()),
+            Err(ProcessError::DigestMismatch(err)) => return Err(err.into()),
+        };
+        self.meta(snapshot.session_id, snapshot.serial)?;
+        for item in snapshot.into_elements() {
+            self.publish(item.uri, item.data.to_vec())?;
+        }
+        Ok(())
+    }
 }
 
 
@@ -354,12 +1057,45 @@ pub trait ProcessDelta {
         Ok(())
     }
 
+    /// Processes a delta file and verifies its SHA-256 hash.
+    ///
+    /// The file is parsed into a [`Delta`] first while calculating the
+    /// hash. Only if it matches `hash` are the `meta`, `publish`, and
+    /// `withdraw` callbacks invoked for its content. Otherwise, a
+    /// `DigestMismatch` error is returned and no callback is invoked at
+    /// all.
+    ///
+    /// [`Delta`]: struct.Delta.html
+    fn process_verified<R: io::BufRead>(
+        &mut self,
+        reader: R,
+        hash: &DigestHex,
+    ) -> Result<(), Self::Err>
+    where Self::Err: From<DigestMismatch> {
+        let delta = match Delta::parse_verified(reader, hash) {
+            Ok(delta) => delta,
+            Err(ProcessError::Xml(err)) => return Err(err.into()),
+            Err(ProcessError::DigestMismatch(err)) => return Err(err.into()),
+        };
+        self.meta(delta.session_id, delta.serial)?;
+        for item in delta.into_elements() {
+            match item {
+                DeltaElement::Publish { uri, hash, data } => {
+                    self.publish(uri, hash, data.to_vec())?
+                }
+                DeltaElement::Withdraw { uri, hash } => {
+                    self.withdraw(uri, hash)?
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 
 //------------ UriAndHash ----------------------------------------------------
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct UriAndHash {
     uri: uri::Https,
     hash: DigestHex,
@@ -383,7 +1119,7 @@ impl UriAndHash {
 //------------ DigestHex -----------------------------------------------------
 
 /// A helper type to encode a digest as a sequence of hex-digits.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DigestHex(Vec<u8>);
 
 impl From<Vec<u8>> for DigestHex {
@@ -439,6 +1175,137 @@ impl fmt::Display for DigestHex {
 }
 
 
+//------------ HashRead ------------------------------------------------------
+
+/// A reader adapter calculating the SHA-256 hash of all data read.
+///
+/// The adapter can be used both via `io::Read` and `io::BufRead`. Once
+/// everything has been read, the hash can be checked via `verify`.
+pub struct HashRead<R> {
+    reader: R,
+    context: digest::Context,
+}
+
+impl<R> HashRead<R> {
+    /// Creates a new hashing reader atop an underlying reader.
+    pub fn new(reader: R) -> Self {
+        HashRead {
+            reader,
+            context: digest::Context::new(&digest::SHA256),
+        }
+    }
+
+    /// Returns the hash of the data read so far.
+    pub fn into_digest(self) -> digest::Digest {
+        self.context.finish()
+    }
+
+    /// Compares the hash of the data read so far to `expected`.
+    ///
+    /// The parsers for the RRDP files read their input to the end, so
+    /// this can be used directly after successful parsing.
+    pub fn verify(self, expected: &DigestHex) -> Result<(), DigestMismatch> {
+        let found = DigestHex::from(self.into_digest());
+        if found != *expected {
+            return Err(DigestMismatch { expected: expected.clone(), found })
+        }
+        Ok(())
+    }
+}
+
+impl<R: io::Read> io::Read for HashRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let len = self.reader.read(buf)?;
+        self.context.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R: io::BufRead> io::BufRead for HashRead<R> {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // Calling `fill_buf` again returns the still unconsumed buffer
+        // without further reading, so this can’t actually fail.
+        if let Ok(buf) = self.reader.fill_buf() {
+            self.context.update(&buf[..amt]);
+        }
+        self.reader.consume(amt)
+    }
+}
+
+
+//------------ DigestMismatch ------------------------------------------------
+
+/// The hash of an RRDP file differs from the one announced for it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DigestMismatch {
+    expected: DigestHex,
+    found: DigestHex,
+}
+
+impl DigestMismatch {
+    /// Returns the hash that was announced for the file.
+    pub fn expected(&self) -> &DigestHex {
+        &self.expected
+    }
+
+    /// Returns the hash of the file actually read.
+    pub fn found(&self) -> &DigestHex {
+        &self.found
+    }
+}
+
+impl fmt::Display for DigestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "hash mismatch (expected {}, found {})",
+            self.expected, self.found
+        )
+    }
+}
+
+impl error::Error for DigestMismatch { }
+
+
+//------------ ProcessError --------------------------------------------------
+
+/// An error happened while parsing and verifying an RRDP file.
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The file was not correctly formatted XML.
+    Xml(Error),
+
+    /// The file’s hash didn’t match the expected hash.
+    DigestMismatch(DigestMismatch),
+}
+
+impl From<Error> for ProcessError {
+    fn from(err: Error) -> Self {
+        ProcessError::Xml(err)
+    }
+}
+
+impl From<DigestMismatch> for ProcessError {
+    fn from(err: DigestMismatch) -> Self {
+        ProcessError::DigestMismatch(err)
+    }
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcessError::Xml(ref err) => err.fmt(f),
+            ProcessError::DigestMismatch(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for ProcessError { }
+
+
 //------------ Action --------------------------------------------------------
 
 enum Action {
@@ -449,7 +1316,8 @@ enum Action {
 
 //------------ Xml Names -----------------------------------------------------
 
-const NS: &[u8] = b"http://www.ripe.net/rpki/rrdp";
+const NS_STR: &str = "http://www.ripe.net/rpki/rrdp";
+const NS: &[u8] = NS_STR.as_bytes();
 const NOTIFICATION: Name = Name::qualified(NS, b"notification");
 const SNAPSHOT: Name = Name::qualified(NS, b"snapshot");
 const DELTA: Name = Name::qualified(NS, b"delta");
@@ -462,6 +1330,7 @@ const WITHDRAW: Name = Name::qualified(NS, b"withdraw");
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::str::FromStr;
 
     pub struct Test;
 
@@ -536,4 +1405,386 @@ mod test {
             include_bytes!("../test-data/ripe-delta.xml").as_ref()
         ).unwrap();
     }
+
+    #[test]
+    fn notification_round_trip() {
+        let notify = NotificationFile::parse(
+            include_bytes!("../test-data/ripe-notification.xml").as_ref()
+        ).unwrap();
+        let mut xml = Vec::new();
+        notify.write_xml(&mut xml).unwrap();
+        let parsed = NotificationFile::parse(xml.as_slice()).unwrap();
+        assert_eq!(notify.session_id, parsed.session_id);
+        assert_eq!(notify.serial, parsed.serial);
+        assert_eq!(notify.snapshot, parsed.snapshot);
+        assert_eq!(notify.deltas, parsed.deltas);
+    }
+
+    #[test]
+    fn snapshot_round_trip() {
+        let snapshot = Snapshot::parse(
+            include_bytes!("../test-data/ripe-snapshot.xml").as_ref()
+        ).unwrap();
+        assert_eq!(
+            snapshot.session_id().to_string(),
+            "a2d845c4-5b91-4015-a2b7-988c03ce232a"
+        );
+        assert_eq!(snapshot.serial(), 1742);
+        assert_eq!(snapshot.iter().count(), 277);
+        let mut xml = Vec::new();
+        snapshot.write_xml(&mut xml).unwrap();
+        assert_eq!(snapshot, Snapshot::parse(xml.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn delta_round_trip() {
+        let delta = Delta::parse(
+            include_bytes!("../test-data/ripe-delta.xml").as_ref()
+        ).unwrap();
+        let mut xml = Vec::new();
+        delta.write_xml(&mut xml).unwrap();
+        assert_eq!(delta, Delta::parse(xml.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn write_delta() {
+        let session = Uuid::nil();
+        let uri = uri::Rsync::from_str(
+            "rsync://example.com/module/a&b.cer"
+        ).unwrap();
+        let hash = DigestHex::from(vec![0x0a, 0xbc]);
+        let mut delta = Delta::new(session, 12);
+        delta.push_publish(uri.clone(), None, Bytes::from_static(b"foo"));
+        delta.push_publish(
+            uri.clone(), Some(hash.clone()), Bytes::from_static(b"bar")
+        );
+        delta.push_withdraw(uri, hash);
+        let mut xml = Vec::new();
+        delta.write_xml(&mut xml).unwrap();
+        assert_eq!(
+            String::from_utf8(xml.clone()).unwrap(),
+            "<delta xmlns=\"http://www.ripe.net/rpki/rrdp\" version=\"1\" \
+             session_id=\"00000000-0000-0000-0000-000000000000\" \
+             serial=\"12\">\n  \
+             <publish uri=\"rsync://example.com/module/a&amp;b.cer\">\
+             Zm9v</publish>\n  \
+             <publish uri=\"rsync://example.com/module/a&amp;b.cer\" \
+             hash=\"0abc\">YmFy</publish>\n  \
+             <withdraw uri=\"rsync://example.com/module/a&amp;b.cer\" \
+             hash=\"0abc\"/>\n\
+             </delta>\n"
+        );
+        assert_eq!(delta, Delta::parse(xml.as_slice()).unwrap());
+    }
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
+
+    fn sha256(data: &[u8]) -> DigestHex {
+        digest::digest(&digest::SHA256, data).into()
+    }
+
+    #[test]
+    fn apply_delta() {
+        let session = Uuid::nil();
+        let mut snapshot = Snapshot::new(session, 4);
+        snapshot.push_publish(
+            rsync("rsync://example.com/m/a"), Bytes::from_static(b"a")
+        );
+        snapshot.push_publish(
+            rsync("rsync://example.com/m/b"), Bytes::from_static(b"b")
+        );
+        snapshot.push_publish(
+            rsync("rsync://example.com/m/c"), Bytes::from_static(b"c")
+        );
+
+        let mut delta = Delta::new(session, 5);
+        delta.push_withdraw(rsync("rsync://example.com/m/a"), sha256(b"a"));
+        delta.push_publish(
+            rsync("rsync://example.com/m/b"), Some(sha256(b"b")),
+            Bytes::from_static(b"b2")
+        );
+        delta.push_publish(
+            rsync("rsync://example.com/m/d"), None,
+            Bytes::from_static(b"d")
+        );
+
+        let next = snapshot.apply_delta(&delta).unwrap();
+        assert_eq!(next.session_id(), session);
+        assert_eq!(next.serial(), 5);
+        assert_eq!(
+            next.iter().map(|item| {
+                (item.uri().to_string(), item.data().clone())
+            }).collect::<Vec<_>>(),
+            vec![
+                ("rsync://example.com/m/b".into(), Bytes::from_static(b"b2")),
+                ("rsync://example.com/m/c".into(), Bytes::from_static(b"c")),
+                ("rsync://example.com/m/d".into(), Bytes::from_static(b"d")),
+            ]
+        );
+
+        // Wrong session and serial.
+        let other = Uuid::from_u128(1);
+        assert_eq!(
+            snapshot.apply_delta(&Delta::new(other, 5)),
+            Err(DeltaError::SessionMismatch {
+                expected: session, found: other
+            })
+        );
+        assert_eq!(
+            snapshot.apply_delta(&Delta::new(session, 6)),
+            Err(DeltaError::SerialMismatch { expected: 5, found: 6 })
+        );
+
+        // Publishing over an existing object without a hash.
+        let mut delta = Delta::new(session, 5);
+        delta.push_publish(
+            rsync("rsync://example.com/m/a"), None, Bytes::from_static(b"x")
+        );
+        assert_eq!(
+            snapshot.apply_delta(&delta),
+            Err(DeltaError::ObjectAlreadyPresent(
+                rsync("rsync://example.com/m/a")
+            ))
+        );
+
+        // Replacing with the wrong hash.
+        let mut delta = Delta::new(session, 5);
+        delta.push_publish(
+            rsync("rsync://example.com/m/a"), Some(sha256(b"b")),
+            Bytes::from_static(b"x")
+        );
+        assert_eq!(
+            snapshot.apply_delta(&delta),
+            Err(DeltaError::ObjectHashMismatch(
+                rsync("rsync://example.com/m/a")
+            ))
+        );
+
+        // Withdrawing twice.
+        let mut delta = Delta::new(session, 5);
+        delta.push_withdraw(rsync("rsync://example.com/m/a"), sha256(b"a"));
+        delta.push_withdraw(rsync("rsync://example.com/m/a"), sha256(b"a"));
+        assert_eq!(
+            snapshot.apply_delta(&delta),
+            Err(DeltaError::MissingObject(rsync("rsync://example.com/m/a")))
+        );
+    }
+
+    #[test]
+    fn parse_verified() {
+        let notify = NotificationFile::parse(
+            include_bytes!("../test-data/ripe-notification.xml").as_ref()
+        ).unwrap();
+        let delta_hash = notify.deltas.iter().find(|item| {
+            item.0 == 1739
+        }).unwrap().1.hash().clone();
+        let delta_data = include_bytes!("../test-data/ripe-delta.xml");
+        let snapshot_data = include_bytes!("../test-data/ripe-snapshot.xml");
+
+        assert_eq!(
+            Delta::parse_verified(delta_data.as_ref(), &delta_hash).unwrap(),
+            Delta::parse(delta_data.as_ref()).unwrap()
+        );
+        assert!(Snapshot::parse_verified(
+            snapshot_data.as_ref(), &sha256(snapshot_data)
+        ).is_ok());
+
+        // The snapshot in the test data is not the one announced.
+        match Snapshot::parse_verified(
+            snapshot_data.as_ref(), notify.snapshot.hash()
+        ) {
+            Err(ProcessError::DigestMismatch(err)) => {
+                assert_eq!(err.expected(), notify.snapshot.hash());
+                assert_eq!(err.found(), &sha256(snapshot_data));
+            }
+            _ => panic!("hash mismatch not detected")
+        }
+    }
+
+    #[test]
+    fn process_verified() {
+        /// Counts all callbacks.
+        struct Verified(usize);
+
+        impl ProcessSnapshot for Verified {
+            type Err = ProcessError;
+
+            fn meta(
+                &mut self,
+                _session_id: Uuid,
+                _serial: usize
+            ) -> Result<(), Self::Err> {
+                self.0 += 1;
+                Ok(())
+            }
+
+            fn publish(
+                &mut self,
+                _uri: uri::Rsync,
+                _data: Vec<u8>,
+            ) -> Result<(), Self::Err> {
+                self.0 += 1;
+                Ok(())
+            }
+        }
+
+        impl ProcessDelta for Verified {
+            type Err = ProcessError;
+
+            fn meta(
+                &mut self,
+                _session_id: Uuid,
+                _serial: usize
+            ) -> Result<(), Self::Err> {
+                self.0 += 1;
+                Ok(())
+            }
+
+            fn publish(
+                &mut self,
+                _uri: uri::Rsync,
+                _hash: Option<DigestHex>,
+                _data: Vec<u8>,
+            ) -> Result<(), Self::Err> {
+                self.0 += 1;
+                Ok(())
+            }
+
+            fn withdraw(
+                &mut self,
+                _uri: uri::Rsync,
+                _hash: DigestHex,
+            ) -> Result<(), Self::Err> {
+                self.0 += 1;
+                Ok(())
+            }
+        }
+
+        let data = include_bytes!("../test-data/ripe-delta.xml");
+        let mut verified = Verified(0);
+        ProcessDelta::process_verified(
+            &mut verified, data.as_ref(), &sha256(data)
+        ).unwrap();
+        assert_eq!(
+            verified.0,
+            Delta::parse(data.as_ref()).unwrap().elements().len() + 1
+        );
+
+        // With a wrong hash, no callback must be called at all.
+        let mut verified = Verified(0);
+        match ProcessDelta::process_verified(
+            &mut verified, data.as_ref(), &sha256(b"")
+        ) {
+            Err(ProcessError::DigestMismatch(_)) => { }
+            res => panic!("expected digest mismatch, got {:?}", res),
+        }
+        assert_eq!(verified.0, 0);
+
+        let data = include_bytes!("../test-data/ripe-snapshot.xml");
+        let mut verified = Verified(0);
+        ProcessSnapshot::process_verified(
+            &mut verified, data.as_ref(), &sha256(data)
+        ).unwrap();
+        assert_eq!(
+            verified.0,
+            Snapshot::parse(data.as_ref()).unwrap().elements().len() + 1
+        );
+        let mut verified = Verified(0);
+        match ProcessSnapshot::process_verified(
+            &mut verified, data.as_ref(), &sha256(b"")
+        ) {
+            Err(ProcessError::DigestMismatch(_)) => { }
+            res => panic!("expected digest mismatch, got {:?}", res),
+        }
+        assert_eq!(verified.0, 0);
+
+        let mut reader = HashRead::new(data.as_ref());
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(reader.verify(&sha256(data)), Ok(()));
+    }
+
+    #[test]
+    fn plan_update() {
+        let notify = NotificationFile::parse(
+            include_bytes!("../test-data/ripe-notification.xml").as_ref()
+        ).unwrap();
+        let session = notify.session_id;
+        let oldest = notify.deltas.iter().map(|item| item.0).min().unwrap();
+        assert_eq!(notify.serial, 1742);
+
+        assert_eq!(
+            notify.plan_update(session, 1742), Ok(UpdatePlan::UpToDate)
+        );
+        match notify.plan_update(session, 1739).unwrap() {
+            UpdatePlan::Deltas(deltas) => {
+                assert_eq!(
+                    deltas.iter().map(|item| item.0).collect::<Vec<_>>(),
+                    vec![1740, 1741, 1742]
+                );
+                assert!(
+                    deltas[0].1.uri().as_str().ends_with("1740/delta.xml")
+                );
+            }
+            plan => panic!("unexpected plan {:?}", plan)
+        }
+        match notify.plan_update(session, oldest - 1).unwrap() {
+            UpdatePlan::Deltas(deltas) => {
+                assert_eq!(deltas.len(), 1742 - oldest + 1)
+            }
+            plan => panic!("unexpected plan {:?}", plan)
+        }
+        assert_eq!(
+            notify.plan_update(session, oldest - 2),
+            Ok(UpdatePlan::Snapshot(SnapshotReason::Truncated))
+        );
+        assert_eq!(
+            notify.plan_update(session, 1743),
+            Ok(UpdatePlan::Snapshot(SnapshotReason::LocalNewer))
+        );
+        assert_eq!(
+            notify.plan_update(Uuid::nil(), 1742),
+            Ok(UpdatePlan::Snapshot(SnapshotReason::SessionChanged))
+        );
+    }
+
+    #[test]
+    fn plan_update_bad_deltas() {
+        let uri_and_hash = UriAndHash::new(
+            uri::Https::from_str("https://example.com/delta.xml").unwrap(),
+            sha256(b"")
+        );
+        let mut notify = NotificationFile {
+            session_id: Uuid::nil(),
+            serial: 10,
+            snapshot: uri_and_hash.clone(),
+            deltas: vec![
+                (8, uri_and_hash.clone()), (9, uri_and_hash.clone()),
+            ],
+        };
+        assert_eq!(
+            notify.plan_update(Uuid::nil(), 8),
+            Ok(UpdatePlan::Snapshot(SnapshotReason::DeltaGap))
+        );
+        notify.deltas.clear();
+        assert_eq!(
+            notify.plan_update(Uuid::nil(), 9),
+            Ok(UpdatePlan::Snapshot(SnapshotReason::Truncated))
+        );
+        notify.deltas = vec![
+            (10, uri_and_hash.clone()), (8, uri_and_hash.clone()),
+        ];
+        assert_eq!(
+            notify.plan_update(Uuid::nil(), 9),
+            Err(DeltaListError::NonContiguous(8))
+        );
+        notify.deltas = vec![
+            (10, uri_and_hash.clone()), (10, uri_and_hash),
+        ];
+        assert_eq!(
+            notify.plan_update(Uuid::nil(), 9),
+            Err(DeltaListError::Duplicate(10))
+        );
+    }
 }
@@ -16,7 +16,10 @@ use crate::resources::{
     AsBlocksBuilder, AsResources, AsResourcesBuilder, IpBlocksBuilder,
     IpResources, IpResourcesBuilder
 };
-use crate::x509::{Name, Serial, Time, ValidationError, Validity, update_once};
+use crate::x509::{
+    Name, Serial, Time, ValidationError, ValidationErrorKind, Validity,
+    update_once
+};
 
 
 //------------ SignedObject --------------------------------------------------
@@ -191,7 +194,9 @@ impl SignedObject {
         // c. cert is an EE cert with the SubjectKeyIdentifer matching
         //    the sid field of the SignerInfo.
         if self.sid != self.cert.subject_key_identifier() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::SignerIdentifierMismatch
+            ).with_rfc(6488, "3"))
         }
         Ok(())
     }
@@ -206,7 +211,9 @@ impl SignedObject {
             context.finish()
         };
         if digest.as_ref() != self.message_digest.as_ref() {
-            return Err(ValidationError)
+            return Err(ValidationError::new(
+                ValidationErrorKind::MessageDigestMismatch
+            ).with_rfc(6488, "3"))
         }
         let msg = self.signed_attrs.encode_verify();
         self.cert.subject_public_key_info().verify(
@@ -279,7 +286,7 @@ impl SignedObject {
 pub struct SignedAttrs(Captured);
 
 impl SignedAttrs {
-    fn new(
+    pub(crate) fn new(
         content_type: &Oid<Bytes>,
         digest: &MessageDigest,
         signing_time: Option<Time>,
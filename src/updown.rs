@@ -0,0 +1,1356 @@
+//! The provisioning protocol.
+//!
+//! RFC 6492 defines the protocol used by a child CA to request resource
+//! certificates from its parent CA. The protocol uses XML messages that
+//! are exchanged via HTTP. This module provides the type [`Message`] for
+//! these messages. What kind of message it is is determined by its
+//! [`Payload`].
+//!
+//! This module only deals with the XML messages themselves. They are
+//! exchanged wrapped in CMS signed data.
+//!
+//! [`Message`]: struct.Message.html
+//! [`Payload`]: enum.Payload.html
+
+use std::{fmt, io};
+use std::convert::TryFrom;
+use std::str::FromStr;
+use bytes::Bytes;
+use chrono::SecondsFormat;
+use crate::uri;
+use crate::cert::Cert;
+use crate::crypto::KeyIdentifier;
+use crate::csr::Csr;
+use crate::idexchange::Handle;
+use crate::resources::{AsBlocks, IpBlock, IpBlocks, IpBlocksBuilder};
+use crate::x509::Time;
+use crate::xml::decode::{AttrValue, Content, Error, Name, Reader};
+use crate::xml::encode;
+
+
+//------------ Message -------------------------------------------------------
+
+/// A message of the provisioning protocol.
+///
+/// This type is defined in section 3.1 of RFC 6492. Each message has a
+/// sender and a recipient as well as a payload that depends on the type
+/// of the message.
+#[derive(Clone, Debug)]
+pub struct Message {
+    sender: Handle,
+    recipient: Handle,
+    payload: Payload,
+}
+
+impl Message {
+    /// Creates a new message from its components.
+    pub fn new(sender: Handle, recipient: Handle, payload: Payload) -> Self {
+        Message { sender, recipient, payload }
+    }
+
+    /// Returns the handle of the sender of the message.
+    pub fn sender(&self) -> &Handle {
+        &self.sender
+    }
+
+    /// Returns the handle of the recipient of the message.
+    pub fn recipient(&self) -> &Handle {
+        &self.recipient
+    }
+
+    /// Returns the payload of the message.
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Converts the message into its payload.
+    pub fn into_payload(self) -> Payload {
+        self.payload
+    }
+
+    /// Parses a message from its XML representation.
+    pub fn parse<R: io::BufRead>(reader: R) -> Result<Self, Error> {
+        let mut reader = Reader::new(reader);
+        let mut sender = None;
+        let mut recipient = None;
+        let mut message_type: Option<String> = None;
+        let mut outer = reader.start(|element| {
+            if element.name() != MESSAGE {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"version" => {
+                    if value.ascii_into::<u8>()? != 1 {
+                        return Err(Error::Malformed)
+                    }
+                    Ok(())
+                }
+                b"sender" => {
+                    sender = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"recipient" => {
+                    recipient = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"type" => {
+                    message_type = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+
+        let payload = match message_type.as_deref() {
+            Some("list") => Payload::List,
+            Some("list_response") => {
+                let mut classes = Vec::new();
+                while let Some(class) = ResourceClass::take_opt_from(
+                    &mut outer, &mut reader
+                )? {
+                    classes.push(class)
+                }
+                Payload::ListResponse(classes)
+            }
+            Some("issue") => {
+                Payload::Issue(
+                    IssueRequest::take_from(&mut outer, &mut reader)?
+                )
+            }
+            Some("issue_response") => {
+                let class = ResourceClass::take_opt_from(
+                    &mut outer, &mut reader
+                )?.ok_or(Error::Malformed)?;
+                if class.issued.len() != 1 {
+                    return Err(Error::Malformed)
+                }
+                Payload::IssueResponse(class)
+            }
+            Some("revoke") => {
+                Payload::Revoke(
+                    RevocationRequest::take_from(&mut outer, &mut reader)?
+                )
+            }
+            Some("revoke_response") => {
+                Payload::RevokeResponse(
+                    RevocationRequest::take_from(&mut outer, &mut reader)?
+                )
+            }
+            Some("error_response") => {
+                Payload::ErrorResponse(
+                    ErrorResponse::take_from(&mut outer, &mut reader)?
+                )
+            }
+            _ => return Err(Error::Malformed)
+        };
+
+        outer.take_end(&mut reader)?;
+        reader.end()?;
+
+        Ok(Message {
+            sender: sender.ok_or(Error::Malformed)?,
+            recipient: recipient.ok_or(Error::Malformed)?,
+            payload
+        })
+    }
+
+    /// Writes the message as XML.
+    pub fn write_xml(
+        &self, writer: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        let mut writer = encode::Writer::new(writer);
+        let element = writer.element(MESSAGE.local())?
+            .attr(b"xmlns", NS_STR)?
+            .attr(b"version", "1")?
+            .attr(b"sender", &self.sender)?
+            .attr(b"recipient", &self.recipient)?
+            .attr(b"type", self.payload.message_type())?;
+        match self.payload {
+            Payload::List => element,
+            Payload::ListResponse(ref classes) => {
+                element.content(|content| {
+                    for class in classes {
+                        class.write_xml(content)?;
+                    }
+                    Ok(())
+                })?
+            }
+            Payload::Issue(ref request) => {
+                element.content(|content| request.write_xml(content))?
+            }
+            Payload::IssueResponse(ref class) => {
+                element.content(|content| class.write_xml(content))?
+            }
+            Payload::Revoke(ref request) |
+            Payload::RevokeResponse(ref request) => {
+                element.content(|content| request.write_xml(content))?
+            }
+            Payload::ErrorResponse(ref response) => {
+                element.content(|content| response.write_xml(content))?
+            }
+        }.done()?;
+        writer.done()?;
+        Ok(())
+    }
+}
+
+
+//------------ Payload -------------------------------------------------------
+
+/// The payload of a message.
+#[derive(Clone, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum Payload {
+    /// A request for the resource classes available to the child.
+    ///
+    /// See section 3.3.1 of RFC 6492.
+    List,
+
+    /// The resource classes available to the child.
+    ///
+    /// See section 3.3.2 of RFC 6492.
+    ListResponse(Vec<ResourceClass>),
+
+    /// A request for a certificate.
+    ///
+    /// See section 3.4.1 of RFC 6492.
+    Issue(IssueRequest),
+
+    /// The response to an issue request.
+    ///
+    /// The resource class contains exactly one issued certificate. See
+    /// section 3.4.2 of RFC 6492.
+    IssueResponse(ResourceClass),
+
+    /// A request to revoke all certificates for a key.
+    ///
+    /// See section 3.5.1 of RFC 6492.
+    Revoke(RevocationRequest),
+
+    /// The response to a revoke request.
+    ///
+    /// See section 3.5.2 of RFC 6492.
+    RevokeResponse(RevocationRequest),
+
+    /// A request could not be processed.
+    ///
+    /// See section 3.6 of RFC 6492.
+    ErrorResponse(ErrorResponse),
+}
+
+impl Payload {
+    /// Returns the value of the message’s type attribute.
+    pub fn message_type(&self) -> &'static str {
+        match *self {
+            Payload::List => "list",
+            Payload::ListResponse(_) => "list_response",
+            Payload::Issue(_) => "issue",
+            Payload::IssueResponse(_) => "issue_response",
+            Payload::Revoke(_) => "revoke",
+            Payload::RevokeResponse(_) => "revoke_response",
+            Payload::ErrorResponse(_) => "error_response",
+        }
+    }
+}
+
+
+//------------ ResourceClass -------------------------------------------------
+
+/// A resource class available to the child.
+///
+/// This is the `class` element defined in section 3.3.2 of RFC 6492. It
+/// describes the resources the child is entitled to, the certificate of
+/// the parent that will issue certificates for these resources, and the
+/// certificates already issued to the child.
+#[derive(Clone, Debug)]
+pub struct ResourceClass {
+    class_name: String,
+    cert_url: Vec<uri::Rsync>,
+    resource_set: ResourceSet,
+    not_after: Time,
+    suggested_sia_head: Option<uri::Rsync>,
+    issued: Vec<IssuedCert>,
+    issuer: Cert,
+}
+
+impl ResourceClass {
+    /// Creates a new resource class without any issued certificates.
+    pub fn new(
+        class_name: String,
+        cert_url: Vec<uri::Rsync>,
+        resource_set: ResourceSet,
+        not_after: Time,
+        issuer: Cert,
+    ) -> Self {
+        ResourceClass {
+            class_name, cert_url, resource_set, not_after,
+            suggested_sia_head: None,
+            issued: Vec::new(),
+            issuer
+        }
+    }
+
+    /// Returns the name of the class.
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Returns the URIs where the issuer certificate can be found.
+    pub fn cert_url(&self) -> &[uri::Rsync] {
+        &self.cert_url
+    }
+
+    /// Returns the resources available in the class.
+    pub fn resource_set(&self) -> &ResourceSet {
+        &self.resource_set
+    }
+
+    /// Returns the time when the entitlement to the resources ends.
+    pub fn not_after(&self) -> Time {
+        self.not_after
+    }
+
+    /// Returns the suggested base URI for the child’s repository.
+    pub fn suggested_sia_head(&self) -> Option<&uri::Rsync> {
+        self.suggested_sia_head.as_ref()
+    }
+
+    /// Sets the suggested base URI for the child’s repository.
+    pub fn set_suggested_sia_head(&mut self, uri: Option<uri::Rsync>) {
+        self.suggested_sia_head = uri
+    }
+
+    /// Returns the certificates already issued to the child.
+    pub fn issued(&self) -> &[IssuedCert] {
+        &self.issued
+    }
+
+    /// Adds an issued certificate.
+    pub fn push_issued(&mut self, cert: IssuedCert) {
+        self.issued.push(cert)
+    }
+
+    /// Returns the certificate of the issuer.
+    pub fn issuer(&self) -> &Cert {
+        &self.issuer
+    }
+
+    /// Takes an optional class element from the content of a message.
+    fn take_opt_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Option<Self>, Error> {
+        let mut class_name = None;
+        let mut cert_url = None;
+        let mut resource_set = ResourceSetAttrs::default();
+        let mut not_after = None;
+        let mut suggested_sia_head = None;
+        let content = outer.take_opt_element(reader, |element| {
+            if element.name() != CLASS {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"class_name" => {
+                    class_name = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"cert_url" => {
+                    cert_url = Some(
+                        parse_uri_list(&value.ascii_into::<String>()?)?
+                    );
+                    Ok(())
+                }
+                b"resource_set_notafter" => {
+                    not_after = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"suggested_sia_head" => {
+                    suggested_sia_head = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => resource_set.take_attr(b"resource_set_", name, value)
+            })
+        })?;
+        let mut content = match content {
+            Some(content) => content,
+            None => return Ok(None)
+        };
+
+        let mut issued = Vec::new();
+        let mut issuer = None;
+        loop {
+            let mut is_issuer = false;
+            let mut cert_url = None;
+            let mut limit = ResourceSetAttrs::default();
+            let inner = content.take_opt_element(reader, |element| {
+                if issuer.is_some() {
+                    // The issuer must be the last element.
+                    return Err(Error::Malformed)
+                }
+                match element.name() {
+                    CERTIFICATE => {
+                        element.attributes(|name, value| match name {
+                            b"cert_url" => {
+                                let value: String = value.ascii_into()?;
+                                cert_url = Some(parse_uri_list(&value)?);
+                                Ok(())
+                            }
+                            _ => {
+                                limit.take_attr(
+                                    b"req_resource_set_", name, value
+                                )
+                            }
+                        })
+                    }
+                    ISSUER => {
+                        is_issuer = true;
+                        element.attributes(|_, _| Err(Error::Malformed))
+                    }
+                    _ => Err(Error::Malformed)
+                }
+            })?;
+            let mut inner = match inner {
+                Some(inner) => inner,
+                None => break
+            };
+            let cert = take_cert(&mut inner, reader)?;
+            if is_issuer {
+                issuer = Some(cert);
+            }
+            else {
+                issued.push(IssuedCert {
+                    cert_url: cert_url.ok_or(Error::Malformed)?,
+                    limit: limit.into_limit(),
+                    cert
+                })
+            }
+        }
+
+        Ok(Some(ResourceClass {
+            class_name: class_name.ok_or(Error::Malformed)?,
+            cert_url: cert_url.ok_or(Error::Malformed)?,
+            resource_set: resource_set.into_set()?,
+            not_after: not_after.ok_or(Error::Malformed)?,
+            suggested_sia_head,
+            issued,
+            issuer: issuer.ok_or(Error::Malformed)?,
+        }))
+    }
+
+    /// Writes the class element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        content.element(CLASS.local())?
+            .attr(b"class_name", &self.class_name)?
+            .attr(b"cert_url", &UriList(&self.cert_url))?
+            .attr(
+                b"resource_set_as", &AsBlocksStr(&self.resource_set.asn)
+            )?
+            .attr(
+                b"resource_set_ipv4",
+                &IpBlocksStr(&self.resource_set.v4, true)
+            )?
+            .attr(
+                b"resource_set_ipv6",
+                &IpBlocksStr(&self.resource_set.v6, false)
+            )?
+            .attr(
+                b"resource_set_notafter",
+                &self.not_after.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )?
+            .opt_attr(
+                b"suggested_sia_head", self.suggested_sia_head.as_ref()
+            )?
+            .content(|content| {
+                for issued in &self.issued {
+                    issued.write_xml(content)?;
+                }
+                content.element(ISSUER.local())?
+                    .content(|content| {
+                        content.base64(self.issuer.to_captured().as_slice())
+                    })?
+                    .done()
+            })?
+            .done()
+    }
+}
+
+
+//------------ IssuedCert ----------------------------------------------------
+
+/// A certificate issued to the child.
+///
+/// This is the `certificate` element defined in section 3.3.2 of RFC
+/// 6492.
+#[derive(Clone, Debug)]
+pub struct IssuedCert {
+    cert_url: Vec<uri::Rsync>,
+    limit: ResourceLimit,
+    cert: Cert,
+}
+
+impl IssuedCert {
+    /// Creates a new issued certificate.
+    pub fn new(
+        cert_url: Vec<uri::Rsync>, limit: ResourceLimit, cert: Cert
+    ) -> Self {
+        IssuedCert { cert_url, limit, cert }
+    }
+
+    /// Returns the URIs where the certificate is published.
+    pub fn cert_url(&self) -> &[uri::Rsync] {
+        &self.cert_url
+    }
+
+    /// Returns the resource limit requested by the child.
+    pub fn limit(&self) -> &ResourceLimit {
+        &self.limit
+    }
+
+    /// Returns the certificate.
+    pub fn cert(&self) -> &Cert {
+        &self.cert
+    }
+
+    /// Writes the certificate element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        let element = content.element(CERTIFICATE.local())?
+            .attr(b"cert_url", &UriList(&self.cert_url))?;
+        self.limit.write_attrs(element)?
+            .content(|content| {
+                content.base64(self.cert.to_captured().as_slice())
+            })?
+            .done()
+    }
+}
+
+
+//------------ IssueRequest --------------------------------------------------
+
+/// A request for a certificate.
+///
+/// This is the `request` element defined in section 3.4.1 of RFC 6492.
+#[derive(Clone, Debug)]
+pub struct IssueRequest {
+    class_name: String,
+    limit: ResourceLimit,
+    csr: Csr,
+}
+
+impl IssueRequest {
+    /// Creates a new issue request.
+    pub fn new(class_name: String, limit: ResourceLimit, csr: Csr) -> Self {
+        IssueRequest { class_name, limit, csr }
+    }
+
+    /// Returns the name of the class a certificate is requested for.
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Returns the resources the child wants to limit the certificate to.
+    pub fn limit(&self) -> &ResourceLimit {
+        &self.limit
+    }
+
+    /// Returns the certificate signing request.
+    pub fn csr(&self) -> &Csr {
+        &self.csr
+    }
+
+    /// Takes the request element from the content of a message.
+    fn take_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut class_name = None;
+        let mut limit = ResourceSetAttrs::default();
+        let mut content = outer.take_element(reader, |element| {
+            if element.name() != REQUEST {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"class_name" => {
+                    class_name = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                _ => limit.take_attr(b"req_resource_set_", name, value)
+            })
+        })?;
+        let csr = content.take_text(reader, |text| {
+            Csr::decode(Bytes::from(text.base64_decode()?))
+                .map_err(|_| Error::Malformed)
+        })?;
+        content.take_end(reader)?;
+        Ok(IssueRequest {
+            class_name: class_name.ok_or(Error::Malformed)?,
+            limit: limit.into_limit(),
+            csr
+        })
+    }
+
+    /// Writes the request element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        let element = content.element(REQUEST.local())?
+            .attr(b"class_name", &self.class_name)?;
+        self.limit.write_attrs(element)?
+            .content(|content| {
+                content.base64(self.csr.to_captured().as_slice())
+            })?
+            .done()
+    }
+}
+
+
+//------------ RevocationRequest ---------------------------------------------
+
+/// A request to revoke the certificates for a key.
+///
+/// This is the `key` element defined in section 3.5.1 of RFC 6492 and
+/// also used in the response to the request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevocationRequest {
+    class_name: String,
+    key: KeyIdentifier,
+}
+
+impl RevocationRequest {
+    /// Creates a new revocation request.
+    pub fn new(class_name: String, key: KeyIdentifier) -> Self {
+        RevocationRequest { class_name, key }
+    }
+
+    /// Returns the name of the class.
+    pub fn class_name(&self) -> &str {
+        &self.class_name
+    }
+
+    /// Returns the identifier of the key to revoke.
+    pub fn key(&self) -> KeyIdentifier {
+        self.key
+    }
+
+    /// Takes the key element from the content of a message.
+    fn take_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut class_name = None;
+        let mut key = None;
+        let mut content = outer.take_element(reader, |element| {
+            if element.name() != KEY {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, value| match name {
+                b"class_name" => {
+                    class_name = Some(value.ascii_into()?);
+                    Ok(())
+                }
+                b"ski" => {
+                    let ski: String = value.ascii_into()?;
+                    let ski = base64::decode_config(
+                        &ski, base64::URL_SAFE_NO_PAD
+                    ).map_err(|_| Error::Malformed)?;
+                    key = Some(
+                        KeyIdentifier::try_from(ski.as_slice())
+                            .map_err(|_| Error::Malformed)?
+                    );
+                    Ok(())
+                }
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        content.take_end(reader)?;
+        Ok(RevocationRequest {
+            class_name: class_name.ok_or(Error::Malformed)?,
+            key: key.ok_or(Error::Malformed)?,
+        })
+    }
+
+    /// Writes the key element.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        content.element(KEY.local())?
+            .attr(b"class_name", &self.class_name)?
+            .attr(
+                b"ski",
+                &base64::encode_config(
+                    self.key.as_slice(), base64::URL_SAFE_NO_PAD
+                )
+            )?
+            .done()
+    }
+}
+
+
+//------------ ErrorResponse -------------------------------------------------
+
+/// The content of an error response.
+///
+/// See section 3.6 of RFC 6492.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErrorResponse {
+    status: ErrorCode,
+    description: Option<String>,
+}
+
+impl ErrorResponse {
+    /// Creates a new error response.
+    pub fn new(status: ErrorCode, description: Option<String>) -> Self {
+        ErrorResponse { status, description }
+    }
+
+    /// Returns the error code.
+    pub fn status(&self) -> ErrorCode {
+        self.status
+    }
+
+    /// Returns the optional human-readable description of the error.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Takes the content of an error response message.
+    fn take_from<R: io::BufRead>(
+        outer: &mut Content,
+        reader: &mut Reader<R>,
+    ) -> Result<Self, Error> {
+        let mut content = outer.take_element(reader, |element| {
+            if element.name() != STATUS {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|_, _| Err(Error::Malformed))
+        })?;
+        let status = content.take_text(reader, |text| {
+            u16::from_str(text.to_ascii()?.trim())
+                .map(ErrorCode).map_err(|_| Error::Malformed)
+        })?;
+        content.take_end(reader)?;
+
+        let content = outer.take_opt_element(reader, |element| {
+            if element.name() != DESCRIPTION {
+                return Err(Error::Malformed)
+            }
+            element.attributes(|name, _| match name {
+                b"xml:lang" => Ok(()),
+                _ => Err(Error::Malformed)
+            })
+        })?;
+        let description = match content {
+            Some(mut content) => {
+                let res = content.take_text(reader, |text| {
+                    text.to_ascii().map(|text| text.into_owned())
+                })?;
+                content.take_end(reader)?;
+                Some(res)
+            }
+            None => None
+        };
+        Ok(ErrorResponse { status, description })
+    }
+
+    /// Writes the content of an error response message.
+    fn write_xml<W: io::Write>(
+        &self, content: &mut encode::Content<W>
+    ) -> Result<(), io::Error> {
+        content.element(STATUS.local())?
+            .content(|content| content.pcdata(&self.status.0))?
+            .done()?;
+        if let Some(ref description) = self.description {
+            content.element(DESCRIPTION.local())?
+                .attr(b"xml:lang", "en-US")?
+                .content(|content| content.pcdata(description))?
+                .done()?;
+        }
+        Ok(())
+    }
+}
+
+
+//------------ ErrorCode -----------------------------------------------------
+
+/// The error code of an error response.
+///
+/// The codes are defined in section 3.6 of RFC 6492.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ErrorCode(u16);
+
+impl ErrorCode {
+    pub const ALREADY_PROCESSING: Self = ErrorCode(1101);
+    pub const VERSION_ERROR: Self = ErrorCode(1102);
+    pub const UNRECOGNIZED_TYPE: Self = ErrorCode(1103);
+    pub const SCHEDULED: Self = ErrorCode(1104);
+    pub const NO_SUCH_CLASS: Self = ErrorCode(1201);
+    pub const NO_RESOURCES: Self = ErrorCode(1202);
+    pub const BAD_CSR: Self = ErrorCode(1203);
+    pub const KEY_REUSED: Self = ErrorCode(1204);
+    pub const REVOKE_NO_SUCH_CLASS: Self = ErrorCode(1301);
+    pub const REVOKE_NO_SUCH_KEY: Self = ErrorCode(1302);
+    pub const INTERNAL_ERROR: Self = ErrorCode(2001);
+
+    /// Creates an error code from its numerical value.
+    pub fn from_u16(code: u16) -> Self {
+        ErrorCode(code)
+    }
+
+    /// Returns the numerical value of the error code.
+    pub fn to_u16(self) -> u16 {
+        self.0
+    }
+
+    /// Returns the description of the code from RFC 6492 if it is known.
+    pub fn description(self) -> Option<&'static str> {
+        Some(match self.0 {
+            1101 => "already processing request",
+            1102 => "version number error",
+            1103 => "unrecognised request type",
+            1104 => "request scheduled for processing",
+            1201 => "request - no such resource class",
+            1202 => "request - no resources allocated in resource class",
+            1203 => "request - badly formed certificate request",
+            1204 => "request - already used key in request",
+            1301 => "revoke - no such resource class",
+            1302 => "revoke - no such key",
+            2001 => "Internal Server Error - Request not performed",
+            _ => return None
+        })
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.description() {
+            Some(desc) => write!(f, "{} ({})", self.0, desc),
+            None => self.0.fmt(f)
+        }
+    }
+}
+
+
+//------------ ResourceSet ---------------------------------------------------
+
+/// The resources of a resource class.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResourceSet {
+    asn: AsBlocks,
+    v4: IpBlocks,
+    v6: IpBlocks,
+}
+
+impl ResourceSet {
+    /// Creates a new resource set from its components.
+    pub fn new(asn: AsBlocks, v4: IpBlocks, v6: IpBlocks) -> Self {
+        ResourceSet { asn, v4, v6 }
+    }
+
+    /// Returns the AS numbers of the set.
+    pub fn asn(&self) -> &AsBlocks {
+        &self.asn
+    }
+
+    /// Returns the IPv4 addresses of the set.
+    pub fn v4(&self) -> &IpBlocks {
+        &self.v4
+    }
+
+    /// Returns the IPv6 addresses of the set.
+    pub fn v6(&self) -> &IpBlocks {
+        &self.v6
+    }
+}
+
+
+//------------ ResourceLimit -------------------------------------------------
+
+/// A limit on the resources requested for a certificate.
+///
+/// For each resource type, the child can ask for a certificate with only
+/// a subset of the resources of the class. If a limit is `None`, all
+/// resources of the type are requested.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResourceLimit {
+    asn: Option<AsBlocks>,
+    v4: Option<IpBlocks>,
+    v6: Option<IpBlocks>,
+}
+
+impl ResourceLimit {
+    /// Creates a new value that doesn’t limit any resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether the value doesn’t limit any resources.
+    pub fn is_empty(&self) -> bool {
+        self.asn.is_none() && self.v4.is_none() && self.v6.is_none()
+    }
+
+    /// Returns the limit for AS numbers.
+    pub fn asn(&self) -> Option<&AsBlocks> {
+        self.asn.as_ref()
+    }
+
+    /// Sets the limit for AS numbers.
+    pub fn set_asn(&mut self, asn: Option<AsBlocks>) {
+        self.asn = asn
+    }
+
+    /// Returns the limit for IPv4 addresses.
+    pub fn v4(&self) -> Option<&IpBlocks> {
+        self.v4.as_ref()
+    }
+
+    /// Sets the limit for IPv4 addresses.
+    pub fn set_v4(&mut self, v4: Option<IpBlocks>) {
+        self.v4 = v4
+    }
+
+    /// Returns the limit for IPv6 addresses.
+    pub fn v6(&self) -> Option<&IpBlocks> {
+        self.v6.as_ref()
+    }
+
+    /// Sets the limit for IPv6 addresses.
+    pub fn set_v6(&mut self, v6: Option<IpBlocks>) {
+        self.v6 = v6
+    }
+
+    /// Adds the limit as `req_resource_set_*` attributes to an element.
+    fn write_attrs<'a, W: io::Write>(
+        &self, element: encode::Element<'a, W>
+    ) -> Result<encode::Element<'a, W>, io::Error> {
+        element
+            .opt_attr(
+                b"req_resource_set_as", self.asn.as_ref().map(AsBlocksStr)
+                    .as_ref()
+            )?
+            .opt_attr(
+                b"req_resource_set_ipv4",
+                self.v4.as_ref().map(|v4| IpBlocksStr(v4, true)).as_ref()
+            )?
+            .opt_attr(
+                b"req_resource_set_ipv6",
+                self.v6.as_ref().map(|v6| IpBlocksStr(v6, false)).as_ref()
+            )
+    }
+}
+
+
+//------------ ResourceSetAttrs ----------------------------------------------
+
+/// Collects the resource set attributes while parsing an element.
+#[derive(Default)]
+struct ResourceSetAttrs {
+    asn: Option<AsBlocks>,
+    v4: Option<IpBlocks>,
+    v6: Option<IpBlocks>,
+}
+
+impl ResourceSetAttrs {
+    /// Processes an attribute if its name is `prefix` plus a resource type.
+    fn take_attr(
+        &mut self,
+        prefix: &[u8],
+        name: &[u8],
+        value: AttrValue,
+    ) -> Result<(), Error> {
+        if !name.starts_with(prefix) {
+            return Err(Error::Malformed)
+        }
+        let value: String = value.ascii_into()?;
+        match &name[prefix.len()..] {
+            b"as" => {
+                self.asn = Some(
+                    AsBlocks::from_str(&value).map_err(|_| Error::Malformed)?
+                );
+            }
+            b"ipv4" => self.v4 = Some(parse_ip_blocks(&value, true)?),
+            b"ipv6" => self.v6 = Some(parse_ip_blocks(&value, false)?),
+            _ => return Err(Error::Malformed)
+        }
+        Ok(())
+    }
+
+    /// Converts the attributes into a resource set.
+    ///
+    /// All three attributes have to be present.
+    fn into_set(self) -> Result<ResourceSet, Error> {
+        match (self.asn, self.v4, self.v6) {
+            (Some(asn), Some(v4), Some(v6)) => {
+                Ok(ResourceSet { asn, v4, v6 })
+            }
+            _ => Err(Error::Malformed)
+        }
+    }
+
+    /// Converts the attributes into a resource limit.
+    fn into_limit(self) -> ResourceLimit {
+        ResourceLimit { asn: self.asn, v4: self.v4, v6: self.v6 }
+    }
+}
+
+
+//------------ Helpers for Attribute Values ----------------------------------
+
+/// Parses the comma separated list of IP blocks of a resource set.
+fn parse_ip_blocks(s: &str, v4: bool) -> Result<IpBlocks, Error> {
+    let mut res = IpBlocksBuilder::new();
+    for item in s.split(',') {
+        let item = item.trim();
+        if item.is_empty() {
+            continue
+        }
+        let block = if v4 {
+            IpBlock::from_v4_str(item)
+        }
+        else {
+            IpBlock::from_v6_str(item)
+        };
+        res.push(block.map_err(|_| Error::Malformed)?);
+    }
+    Ok(res.finalize())
+}
+
+/// Parses a comma separated list of URIs.
+fn parse_uri_list(s: &str) -> Result<Vec<uri::Rsync>, Error> {
+    s.split(',').map(|item| {
+        uri::Rsync::from_str(item.trim()).map_err(|_| Error::Malformed)
+    }).collect()
+}
+
+/// Formats AS blocks in the syntax of a resource set.
+struct AsBlocksStr<'a>(&'a AsBlocks);
+
+impl<'a> fmt::Display for AsBlocksStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, block) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            let (min, max) = (u32::from(block.min()), u32::from(block.max()));
+            if min == max {
+                write!(f, "{}", min)?;
+            }
+            else {
+                write!(f, "{}-{}", min, max)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats IP blocks in the syntax of a resource set.
+///
+/// The second element is `true` for IPv4 and `false` for IPv6.
+struct IpBlocksStr<'a>(&'a IpBlocks, bool);
+
+impl<'a> fmt::Display for IpBlocksStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, block) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            if self.1 {
+                block.fmt_v4(f)?;
+            }
+            else {
+                block.fmt_v6(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Formats a list of URIs as a comma separated list.
+struct UriList<'a>(&'a [uri::Rsync]);
+
+impl<'a> fmt::Display for UriList<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (idx, uri) in self.0.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(",")?;
+            }
+            uri.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Takes base64 encoded certificate from the content of an element.
+fn take_cert<R: io::BufRead>(
+    content: &mut Content,
+    reader: &mut Reader<R>,
+) -> Result<Cert, Error> {
+    let res = content.take_text(reader, |text| {
+        Cert::decode(Bytes::from(text.base64_decode()?))
+            .map_err(|_| Error::Malformed)
+    })?;
+    content.take_end(reader)?;
+    Ok(res)
+}
+
+
+//------------ Xml Names -----------------------------------------------------
+
+const NS_STR: &str = "http://www.apnic.net/specs/rescerts/up-down/";
+const NS: &[u8] = NS_STR.as_bytes();
+const MESSAGE: Name = Name::qualified(NS, b"message");
+const CLASS: Name = Name::qualified(NS, b"class");
+const CERTIFICATE: Name = Name::qualified(NS, b"certificate");
+const ISSUER: Name = Name::qualified(NS, b"issuer");
+const REQUEST: Name = Name::qualified(NS, b"request");
+const KEY: Name = Name::qualified(NS, b"key");
+const STATUS: Name = Name::qualified(NS, b"status");
+const DESCRIPTION: Name = Name::qualified(NS, b"description");
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn handle(s: &str) -> Handle {
+        Handle::from_str(s).unwrap()
+    }
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
+
+    fn ta_cert() -> Cert {
+        Cert::decode(include_bytes!("../test-data/ta.cer").as_ref()).unwrap()
+    }
+
+    fn ca_cert() -> Cert {
+        Cert::decode(include_bytes!("../test-data/ca1.cer").as_ref()).unwrap()
+    }
+
+    /// Writes the message, parses it again, and checks the result.
+    ///
+    /// Since the message types don’t implement `PartialEq`, this compares
+    /// the XML produced from the original and parsed messages.
+    fn round_trip(message: &Message) -> Message {
+        let mut xml = Vec::new();
+        message.write_xml(&mut xml).unwrap();
+        let parsed = Message::parse(xml.as_slice()).unwrap();
+        let mut reparsed = Vec::new();
+        parsed.write_xml(&mut reparsed).unwrap();
+        assert_eq!(xml, reparsed);
+        parsed
+    }
+
+    #[test]
+    fn parse_list_response() {
+        let xml = format!(
+            "<message \
+                xmlns=\"http://www.apnic.net/specs/rescerts/up-down/\" \
+                version=\"1\" sender=\"Alice\" recipient=\"Bob\" \
+                type=\"list_response\">\
+              <class class_name=\"class-1\" \
+                  cert_url=\"rsync://a.example/rpki/ta.cer,\
+                             rsync://b.example/rpki/ta.cer\" \
+                  resource_set_as=\"64496-64511,65000\" \
+                  resource_set_ipv4=\"192.0.2.0/24,\
+                                     198.51.100.0-198.51.100.23\" \
+                  resource_set_ipv6=\"\" \
+                  resource_set_notafter=\"2021-08-15T22:12:51Z\">\
+                <certificate cert_url=\"rsync://a.example/rpki/bob/ca1.cer\" \
+                    req_resource_set_ipv6=\"2001:db8::/32\">{}</certificate>\
+                <issuer>{}</issuer>\
+              </class>\
+            </message>",
+            base64::encode(include_bytes!("../test-data/ca1.cer").as_ref()),
+            base64::encode(include_bytes!("../test-data/ta.cer").as_ref()),
+        );
+        let message = Message::parse(xml.as_bytes()).unwrap();
+        assert_eq!(message.sender(), &handle("Alice"));
+        assert_eq!(message.recipient(), &handle("Bob"));
+        let classes = match message.payload() {
+            Payload::ListResponse(classes) => classes,
+            _ => panic!("not a list response")
+        };
+        assert_eq!(classes.len(), 1);
+        let class = &classes[0];
+        assert_eq!(class.class_name(), "class-1");
+        assert_eq!(
+            class.cert_url(),
+            &[rsync("rsync://a.example/rpki/ta.cer"),
+              rsync("rsync://b.example/rpki/ta.cer")]
+        );
+        assert_eq!(
+            class.resource_set().asn(),
+            &AsBlocks::from_str("AS64496-AS64511, AS65000").unwrap()
+        );
+        assert_eq!(
+            class.resource_set().v4(),
+            &parse_ip_blocks("198.51.100.0-198.51.100.23, 192.0.2.0/24", true)
+                .unwrap()
+        );
+        assert!(class.resource_set().v6().is_empty());
+        assert_eq!(
+            class.not_after(), Time::from_str("2021-08-15T22:12:51Z").unwrap()
+        );
+        assert_eq!(class.issued().len(), 1);
+        let issued = &class.issued()[0];
+        assert_eq!(issued.limit().asn(), None);
+        assert_eq!(
+            issued.limit().v6(),
+            Some(&parse_ip_blocks("2001:db8::/32", false).unwrap())
+        );
+        assert_eq!(
+            issued.cert().to_captured().as_slice(),
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        );
+
+        round_trip(&message);
+    }
+
+    #[test]
+    fn resource_set_syntax() {
+        let set = ResourceSet::new(
+            AsBlocks::from_str("AS65000, AS64496-AS64511").unwrap(),
+            parse_ip_blocks("192.0.2.0/24,198.51.100.0-198.51.100.23", true)
+                .unwrap(),
+            parse_ip_blocks("2001:db8::/32", false).unwrap(),
+        );
+        assert_eq!(AsBlocksStr(set.asn()).to_string(), "64496-64511,65000");
+        assert_eq!(
+            IpBlocksStr(set.v4(), true).to_string(),
+            "192.0.2.0/24,198.51.100.0-198.51.100.23"
+        );
+        assert_eq!(IpBlocksStr(set.v6(), false).to_string(), "2001:db8::/32");
+        assert!(parse_ip_blocks("2001:db8::/32", true).is_err());
+        assert!(parse_ip_blocks("192.0.2.0/24", false).is_err());
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        round_trip(
+            &Message::new(handle("Bob"), handle("Alice"), Payload::List)
+        );
+
+        let mut limit = ResourceLimit::new();
+        limit.set_asn(Some(AsBlocks::from_str("AS64496").unwrap()));
+        let csr = Csr::decode(
+            include_bytes!("../test-data/drl-csr.der").as_ref()
+        ).unwrap();
+        let message = round_trip(&Message::new(
+            handle("Bob"), handle("Alice"),
+            Payload::Issue(IssueRequest::new("class-1".into(), limit, csr))
+        ));
+        match message.payload() {
+            Payload::Issue(request) => {
+                assert_eq!(request.class_name(), "class-1");
+                assert_eq!(
+                    request.limit().asn(),
+                    Some(&AsBlocks::from_str("AS64496").unwrap())
+                );
+                assert_eq!(request.limit().v4(), None);
+                assert_eq!(
+                    request.csr().to_captured().as_slice(),
+                    include_bytes!("../test-data/drl-csr.der").as_ref()
+                );
+            }
+            _ => panic!("not an issue request")
+        }
+
+        let mut class = ResourceClass::new(
+            "class-1".into(),
+            vec![rsync("rsync://a.example/rpki/ta.cer")],
+            ResourceSet::new(
+                AsBlocks::from_str("AS64496").unwrap(),
+                IpBlocks::empty(),
+                parse_ip_blocks("2001:db8::/32", false).unwrap(),
+            ),
+            Time::from_str("2021-08-15T22:12:51Z").unwrap(),
+            ta_cert(),
+        );
+        class.set_suggested_sia_head(
+            Some(rsync("rsync://a.example/rpki/bob/"))
+        );
+        class.push_issued(IssuedCert::new(
+            vec![rsync("rsync://a.example/rpki/bob.cer")],
+            ResourceLimit::new(),
+            ca_cert()
+        ));
+        let message = round_trip(&Message::new(
+            handle("Alice"), handle("Bob"), Payload::IssueResponse(class)
+        ));
+        match message.payload() {
+            Payload::IssueResponse(class) => {
+                assert_eq!(
+                    class.suggested_sia_head(),
+                    Some(&rsync("rsync://a.example/rpki/bob/"))
+                );
+                assert!(class.resource_set().v4().is_empty());
+            }
+            _ => panic!("not an issue response")
+        }
+
+        let request = RevocationRequest::new(
+            "class-1".into(), ca_cert().subject_key_identifier()
+        );
+        let message = round_trip(&Message::new(
+            handle("Bob"), handle("Alice"), Payload::Revoke(request.clone())
+        ));
+        match message.payload() {
+            Payload::Revoke(parsed) => assert_eq!(parsed, &request),
+            _ => panic!("not a revoke request")
+        }
+        round_trip(&Message::new(
+            handle("Alice"), handle("Bob"), Payload::RevokeResponse(request)
+        ));
+
+        let message = round_trip(&Message::new(
+            handle("Alice"), handle("Bob"),
+            Payload::ErrorResponse(ErrorResponse::new(
+                ErrorCode::NO_SUCH_CLASS, Some("no <such> class".into())
+            ))
+        ));
+        match message.payload() {
+            Payload::ErrorResponse(response) => {
+                assert_eq!(response.status(), ErrorCode::NO_SUCH_CLASS);
+                assert_eq!(response.description(), Some("no <such> class"));
+            }
+            _ => panic!("not an error response")
+        }
+    }
+
+    #[test]
+    fn parse_revoke() {
+        let message = Message::parse(
+            b"<message \
+                xmlns=\"http://www.apnic.net/specs/rescerts/up-down/\" \
+                version=\"1\" sender=\"Bob\" recipient=\"Alice\" \
+                type=\"revoke\">\
+              <key class_name=\"class-1\" \
+                  ski=\"AAECAwQFBgcICQoLDA0ODxAREhM\"/>\
+              </message>".as_ref()
+        ).unwrap();
+        match message.payload() {
+            Payload::Revoke(request) => {
+                assert_eq!(
+                    request.key().as_slice(),
+                    &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+                      16, 17, 18, 19]
+                );
+            }
+            _ => panic!("not a revoke request")
+        }
+
+        // Unknown message type.
+        assert!(Message::parse(
+            b"<message \
+                xmlns=\"http://www.apnic.net/specs/rescerts/up-down/\" \
+                version=\"1\" sender=\"Bob\" recipient=\"Alice\" \
+                type=\"frobnicate\"/>".as_ref()
+        ).is_err());
+    }
+}
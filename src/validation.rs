@@ -0,0 +1,1225 @@
+//! Top-down validation of an RPKI repository.
+//!
+//! This module combines the various object types of this crate into a
+//! complete relying party walk through an RPKI repository. Starting from a
+//! set of [`Tal`]s, it validates the trust anchor certificates, then
+//! processes the publication point of each CA certificate following its
+//! manifest as described in RFC 6487, RFC 6488, and RFC 9286, and descends
+//! into all child CA certificates found there.
+//!
+//! Access to the repository is abstracted via the [`Repository`] trait. The
+//! [`DirectoryRepository`] provides an implementation atop a local directory
+//! that mirrors the rsync repositories in the form of
+//! `<base>/<authority>/<module>/<path>`.
+//!
+//! The result of a validation run is a [`ValidationReport`]. It contains
+//! the content of all valid ROAs, the keys of all valid BGPsec router
+//! certificates, as well as a report for every object that was encountered
+//! during the walk.
+//!
+//! [`Tal`]: ../tal/struct.Tal.html
+//! [`Repository`]: trait.Repository.html
+//! [`DirectoryRepository`]: struct.DirectoryRepository.html
+//! [`ValidationReport`]: struct.ValidationReport.html
+
+use std::{fmt, fs, io};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use bytes::Bytes;
+use log::{debug, info, warn};
+use crate::uri;
+use crate::cert::{Cert, ResourceCert, RouterKey};
+use crate::crl::{Crl, CrlStore};
+use crate::crypto::KeyIdentifier;
+use crate::manifest::Manifest;
+use crate::pubpoint::{
+    self, FileSource, FileStatus, FileType, PublicationPointError
+};
+use crate::roa::{Roa, RouteOriginAttestation};
+use crate::tal::{Tal, TalInfo, TalUri};
+use crate::x509::{Serial, Time, ValidationError, ValidationErrorKind};
+
+
+//------------ Configuration -------------------------------------------------
+
+/// The maximum depth of the CA tree below a trust anchor.
+///
+/// This guards against excessively long chains of CA certificates that a
+/// malicious repository could use to keep us busy.
+const MAX_DEPTH: usize = 32;
+
+
+//------------ Repository ----------------------------------------------------
+
+/// Access to the objects published in an RPKI repository.
+///
+/// The validator uses this trait to retrieve the raw content of all the
+/// objects it encounters during its walk.
+pub trait Repository {
+    /// Loads the content of the object published under the given URI.
+    ///
+    /// Returns `Ok(None)` if there is no such object.
+    fn load(&self, uri: &uri::Rsync) -> Result<Option<Bytes>, io::Error>;
+}
+
+impl<R: Repository + ?Sized> Repository for &R {
+    fn load(&self, uri: &uri::Rsync) -> Result<Option<Bytes>, io::Error> {
+        (*self).load(uri)
+    }
+}
+
+
+//------------ DirectoryRepository -------------------------------------------
+
+/// A repository stored in a local directory.
+///
+/// The directory is expected to be a mirror of the rsync repositories with
+/// an object published under `rsync://<authority>/<module>/<path>` stored
+/// in the file `<base>/<authority>/<module>/<path>`.
+#[derive(Clone, Debug)]
+pub struct DirectoryRepository {
+    base: PathBuf,
+}
+
+impl DirectoryRepository {
+    /// Creates a new repository using `base` as its base directory.
+    pub fn new<P: Into<PathBuf>>(base: P) -> Self {
+        DirectoryRepository { base: base.into() }
+    }
+
+    /// Returns the base directory of the repository.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// Returns the path of the file for the given URI.
+    pub fn uri_path(&self, uri: &uri::Rsync) -> PathBuf {
+        let mut res = self.base.join(uri.authority());
+        res.push(uri.module().module());
+        res.push(uri.path());
+        res
+    }
+}
+
+impl Repository for DirectoryRepository {
+    fn load(&self, uri: &uri::Rsync) -> Result<Option<Bytes>, io::Error> {
+        match fs::read(self.uri_path(uri)) {
+            Ok(data) => Ok(Some(data.into())),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {
+                Ok(None)
+            }
+            Err(err) => Err(err)
+        }
+    }
+}
+
+
+//------------ Validator -----------------------------------------------------
+
+/// A validator performing the top-down walk through an RPKI repository.
+///
+/// The validator remembers the CRL number of the last CRL it has accepted
+/// for each CA. If it is used for repeated validation runs, a CRL with a
+/// lower CRL number than the one previously seen for the same CA is
+/// rejected.
+#[derive(Clone, Debug)]
+pub struct Validator<R> {
+    /// The repository to take the objects from.
+    repository: R,
+
+    /// Should we be strict when decoding and validating objects?
+    strict: bool,
+
+    /// The CRL number of the last accepted CRL by authority key identifier.
+    ///
+    /// This is used to check that CRL numbers don’t decrease (RFC 5280,
+    /// section 5.2.3).
+    crl_numbers: HashMap<KeyIdentifier, Serial>,
+}
+
+impl<R> Validator<R> {
+    /// Creates a new validator for the given repository.
+    pub fn new(repository: R, strict: bool) -> Self {
+        Validator { repository, strict, crl_numbers: HashMap::new() }
+    }
+
+    /// Returns a reference to the underlying repository.
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Returns whether the validator is strict.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Converts the validator into the underlying repository.
+    pub fn into_repository(self) -> R {
+        self.repository
+    }
+}
+
+impl<R: Repository> Validator<R> {
+    /// Validates the repository starting from the given TALs.
+    pub fn validate<'a, I>(&mut self, tals: I) -> ValidationReport
+    where I: IntoIterator<Item = &'a Tal> {
+        self.validate_at(tals, Time::now())
+    }
+
+    /// Validates the repository at the given time.
+    pub fn validate_at<'a, I>(
+        &mut self, tals: I, now: Time
+    ) -> ValidationReport
+    where I: IntoIterator<Item = &'a Tal> {
+        let mut report = ValidationReport::default();
+        for tal in tals {
+            self.process_tal(tal, now, &mut report);
+        }
+        report
+    }
+
+    /// Processes a single TAL.
+    ///
+    /// Tries the URIs of the TAL in order and processes the first trust
+    /// anchor certificate that can be validated (RFC 8630, section 3).
+    fn process_tal(
+        &mut self,
+        tal: &Tal,
+        now: Time,
+        report: &mut ValidationReport
+    ) {
+        for uri in tal.uris() {
+            let uri = match *uri {
+                TalUri::Rsync(ref uri) => uri,
+                TalUri::Https(ref uri) => {
+                    info!("{}: skipping unsupported TAL URI {}",
+                          tal.info().name(), uri);
+                    continue
+                }
+            };
+            if let Some(cert) = self.process_ta_cert(tal, uri, now, report) {
+                let mut chain = Vec::new();
+                self.process_ca(cert, &mut chain, now, report);
+                return
+            }
+        }
+        warn!("{}: no valid trust anchor certificate", tal.info().name());
+    }
+
+    /// Loads and validates a trust anchor certificate.
+    fn process_ta_cert(
+        &self,
+        tal: &Tal,
+        uri: &uri::Rsync,
+        now: Time,
+        report: &mut ValidationReport
+    ) -> Option<ResourceCert> {
+        let data = self.load(uri, report)?;
+        let cert = match Cert::decode(data) {
+            Ok(cert) => cert,
+            Err(_) => {
+                report.push(uri.clone(), ObjectStatus::Malformed);
+                return None
+            }
+        };
+
+        // RFC 8630, section 3, step 2: the public key must match the one
+        // of the TAL.
+        if cert.subject_public_key_info() != tal.key_info() {
+            report.push(uri.clone(), ObjectStatus::Invalid(
+                ValidationError::new(
+                    ValidationErrorKind::TrustAnchorKeyMismatch
+                ).with_rfc(8630, "3")
+            ));
+            return None
+        }
+        match cert.validate_ta_at(tal.info().clone(), self.strict, now) {
+            Ok(cert) => {
+                report.push(uri.clone(), ObjectStatus::Valid);
+                Some(cert)
+            }
+            Err(err) => {
+                report.push(uri.clone(), ObjectStatus::Invalid(err));
+                None
+            }
+        }
+    }
+
+    /// Processes the publication point of a validated CA certificate.
+    ///
+    /// The `chain` contains the key identifiers of all the CA certificates
+    /// above this one and is used to detect loops.
+    fn process_ca(
+        &mut self,
+        ca: ResourceCert,
+        chain: &mut Vec<KeyIdentifier>,
+        now: Time,
+        report: &mut ValidationReport
+    ) {
+        let point = match self.process_manifest(&ca, now, report) {
+            Some(point) => point,
+            None => return
+        };
+        chain.push(ca.subject_key_identifier());
+        for (uri, data) in point.objects {
+            if uri.ends_with(".cer") {
+                if let Some(cert) = self.process_cert(
+                    &uri, data, &ca, &point.crls, chain, now, report
+                ) {
+                    self.process_ca(cert, chain, now, report)
+                }
+            }
+            else if uri.ends_with(".roa") {
+                self.process_roa(uri, data, &ca, &point.crls, now, report)
+            }
+            else {
+                debug!("{}: ignoring object of unknown type", uri);
+                report.push(uri, ObjectStatus::Unsupported);
+            }
+        }
+        chain.pop();
+    }
+
+    /// Processes the manifest and CRL of a CA’s publication point.
+    ///
+    /// Returns the validated CRL and the objects listed on the manifest
+    /// whose hashes match if the publication point can be used, or `None`
+    /// if it has to be abandoned (RFC 9286, section 6).
+    fn process_manifest(
+        &mut self,
+        ca: &ResourceCert,
+        now: Time,
+        report: &mut ValidationReport
+    ) -> Option<PublicationPoint> {
+        let (repository_uri, mft_uri) = match (
+            ca.ca_repository(), ca.rpki_manifest()
+        ) {
+            (Some(repository_uri), Some(mft_uri)) => {
+                (repository_uri, mft_uri)
+            }
+            _ => {
+                warn!("CA certificate {} without repository information",
+                      ca.subject_key_identifier());
+                return None
+            }
+        };
+
+        let data = self.load(mft_uri, report)?;
+        let manifest = match Manifest::decode(data, self.strict) {
+            Ok(manifest) => manifest,
+            Err(_) => {
+                report.push(mft_uri.clone(), ObjectStatus::Malformed);
+                return None
+            }
+        };
+        let (ee_cert, content) = match manifest.validate_at(
+            ca, self.strict, now
+        ) {
+            Ok(res) => res,
+            Err(err) => {
+                report.push(mft_uri.clone(), ObjectStatus::Invalid(err));
+                return None
+            }
+        };
+        if content.this_update() > now || content.next_update() < now {
+            report.push(mft_uri.clone(), ObjectStatus::Stale);
+            return None
+        }
+
+        // Check the files listed on the manifest. This also makes sure the
+        // file names are acceptable before turning them into URIs (RFC
+        // 9286, sections 4.2.2 and 6.4).
+        let source = RepositorySource {
+            repository: &self.repository, repository_uri
+        };
+        let point = match pubpoint::PublicationPoint::process(
+            &ee_cert, &content, &source, None
+        ) {
+            Ok(point) => point,
+            Err(PublicationPointError::Invalid(err)) => {
+                report.push(mft_uri.clone(), ObjectStatus::Invalid(err));
+                return None
+            }
+            Err(PublicationPointError::Io(err)) => {
+                warn!("{}: {}", mft_uri, err);
+                report.push(mft_uri.clone(), ObjectStatus::Unreadable);
+                return None
+            }
+        };
+        for file in point.files() {
+            let status = match file.status() {
+                FileStatus::Missing => ObjectStatus::Missing,
+                FileStatus::HashMismatch => ObjectStatus::HashMismatch,
+                _ => continue
+            };
+            report.push(repository_uri.join(file.name().as_bytes()), status);
+        }
+        if !point.is_complete() {
+            report.push(mft_uri.clone(), ObjectStatus::Invalid(
+                ValidationError::new(
+                    ValidationErrorKind::IncompletePublicationPoint
+                ).with_rfc(9286, "6.4")
+            ));
+            return None
+        }
+
+        // The CRL must be the one referenced by the EE certificate, not
+        // just have the same file name.
+        let crl_uri = repository_uri.join(point.crl().name().as_bytes());
+        if ee_cert.crl_uri() != Some(&crl_uri) {
+            report.push(mft_uri.clone(), ObjectStatus::Invalid(
+                ValidationError::new(
+                    ValidationErrorKind::ManifestCrlMismatch
+                ).with_rfc(9286, "6.4")
+            ));
+            return None
+        }
+        let crl_data = point.crl().data()?.clone();
+        let crls = self.process_crl(ca, crl_uri, crl_data, now, report)?;
+        if let Err(err) = Self::check_crl(&ee_cert, &crls) {
+            report.push(mft_uri.clone(), ObjectStatus::Invalid(err));
+            return None
+        }
+        report.push(mft_uri.clone(), ObjectStatus::Valid);
+        let objects = point.iter_ok().filter(|file| {
+            file.file_type() != FileType::Crl
+        }).filter_map(|file| {
+            file.data().map(|data| {
+                (repository_uri.join(file.name().as_bytes()), data.clone())
+            })
+        }).collect();
+        Some(PublicationPoint { crls, objects })
+    }
+
+    /// Decodes and validates the CRL of a publication point.
+    ///
+    /// Returns a store containing only this CRL for checking the objects
+    /// of the publication point.
+    fn process_crl(
+        &mut self,
+        ca: &ResourceCert,
+        uri: uri::Rsync,
+        data: Bytes,
+        now: Time,
+        report: &mut ValidationReport
+    ) -> Option<CrlStore> {
+        let crl = match Crl::decode(data) {
+            Ok(crl) => crl,
+            Err(_) => {
+                report.push(uri, ObjectStatus::Malformed);
+                return None
+            }
+        };
+        let crl = match crl.validate_at(ca, self.strict, now) {
+            Ok(crl) => crl,
+            Err(err) if err.kind() == ValidationErrorKind::StaleCrl => {
+                report.push(uri, ObjectStatus::Stale);
+                return None
+            }
+            Err(err) => {
+                report.push(uri, ObjectStatus::Invalid(err));
+                return None
+            }
+        };
+        let aki = *crl.authority_key_identifier();
+        if let Some(last) = self.crl_numbers.get(&aki) {
+            if crl.crl_number() < *last {
+                report.push(uri, ObjectStatus::Invalid(
+                    ValidationError::new(
+                        ValidationErrorKind::CrlNumberRollback
+                    ).with_rfc(5280, "5.2.3")
+                ));
+                return None
+            }
+        }
+        self.crl_numbers.insert(aki, crl.crl_number());
+        let mut crls = CrlStore::new();
+        crls.enable_serial_caching();
+        crls.push(uri.clone(), crl.into_crl());
+        report.push(uri, ObjectStatus::Valid);
+        Some(crls)
+    }
+
+    /// Decodes and validates a child CA certificate.
+    ///
+    /// BGPsec router certificates are recognized by their extended key
+    /// usage and validated as such. Since they don’t have a publication
+    /// point, `None` is returned for them.
+    #[allow(clippy::too_many_arguments)]
+    fn process_cert(
+        &self,
+        uri: &uri::Rsync,
+        data: Bytes,
+        ca: &ResourceCert,
+        crls: &CrlStore,
+        chain: &[KeyIdentifier],
+        now: Time,
+        report: &mut ValidationReport
+    ) -> Option<ResourceCert> {
+        let cert = match Cert::decode(data) {
+            Ok(cert) => cert,
+            Err(_) => {
+                report.push(uri.clone(), ObjectStatus::Malformed);
+                return None
+            }
+        };
+        if let Err(err) = Self::check_crl(&cert, crls) {
+            report.push(uri.clone(), ObjectStatus::Invalid(err));
+            return None
+        }
+        if cert.has_bgpsec_router_eku() {
+            self.process_router_cert(uri, cert, ca, now, report);
+            return None
+        }
+        if chain.len() >= MAX_DEPTH
+            || chain.contains(&cert.subject_key_identifier())
+        {
+            warn!("{}: CA depth exceeded or loop detected", uri);
+            report.push(uri.clone(), ObjectStatus::Invalid(
+                ValidationErrorKind::ChainDepth.into()
+            ));
+            return None
+        }
+        match cert.validate_ca_at(ca, self.strict, now) {
+            Ok(cert) => {
+                report.push(uri.clone(), ObjectStatus::Valid);
+                Some(cert)
+            }
+            Err(err) => {
+                report.push(uri.clone(), ObjectStatus::Invalid(err));
+                None
+            }
+        }
+    }
+
+    /// Validates a BGPsec router certificate.
+    fn process_router_cert(
+        &self,
+        uri: &uri::Rsync,
+        cert: Cert,
+        ca: &ResourceCert,
+        now: Time,
+        report: &mut ValidationReport
+    ) {
+        match cert.validate_router_at(ca, self.strict, now) {
+            Ok(cert) => {
+                report.push(uri.clone(), ObjectStatus::Valid);
+                report.router_keys.push(ValidatedRouterKey {
+                    uri: uri.clone(),
+                    tal: cert.tal().clone(),
+                    key: cert.router_key(),
+                });
+            }
+            Err(err) => {
+                report.push(uri.clone(), ObjectStatus::Invalid(err));
+            }
+        }
+    }
+
+    /// Decodes and validates a ROA.
+    fn process_roa(
+        &self,
+        uri: uri::Rsync,
+        data: Bytes,
+        ca: &ResourceCert,
+        crls: &CrlStore,
+        now: Time,
+        report: &mut ValidationReport
+    ) {
+        let roa = match Roa::decode(data, self.strict) {
+            Ok(roa) => roa,
+            Err(_) => {
+                report.push(uri, ObjectStatus::Malformed);
+                return
+            }
+        };
+        match roa.process_at(
+            ca, self.strict, now, |cert| Self::check_crl(cert, crls)
+        ) {
+            Ok(content) => {
+                report.push(uri.clone(), ObjectStatus::Valid);
+                report.roas.push(ValidatedRoa {
+                    uri,
+                    tal: ca.tal().clone(),
+                    content
+                });
+            }
+            Err(err) => {
+                report.push(uri, ObjectStatus::Invalid(err));
+            }
+        }
+    }
+
+    /// Checks a certificate against the CRL of its publication point.
+    fn check_crl(
+        cert: &Cert,
+        crls: &CrlStore
+    ) -> Result<(), ValidationError> {
+        let crl = match cert.crl_uri().and_then(|uri| crls.get(uri)) {
+            Some(crl) => crl,
+            None => {
+                return Err(ValidationError::new(
+                    ValidationErrorKind::InvalidCrlDistributionPoint
+                ).with_rfc(6487, "4.8.6"))
+            }
+        };
+        if crl.contains(cert.serial_number()) {
+            Err(ValidationErrorKind::Revoked.into())
+        }
+        else {
+            Ok(())
+        }
+    }
+
+    /// Loads an object from the repository.
+    ///
+    /// Reports the object as missing or unreadable if it can’t be loaded.
+    fn load(
+        &self,
+        uri: &uri::Rsync,
+        report: &mut ValidationReport
+    ) -> Option<Bytes> {
+        match self.repository.load(uri) {
+            Ok(Some(data)) => Some(data),
+            Ok(None) => {
+                report.push(uri.clone(), ObjectStatus::Missing);
+                None
+            }
+            Err(err) => {
+                warn!("{}: {}", uri, err);
+                report.push(uri.clone(), ObjectStatus::Unreadable);
+                None
+            }
+        }
+    }
+}
+
+
+//------------ PublicationPoint ----------------------------------------------
+
+/// The checked content of a publication point.
+struct PublicationPoint {
+    /// The store holding the validated CRL of the publication point.
+    crls: CrlStore,
+
+    /// The other objects listed on the manifest.
+    objects: Vec<(uri::Rsync, Bytes)>,
+}
+
+
+//------------ RepositorySource ----------------------------------------------
+
+/// The files of a publication point taken from a repository.
+///
+/// Since a repository can’t list its content, files not listed on the
+/// manifest are never found.
+struct RepositorySource<'a, R> {
+    /// The repository to load the files from.
+    repository: &'a R,
+
+    /// The rsync URI of the publication point.
+    repository_uri: &'a uri::Rsync,
+}
+
+impl<'a, R: Repository> FileSource for RepositorySource<'a, R> {
+    fn list(&self) -> Result<Vec<String>, io::Error> {
+        Ok(Vec::new())
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Bytes>, io::Error> {
+        self.repository.load(&self.repository_uri.join(name.as_bytes()))
+    }
+}
+
+
+//------------ ValidationReport ----------------------------------------------
+
+/// The result of a validation run.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// The validated ROAs.
+    roas: Vec<ValidatedRoa>,
+
+    /// The keys of the validated BGPsec router certificates.
+    router_keys: Vec<ValidatedRouterKey>,
+
+    /// The reports for all the objects encountered.
+    objects: Vec<ObjectReport>,
+}
+
+impl ValidationReport {
+    /// Returns a slice of all the validated ROAs.
+    pub fn roas(&self) -> &[ValidatedRoa] {
+        &self.roas
+    }
+
+    /// Returns a slice of all the validated BGPsec router keys.
+    pub fn router_keys(&self) -> &[ValidatedRouterKey] {
+        &self.router_keys
+    }
+
+    /// Returns a slice with the reports for all encountered objects.
+    ///
+    /// The reports are in the order the objects were processed.
+    pub fn objects(&self) -> &[ObjectReport] {
+        &self.objects
+    }
+
+    /// Returns the status of the object with the given URI.
+    ///
+    /// If the object was encountered more than once, returns the last
+    /// status.
+    pub fn status(&self, uri: &uri::Rsync) -> Option<&ObjectStatus> {
+        self.objects.iter().rev().find(|item| item.uri == *uri)
+            .map(|item| &item.status)
+    }
+
+    /// Converts the report into the validated ROAs and the object reports.
+    pub fn into_parts(self) -> (Vec<ValidatedRoa>, Vec<ObjectReport>) {
+        (self.roas, self.objects)
+    }
+
+    fn push(&mut self, uri: uri::Rsync, status: ObjectStatus) {
+        debug!("{}: {}", uri, status);
+        self.objects.push(ObjectReport { uri, status })
+    }
+}
+
+
+//------------ ValidatedRoa --------------------------------------------------
+
+/// The content of a ROA that passed validation.
+#[derive(Clone, Debug)]
+pub struct ValidatedRoa {
+    /// The rsync URI of the ROA.
+    uri: uri::Rsync,
+
+    /// The TAL the ROA was validated under.
+    tal: Arc<TalInfo>,
+
+    /// The content of the ROA.
+    content: RouteOriginAttestation,
+}
+
+impl ValidatedRoa {
+    /// Returns the rsync URI of the ROA.
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns information about the TAL the ROA was validated under.
+    pub fn tal(&self) -> &Arc<TalInfo> {
+        &self.tal
+    }
+
+    /// Returns a reference to the content of the ROA.
+    pub fn content(&self) -> &RouteOriginAttestation {
+        &self.content
+    }
+
+    /// Converts the value into the content of the ROA.
+    pub fn into_content(self) -> RouteOriginAttestation {
+        self.content
+    }
+}
+
+
+//------------ ValidatedRouterKey --------------------------------------------
+
+/// A router key from a BGPsec router certificate that passed validation.
+#[derive(Clone, Debug)]
+pub struct ValidatedRouterKey {
+    /// The rsync URI of the router certificate.
+    uri: uri::Rsync,
+
+    /// The TAL the router certificate was validated under.
+    tal: Arc<TalInfo>,
+
+    /// The router key.
+    key: RouterKey,
+}
+
+impl ValidatedRouterKey {
+    /// Returns the rsync URI of the router certificate.
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns information about the TAL the key was validated under.
+    pub fn tal(&self) -> &Arc<TalInfo> {
+        &self.tal
+    }
+
+    /// Returns a reference to the router key.
+    pub fn key(&self) -> &RouterKey {
+        &self.key
+    }
+
+    /// Converts the value into the router key.
+    pub fn into_key(self) -> RouterKey {
+        self.key
+    }
+}
+
+
+//------------ ObjectReport --------------------------------------------------
+
+/// The outcome of processing a single object.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ObjectReport {
+    /// The rsync URI of the object.
+    uri: uri::Rsync,
+
+    /// What happened to the object.
+    status: ObjectStatus,
+}
+
+impl ObjectReport {
+    /// Returns the rsync URI of the object.
+    pub fn uri(&self) -> &uri::Rsync {
+        &self.uri
+    }
+
+    /// Returns the status of the object.
+    pub fn status(&self) -> &ObjectStatus {
+        &self.status
+    }
+}
+
+
+//------------ ObjectStatus --------------------------------------------------
+
+/// The status of an object after processing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ObjectStatus {
+    /// The object is valid.
+    Valid,
+
+    /// The object is not present in the repository.
+    Missing,
+
+    /// The object could not be read from the repository.
+    Unreadable,
+
+    /// The object’s content does not match the hash on the manifest.
+    HashMismatch,
+
+    /// The object could not be decoded.
+    Malformed,
+
+    /// The object failed validation for the given reason.
+    Invalid(ValidationError),
+
+    /// The manifest or CRL is not current.
+    Stale,
+
+    /// The object is of a type not processed by the validator.
+    Unsupported,
+}
+
+impl fmt::Display for ObjectStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ObjectStatus::Valid => f.write_str("valid"),
+            ObjectStatus::Missing => f.write_str("missing"),
+            ObjectStatus::Unreadable => f.write_str("unreadable"),
+            ObjectStatus::HashMismatch => f.write_str("hash mismatch"),
+            ObjectStatus::Malformed => f.write_str("malformed"),
+            ObjectStatus::Invalid(ref err) => write!(f, "invalid: {}", err),
+            ObjectStatus::Stale => f.write_str("stale"),
+            ObjectStatus::Unsupported => {
+                f.write_str("unsupported object type")
+            }
+        }
+    }
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use super::*;
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
+
+    /// Creates a directory mirror from the objects in test-data.
+    fn test_repository(name: &str) -> DirectoryRepository {
+        let repo = DirectoryRepository::new(
+            std::env::temp_dir().join(
+                format!("rpki-validation-{}-{}", name, std::process::id())
+            )
+        );
+        for &(uri, file) in &[
+            ("rsync://rpki.ripe.net/ta/ripe-ncc-ta.cer", "ta.cer"),
+            ("rsync://rpki.ripe.net/repository/ripe-ncc-ta.mft", "ta.mft"),
+            ("rsync://rpki.ripe.net/repository/ripe-ncc-ta.crl", "ta.crl"),
+            (
+                "rsync://rpki.ripe.net/repository/\
+                 2a7dd1d787d793e4c8af56e197d4eed92af6ba13.cer",
+                "ca1.cer"
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/aca/\
+                 Kn3R14fXk-TIr1bhl9Tu2Sr2uhM.mft",
+                "ca1.mft"
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/aca/\
+                 Kn3R14fXk-TIr1bhl9Tu2Sr2uhM.crl",
+                "ca1.crl"
+            ),
+        ] {
+            let path = repo.uri_path(&rsync(uri));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::copy(Path::new("test-data").join(file), path).unwrap();
+        }
+        repo
+    }
+
+    fn ripe_tal() -> Tal {
+        Tal::read_named(
+            "ripe".into(),
+            &mut include_bytes!("../test-data/ripe.tal").as_ref()
+        ).unwrap()
+    }
+
+    #[test]
+    fn directory_repository() {
+        let repo = DirectoryRepository::new("/base");
+        assert_eq!(
+            repo.uri_path(&rsync("rsync://host/module/foo/bar.cer")),
+            Path::new("/base/host/module/foo/bar.cer")
+        );
+    }
+
+    #[test]
+    fn validate_test_data() {
+        let repo = test_repository("valid");
+        let tal = ripe_tal();
+        let report = Validator::new(&repo, false).validate_at(
+            Some(&tal), Time::utc(2019, 4, 6, 12, 0, 0)
+        );
+        fs::remove_dir_all(repo.base()).unwrap();
+
+        for (uri, status) in &[
+            ("rsync://rpki.ripe.net/ta/ripe-ncc-ta.cer", ObjectStatus::Valid),
+            (
+                "rsync://rpki.ripe.net/repository/ripe-ncc-ta.crl",
+                ObjectStatus::Valid
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/ripe-ncc-ta.mft",
+                ObjectStatus::Valid
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/\
+                 2a7dd1d787d793e4c8af56e197d4eed92af6ba13.cer",
+                ObjectStatus::Valid
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/aca/\
+                 HGp1AESLbyiopScGy7yW4b6s_T4.cer",
+                ObjectStatus::Missing
+            ),
+            (
+                "rsync://rpki.ripe.net/repository/aca/\
+                 Kn3R14fXk-TIr1bhl9Tu2Sr2uhM.mft",
+                ObjectStatus::Invalid(
+                    ValidationError::new(
+                        ValidationErrorKind::IncompletePublicationPoint
+                    ).with_rfc(9286, "6.4")
+                )
+            ),
+        ] {
+            assert_eq!(report.status(&rsync(uri)), Some(status), "{}", uri);
+        }
+        assert!(report.roas().is_empty());
+    }
+
+    #[test]
+    fn validate_expired_test_data() {
+        let repo = test_repository("expired");
+        let tal = ripe_tal();
+        let report = Validator::new(&repo, false).validate_at(
+            Some(&tal), Time::utc(2019, 6, 1, 0, 0, 0)
+        );
+        fs::remove_dir_all(repo.base()).unwrap();
+
+        assert_eq!(
+            report.status(&rsync("rsync://rpki.ripe.net/ta/ripe-ncc-ta.cer")),
+            Some(&ObjectStatus::Valid)
+        );
+        assert_eq!(
+            report.status(&rsync(
+                "rsync://rpki.ripe.net/repository/ripe-ncc-ta.mft"
+            )),
+            Some(&ObjectStatus::Invalid(
+                ValidationError::new(
+                    ValidationErrorKind::Expired
+                ).with_rfc(6487, "4.6")
+            ))
+        );
+        assert_eq!(report.objects().len(), 2);
+    }
+}
+
+#[cfg(all(test, feature="softkeys"))]
+mod signer_test {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use crate::cert::{KeyUsage, Overclaim, TbsCert};
+    use crate::crl::{CrlEntry, TbsCertList};
+    use crate::crypto::{DigestAlgorithm, PublicKeyFormat, Signer};
+    use crate::crypto::softsigner::{KeyId, OpenSslSigner};
+    use crate::manifest::{FileAndHash, ManifestContent};
+    use crate::resources::{AddressFamily, AsId, Prefix};
+    use crate::roa::RoaBuilder;
+    use crate::sigobj::SignedObjectBuilder;
+    use crate::vrp::Vrp;
+    use crate::x509::Validity;
+    use super::*;
+
+    fn rsync(s: &str) -> uri::Rsync {
+        uri::Rsync::from_str(s).unwrap()
+    }
+
+    /// A repository kept in memory.
+    ///
+    /// The objects can be replaced while a validator holds a reference.
+    #[derive(Default)]
+    struct TestRepository(RefCell<HashMap<uri::Rsync, Bytes>>);
+
+    impl TestRepository {
+        fn insert(&self, uri: &str, data: Bytes) {
+            self.0.borrow_mut().insert(rsync(uri), data);
+        }
+    }
+
+    impl Repository for TestRepository {
+        fn load(
+            &self, uri: &uri::Rsync
+        ) -> Result<Option<Bytes>, io::Error> {
+            Ok(self.0.borrow().get(uri).cloned())
+        }
+    }
+
+    /// A trust anchor CA publishing a single ROA.
+    struct TestCa {
+        signer: OpenSslSigner,
+        key: KeyId,
+        tal: Tal,
+        repo: TestRepository,
+    }
+
+    impl TestCa {
+        const TA_URI: &'static str = "rsync://example.com/ta/ta.cer";
+        const REPO_URI: &'static str = "rsync://example.com/repo/";
+        const MFT_URI: &'static str = "rsync://example.com/repo/ta.mft";
+        const CRL_URI: &'static str = "rsync://example.com/repo/ta.crl";
+        const ROA_URI: &'static str = "rsync://example.com/repo/test.roa";
+
+        /// Creates the CA and publishes its trust anchor certificate.
+        fn new() -> Self {
+            let mut signer = OpenSslSigner::new();
+            let key = signer.create_key(PublicKeyFormat::default()).unwrap();
+            let pubkey = signer.get_key_info(&key).unwrap();
+
+            let mut cert = TbsCert::new(
+                1u64.into(), pubkey.to_subject_name(),
+                Validity::from_secs(86400), None, pubkey.clone(),
+                KeyUsage::Ca, Overclaim::Refuse
+            );
+            cert.set_basic_ca(Some(true));
+            cert.set_ca_repository(Some(rsync(Self::REPO_URI)));
+            cert.set_rpki_manifest(Some(rsync(Self::MFT_URI)));
+            cert.build_v4_resource_blocks(|b| b.push(Prefix::new(0, 0)));
+            cert.build_as_resource_blocks(|b| {
+                b.push((AsId::MIN, AsId::MAX))
+            });
+            let cert = cert.into_cert(&signer, &key).unwrap();
+
+            let tal = format!(
+                "{}\n\n{}\n",
+                Self::TA_URI, base64::encode(&pubkey.to_info_bytes())
+            );
+            let tal = Tal::read_named(
+                "test".into(), &mut tal.as_bytes()
+            ).unwrap();
+
+            let repo = TestRepository::default();
+            repo.insert(Self::TA_URI, cert.to_captured().into_bytes());
+            TestCa { signer, key, tal, repo }
+        }
+
+        /// Publishes a CRL, a ROA, and a manifest.
+        ///
+        /// The CRL and manifest get the given number. Serial numbers of
+        /// the EE certificates are derived from it, too.
+        fn publish(&self, number: u64, revoked: &[u64]) {
+            self.publish_listing(number, revoked, &[])
+        }
+
+        /// Publishes like `publish` but lists additional files.
+        ///
+        /// The additional files are listed on the manifest only.
+        fn publish_listing(
+            &self, number: u64, revoked: &[u64], extra: &[&[u8]]
+        ) {
+            let pubkey = self.signer.get_key_info(&self.key).unwrap();
+            let crl = TbsCertList::new(
+                Default::default(),
+                pubkey.to_subject_name(),
+                Time::five_minutes_ago(),
+                Time::tomorrow(),
+                revoked.iter().map(|serial| {
+                    CrlEntry::new((*serial).into(), Time::five_minutes_ago())
+                }).collect::<Vec<_>>(),
+                pubkey.key_identifier(),
+                number.into()
+            );
+            let crl = crl.into_crl(
+                &self.signer, &self.key
+            ).unwrap().to_captured().into_bytes();
+
+            let mut roa = RoaBuilder::new(64496.into());
+            roa.push_v4_addr(Ipv4Addr::new(192, 0, 2, 0), 24, Some(26));
+            let roa = roa.finalize(
+                SignedObjectBuilder::new(
+                    (number * 2).into(), Validity::from_secs(86400),
+                    rsync(Self::CRL_URI), rsync(Self::TA_URI),
+                    rsync(Self::ROA_URI)
+                ),
+                &self.signer, &self.key
+            ).unwrap().to_captured().into_bytes();
+
+            let alg = DigestAlgorithm::default();
+            let mut files = vec![
+                FileAndHash::new(
+                    b"ta.crl".to_vec(), alg.digest(&crl).as_ref().to_vec()
+                ),
+                FileAndHash::new(
+                    b"test.roa".to_vec(), alg.digest(&roa).as_ref().to_vec()
+                ),
+            ];
+            files.extend(extra.iter().map(|name| {
+                FileAndHash::new(
+                    name.to_vec(), alg.digest(b"").as_ref().to_vec()
+                )
+            }));
+            let content = ManifestContent::new(
+                number.into(), Time::five_minutes_ago(), Time::tomorrow(),
+                alg, files.iter()
+            );
+            let mft = content.into_manifest(
+                SignedObjectBuilder::new(
+                    (number * 2 + 1).into(), Validity::from_secs(86400),
+                    rsync(Self::CRL_URI), rsync(Self::TA_URI),
+                    rsync(Self::MFT_URI)
+                ),
+                &self.signer, &self.key
+            ).unwrap().to_captured().into_bytes();
+
+            self.repo.insert(Self::CRL_URI, crl);
+            self.repo.insert(Self::ROA_URI, roa);
+            self.repo.insert(Self::MFT_URI, mft);
+        }
+    }
+
+    #[test]
+    fn validate_roa() {
+        let ca = TestCa::new();
+        ca.publish(1, &[]);
+        let report = Validator::new(&ca.repo, true).validate(Some(&ca.tal));
+
+        for uri in &[
+            TestCa::TA_URI, TestCa::MFT_URI, TestCa::CRL_URI, TestCa::ROA_URI
+        ] {
+            assert_eq!(
+                report.status(&rsync(uri)), Some(&ObjectStatus::Valid),
+                "{}", uri
+            );
+        }
+        let vrps: Vec<_> = report.roas().iter().flat_map(|roa| {
+            Vrp::iter_roa(roa.content())
+        }).collect();
+        assert_eq!(
+            vrps,
+            vec![Vrp::new(
+                64496.into(), AddressFamily::Ipv4,
+                Prefix::from_v4_str("192.0.2.0/24").unwrap(), 26
+            )]
+        );
+        assert_eq!(report.roas()[0].uri(), &rsync(TestCa::ROA_URI));
+
+        // Revoking the ROA’s EE certificate makes it invalid.
+        ca.publish(2, &[4]);
+        let report = Validator::new(&ca.repo, true).validate(Some(&ca.tal));
+        assert_eq!(
+            report.status(&rsync(TestCa::ROA_URI)),
+            Some(&ObjectStatus::Invalid(ValidationErrorKind::Revoked.into()))
+        );
+        assert!(report.roas().is_empty());
+    }
+
+    #[test]
+    fn reject_hostile_file_names() {
+        let ca = TestCa::new();
+        for name in &[
+            b"bad name.roa".as_ref(), b"quote\".roa".as_ref(),
+            b"../../etc/passwd.roa".as_ref(), b"sub/test.roa".as_ref(),
+        ] {
+            ca.publish_listing(1, &[], &[name]);
+            let report = Validator::new(
+                &ca.repo, true
+            ).validate(Some(&ca.tal));
+            match report.status(&rsync(TestCa::MFT_URI)) {
+                Some(ObjectStatus::Invalid(err)) => {
+                    assert_eq!(err.kind(), ValidationErrorKind::Malformed)
+                }
+                status => panic!("unexpected manifest status {:?}", status),
+            }
+            assert!(report.roas().is_empty());
+        }
+    }
+
+    #[test]
+    fn reject_crl_number_rollback() {
+        let ca = TestCa::new();
+        let mut validator = Validator::new(&ca.repo, true);
+        ca.publish(2, &[]);
+        let report = validator.validate(Some(&ca.tal));
+        assert_eq!(
+            report.status(&rsync(TestCa::CRL_URI)), Some(&ObjectStatus::Valid)
+        );
+        assert_eq!(report.roas().len(), 1);
+
+        // Going back to an older CRL must fail with the same validator.
+        ca.publish(1, &[]);
+        let report = validator.validate(Some(&ca.tal));
+        match report.status(&rsync(TestCa::CRL_URI)) {
+            Some(ObjectStatus::Invalid(err)) => {
+                assert_eq!(err.kind(), ValidationErrorKind::CrlNumberRollback)
+            }
+            status => panic!("unexpected CRL status {:?}", status),
+        }
+        assert!(report.roas().is_empty());
+
+        // A fresh validator hasn’t seen the newer CRL.
+        let report = Validator::new(&ca.repo, true).validate(Some(&ca.tal));
+        assert_eq!(
+            report.status(&rsync(TestCa::CRL_URI)), Some(&ObjectStatus::Valid)
+        );
+
+        // Moving forward again is fine.
+        ca.publish(3, &[]);
+        let report = validator.validate(Some(&ca.tal));
+        assert_eq!(report.roas().len(), 1);
+    }
+}
@@ -0,0 +1,669 @@
+//! Validated ROA payloads and route origin validation.
+//!
+//! The outcome of validating ROAs is a set of _validated ROA payloads_ or
+//! VRPs, each consisting of an AS number, an address prefix, and a maximum
+//! prefix length. These are represented by the type [`Vrp`].
+//!
+//! Routers use VRPs for route origin validation as defined in [RFC 6811]:
+//! a route is _valid_ if there is at least one VRP for its origin AS that
+//! covers the route’s prefix and allows its prefix length, _invalid_ if
+//! there are covering VRPs but none of them match, and _not found_ if there
+//! aren’t any covering VRPs at all. The type [`VrpIndex`] keeps a set of
+//! VRPs in a prefix tree per address family so that these lookups can be
+//! done quickly even for large sets of VRPs.
+//!
+//! [`Vrp`]: struct.Vrp.html
+//! [`VrpIndex`]: struct.VrpIndex.html
+//! [RFC 6811]: https://tools.ietf.org/html/rfc6811
+
+use std::{fmt, iter};
+use crate::resources::{Addr, AddressFamily, AsId, Prefix};
+use crate::roa::{FriendlyRoaIpAddress, RouteOriginAttestation};
+
+
+//------------ Vrp -----------------------------------------------------------
+
+/// A validated ROA payload.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Vrp {
+    /// The AS number allowed to originate routes.
+    asn: AsId,
+
+    /// The address family of the prefix.
+    family: AddressFamily,
+
+    /// The address prefix.
+    prefix: Prefix,
+
+    /// The maximum prefix length of routes.
+    max_length: u8,
+}
+
+impl Vrp {
+    /// Creates a new VRP from its components.
+    ///
+    /// A VRP whose `max_length` is smaller than the prefix length will
+    /// never match any route but still counts as covering.
+    pub fn new(
+        asn: AsId,
+        family: AddressFamily,
+        prefix: Prefix,
+        max_length: u8
+    ) -> Self {
+        Vrp { asn, family, prefix, max_length }
+    }
+
+    /// Returns an iterator over the VRPs of a ROA.
+    pub fn iter_roa(
+        roa: &RouteOriginAttestation
+    ) -> impl Iterator<Item=Vrp> + '_ {
+        let asn = roa.as_id();
+        roa.iter().map(move |addr| Self::from_roa_addr(asn, &addr))
+    }
+
+    /// Creates a VRP from an AS number and a ROA address.
+    pub fn from_roa_addr(asn: AsId, addr: &FriendlyRoaIpAddress) -> Self {
+        let address = addr.address();
+        let family = if address.is_ipv4() {
+            AddressFamily::Ipv4
+        }
+        else {
+            AddressFamily::Ipv6
+        };
+        Vrp::new(
+            asn, family,
+            Prefix::new(Addr::from(address), addr.address_length()),
+            addr.max_length()
+        )
+    }
+
+    /// Returns the AS number of the VRP.
+    pub fn asn(&self) -> AsId {
+        self.asn
+    }
+
+    /// Returns the address family of the VRP.
+    pub fn family(&self) -> AddressFamily {
+        self.family
+    }
+
+    /// Returns the prefix of the VRP.
+    pub fn prefix(&self) -> Prefix {
+        self.prefix
+    }
+
+    /// Returns the maximum prefix length of the VRP.
+    pub fn max_length(&self) -> u8 {
+        self.max_length
+    }
+
+    /// Returns whether the VRP covers the given prefix.
+    ///
+    /// This is the case if the VRP’s prefix is equal to or less specific
+    /// than `prefix`. The address family is not checked.
+    pub fn covers(&self, prefix: Prefix) -> bool {
+        self.prefix.addr_len() <= prefix.addr_len()
+            && prefix.addr().to_min(self.prefix.addr_len())
+                == self.prefix.addr()
+    }
+
+    /// Returns whether the VRP matches a route.
+    ///
+    /// A route matches if the VRP covers its prefix, the prefix length is
+    /// not larger than the VRP’s maximum length, and the origin AS is that
+    /// of the VRP. If `origin` is `None`, i.e., the route’s origin cannot
+    /// be determined, and for VRPs for AS 0, there never is a match.
+    pub fn matches(&self, prefix: Prefix, origin: Option<AsId>) -> bool {
+        self.covers(prefix)
+            && prefix.addr_len() <= self.max_length
+            && origin == Some(self.asn)
+            && self.asn != AsId::MIN
+    }
+
+    /// Returns a key for sorting VRPs by prefix.
+    fn sort_key(&self) -> (u128, u8, u32, u8) {
+        (
+            self.prefix.addr().to_bits(), self.prefix.addr_len(),
+            self.asn.into(), self.max_length
+        )
+    }
+}
+
+
+//--- Display
+
+impl fmt::Display for Vrp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.family {
+            AddressFamily::Ipv4 => self.prefix.fmt_v4(f)?,
+            AddressFamily::Ipv6 => self.prefix.fmt_v6(f)?,
+        }
+        write!(f, "-{} => {}", self.max_length, self.asn)
+    }
+}
+
+
+//------------ VrpIndex ------------------------------------------------------
+
+/// A set of VRPs prepared for route origin validation.
+///
+/// The index is created once from all VRPs via `FromIterator` or
+/// [`from_roas`] and can then be queried via [`validate`] or
+/// [`covering`].
+///
+/// [`from_roas`]: #method.from_roas
+/// [`validate`]: #method.validate
+/// [`covering`]: #method.covering
+#[derive(Clone, Debug, Default)]
+pub struct VrpIndex {
+    /// The tree for IPv4 VRPs.
+    v4: PrefixTree,
+
+    /// The tree for IPv6 VRPs.
+    v6: PrefixTree,
+}
+
+impl VrpIndex {
+    /// Creates an index from the VRPs of a set of ROAs.
+    pub fn from_roas<'a, I>(roas: I) -> Self
+    where I: IntoIterator<Item=&'a RouteOriginAttestation> {
+        roas.into_iter().flat_map(Vrp::iter_roa).collect()
+    }
+
+    /// Returns the number of VRPs in the index.
+    ///
+    /// Duplicate VRPs are only counted once.
+    pub fn len(&self) -> usize {
+        self.v4.vrps.len() + self.v6.vrps.len()
+    }
+
+    /// Returns whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over all VRPs in the index.
+    pub fn iter(&self) -> impl Iterator<Item=&Vrp> {
+        self.v4.vrps.iter().chain(self.v6.vrps.iter())
+    }
+
+    /// Returns an iterator over the VRPs covering a prefix.
+    ///
+    /// The VRPs are returned from least specific to most specific.
+    pub fn covering(
+        &self, family: AddressFamily, prefix: Prefix
+    ) -> impl Iterator<Item=&Vrp> {
+        self.tree(family).covering(prefix)
+    }
+
+    /// Validates the origin of a route.
+    ///
+    /// The route is given through the address family and prefix as well
+    /// as its origin AS. The latter can be `None` if it can’t be
+    /// determined, e.g., because the route’s AS path ends in an AS set.
+    pub fn validate(
+        &self,
+        family: AddressFamily,
+        prefix: Prefix,
+        origin: Option<AsId>
+    ) -> RouteValidation<'_> {
+        RouteValidation {
+            prefix,
+            origin,
+            covering: self.covering(family, prefix).collect(),
+        }
+    }
+
+    fn tree(&self, family: AddressFamily) -> &PrefixTree {
+        match family {
+            AddressFamily::Ipv4 => &self.v4,
+            AddressFamily::Ipv6 => &self.v6,
+        }
+    }
+}
+
+
+//--- FromIterator
+
+impl iter::FromIterator<Vrp> for VrpIndex {
+    fn from_iter<I: IntoIterator<Item=Vrp>>(iter: I) -> Self {
+        let (v4, v6): (Vec<_>, Vec<_>) = iter.into_iter().partition(|vrp| {
+            vrp.family == AddressFamily::Ipv4
+        });
+        VrpIndex {
+            v4: PrefixTree::new(v4),
+            v6: PrefixTree::new(v6),
+        }
+    }
+}
+
+
+//------------ RouteValidation -----------------------------------------------
+
+/// The result of validating the origin of a route.
+#[derive(Clone, Debug)]
+pub struct RouteValidation<'a> {
+    /// The prefix of the route.
+    prefix: Prefix,
+
+    /// The origin AS of the route.
+    origin: Option<AsId>,
+
+    /// All VRPs covering the route’s prefix.
+    covering: Vec<&'a Vrp>,
+}
+
+impl<'a> RouteValidation<'a> {
+    /// Returns the validation state of the route.
+    pub fn state(&self) -> RouteState {
+        if self.matching().next().is_some() {
+            RouteState::Valid
+        }
+        else if self.covering.is_empty() {
+            RouteState::NotFound
+        }
+        else {
+            RouteState::Invalid
+        }
+    }
+
+    /// Returns an iterator over the VRPs matching the route.
+    pub fn matching<'s>(&'s self) -> impl Iterator<Item=&'a Vrp> + 's {
+        let (prefix, origin) = (self.prefix, self.origin);
+        self.covering.iter().cloned().filter(move |vrp| {
+            vrp.matches(prefix, origin)
+        })
+    }
+
+    /// Returns the VRPs covering the route’s prefix.
+    ///
+    /// The VRPs are ordered from least specific to most specific.
+    pub fn covering(&self) -> &[&'a Vrp] {
+        &self.covering
+    }
+}
+
+
+//------------ RouteState ----------------------------------------------------
+
+/// The route origin validation state of a route as defined in RFC 6811.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RouteState {
+    /// At least one VRP matches the route.
+    Valid,
+
+    /// There are VRPs covering the route but none of them matches.
+    Invalid,
+
+    /// No VRP covers the route.
+    NotFound,
+}
+
+impl fmt::Display for RouteState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            RouteState::Valid => "valid",
+            RouteState::Invalid => "invalid",
+            RouteState::NotFound => "not-found",
+        })
+    }
+}
+
+
+//------------ PrefixTree ----------------------------------------------------
+
+/// A path-compressed binary tree of the VRPs of one address family.
+///
+/// The VRPs are kept sorted by prefix in a vec so that all VRPs for the
+/// same prefix form a consecutive slice. The nodes of the tree are kept in
+/// a vec, too, and refer to each other and to the VRPs via indexes. The
+/// node at index 0 is the root for the prefix of length zero.
+///
+/// Each node has up to two children, one for each value of the bit
+/// following the node’s prefix. Nodes only exist for prefixes that have
+/// VRPs and where the paths to two such nodes branch off.
+#[derive(Clone, Debug)]
+struct PrefixTree {
+    /// The nodes of the tree.
+    nodes: Vec<Node>,
+
+    /// The VRPs sorted by prefix.
+    vrps: Vec<Vrp>,
+}
+
+/// A node in a prefix tree.
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// The prefix of the node.
+    prefix: Prefix,
+
+    /// The indexes of the child nodes or `NO_NODE` if there is none.
+    children: [u32; 2],
+
+    /// The index of the first VRP for the prefix.
+    start: u32,
+
+    /// The index after the last VRP for the prefix.
+    end: u32,
+}
+
+/// The child index used for a missing child.
+const NO_NODE: u32 = std::u32::MAX;
+
+impl Node {
+    fn new(prefix: Prefix, start: u32, end: u32) -> Self {
+        Node { prefix, children: [NO_NODE, NO_NODE], start, end }
+    }
+}
+
+impl PrefixTree {
+    /// Creates a new tree from a set of VRPs.
+    fn new(mut vrps: Vec<Vrp>) -> Self {
+        vrps.sort_unstable_by_key(Vrp::sort_key);
+        vrps.dedup();
+        let mut res = PrefixTree {
+            nodes: vec![Node::new(Prefix::new(0, 0), 0, 0)],
+            vrps: Vec::new(),
+        };
+        let mut start = 0;
+        while start < vrps.len() {
+            let prefix = vrps[start].prefix;
+            let end = start + vrps[start..].iter().take_while(|vrp| {
+                vrp.prefix == prefix
+            }).count();
+            res.insert(prefix, start as u32, end as u32);
+            start = end;
+        }
+        res.vrps = vrps;
+        res
+    }
+
+    /// Inserts a node for the VRPs of a prefix.
+    fn insert(&mut self, prefix: Prefix, start: u32, end: u32) {
+        let mut idx = 0;
+        loop {
+            // The node at idx always covers prefix.
+            let node = self.nodes[idx];
+            if node.prefix.addr_len() == prefix.addr_len() {
+                self.nodes[idx].start = start;
+                self.nodes[idx].end = end;
+                return
+            }
+            let bit = bit_at(prefix.addr(), node.prefix.addr_len());
+            let child_idx = node.children[bit];
+            if child_idx == NO_NODE {
+                self.nodes[idx].children[bit] = self.push(
+                    Node::new(prefix, start, end)
+                );
+                return
+            }
+            let child = self.nodes[child_idx as usize].prefix;
+            let common = common_len(child, prefix);
+            if common == child.addr_len() {
+                // The child covers prefix, too.
+                idx = child_idx as usize;
+                continue
+            }
+            let mut new = if common == prefix.addr_len() {
+                // Prefix covers child, so it goes between node and child.
+                Node::new(prefix, start, end)
+            }
+            else {
+                // Prefix and child branch off after common. We need a new
+                // node without VRPs for the branching point.
+                let mut new = Node::new(
+                    Prefix::new(prefix.addr(), common), 0, 0
+                );
+                new.children[bit_at(prefix.addr(), common)] = self.push(
+                    Node::new(prefix, start, end)
+                );
+                new
+            };
+            new.children[bit_at(child.addr(), common)] = child_idx;
+            self.nodes[idx].children[bit] = self.push(new);
+            return
+        }
+    }
+
+    /// Adds a node to the tree and returns its index.
+    fn push(&mut self, node: Node) -> u32 {
+        // With each VRP resulting in at most two nodes, we won’t run out
+        // of node indexes before the VRP indexes overflow.
+        let res = self.nodes.len() as u32;
+        self.nodes.push(node);
+        res
+    }
+
+    /// Returns an iterator over the VRPs covering the given prefix.
+    fn covering(&self, prefix: Prefix) -> impl Iterator<Item=&Vrp> {
+        CoveringNodes { tree: self, idx: 0, prefix }.flat_map(move |node| {
+            self.vrps[node.start as usize..node.end as usize].iter()
+        })
+    }
+}
+
+impl Default for PrefixTree {
+    fn default() -> Self {
+        PrefixTree::new(Vec::new())
+    }
+}
+
+
+//------------ CoveringNodes -------------------------------------------------
+
+/// An iterator over the nodes of a tree covering a prefix.
+struct CoveringNodes<'a> {
+    /// The tree to walk.
+    tree: &'a PrefixTree,
+
+    /// The index of the next node to look at or `NO_NODE` if done.
+    idx: u32,
+
+    /// The prefix to look for.
+    prefix: Prefix,
+}
+
+impl<'a> Iterator for CoveringNodes<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx != NO_NODE {
+            let node = &self.tree.nodes[self.idx as usize];
+            let len = node.prefix.addr_len();
+            if len > self.prefix.addr_len()
+                || self.prefix.addr().to_min(len) != node.prefix.addr() {
+                // Nodes further down are even more specific.
+                self.idx = NO_NODE;
+                return None
+            }
+            self.idx = if len < self.prefix.addr_len() {
+                node.children[bit_at(self.prefix.addr(), len)]
+            }
+            else {
+                NO_NODE
+            };
+            if node.start != node.end {
+                return Some(node)
+            }
+        }
+        None
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Returns the bit following the first `len` bits of `addr`.
+///
+/// `len` must be less than 128.
+fn bit_at(addr: Addr, len: u8) -> usize {
+    ((addr.to_bits() >> (127 - len)) & 1) as usize
+}
+
+/// Returns the length of the longest prefix covering both prefixes.
+fn common_len(left: Prefix, right: Prefix) -> u8 {
+    let diff = (left.addr().to_bits() ^ right.addr().to_bits())
+        .leading_zeros() as u8;
+    diff.min(left.addr_len()).min(right.addr_len())
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use super::*;
+
+    fn v4(asn: u32, prefix: &str, max_length: u8) -> Vrp {
+        Vrp::new(
+            asn.into(), AddressFamily::Ipv4,
+            Prefix::from_v4_str(prefix).unwrap(), max_length
+        )
+    }
+
+    fn v6(asn: u32, prefix: &str, max_length: u8) -> Vrp {
+        Vrp::new(
+            asn.into(), AddressFamily::Ipv6,
+            Prefix::from_v6_str(prefix).unwrap(), max_length
+        )
+    }
+
+    fn validate(
+        index: &VrpIndex, prefix: &str, origin: u32
+    ) -> RouteState {
+        let (family, prefix) = if prefix.contains(':') {
+            (AddressFamily::Ipv6, Prefix::from_v6_str(prefix).unwrap())
+        }
+        else {
+            (AddressFamily::Ipv4, Prefix::from_v4_str(prefix).unwrap())
+        };
+        index.validate(family, prefix, Some(origin.into())).state()
+    }
+
+    #[test]
+    fn route_origin_validation() {
+        let index: VrpIndex = vec![
+            v4(64496, "192.0.2.0/24", 24),
+            v4(64497, "192.0.2.0/24", 26),
+            v4(64498, "10.0.0.0/8", 16),
+            v4(0, "198.51.100.0/24", 32),
+            v4(64499, "0.0.0.0/0", 0),
+            v6(64496, "2001:db8::/32", 48),
+            v6(64496, "2001:db8::/32", 48),
+        ].into_iter().collect();
+        assert_eq!(index.len(), 6);
+
+        assert_eq!(
+            validate(&index, "192.0.2.0/24", 64496), RouteState::Valid
+        );
+        assert_eq!(
+            validate(&index, "192.0.2.0/25", 64497), RouteState::Valid
+        );
+        assert_eq!(
+            validate(&index, "192.0.2.0/25", 64496), RouteState::Invalid
+        );
+        assert_eq!(
+            validate(&index, "192.0.2.0/27", 64497), RouteState::Invalid
+        );
+        assert_eq!(validate(&index, "10.1.0.0/16", 64498), RouteState::Valid);
+        assert_eq!(
+            validate(&index, "10.1.1.0/24", 64498), RouteState::Invalid
+        );
+        assert_eq!(
+            validate(&index, "198.51.100.0/24", 0), RouteState::Invalid
+        );
+        assert_eq!(validate(&index, "0.0.0.0/0", 64499), RouteState::Valid);
+        assert_eq!(
+            validate(&index, "203.0.113.0/24", 64499), RouteState::Invalid
+        );
+        assert_eq!(
+            validate(&index, "2001:db8:1::/48", 64496), RouteState::Valid
+        );
+        assert_eq!(
+            validate(&index, "2001:db8:1::/49", 64496), RouteState::Invalid
+        );
+        assert_eq!(
+            validate(&index, "2001:db9::/32", 64496), RouteState::NotFound
+        );
+        // IPv4 VRPs mustn’t cover IPv6 routes and vice versa.
+        assert_eq!(
+            validate(&index, "c000:0200::/24", 64496), RouteState::NotFound
+        );
+
+        let route = index.validate(
+            AddressFamily::Ipv4,
+            Prefix::from_v4_str("192.0.2.0/25").unwrap(),
+            None
+        );
+        assert_eq!(route.state(), RouteState::Invalid);
+        assert_eq!(
+            route.covering().iter().map(|vrp| vrp.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "0.0.0.0/0-0 => AS64499",
+                "192.0.2.0/24-24 => AS64496",
+                "192.0.2.0/24-26 => AS64497",
+            ]
+        );
+        assert_eq!(route.matching().count(), 0);
+    }
+
+    #[test]
+    fn covering_against_linear_search() {
+        // Builds an index over a bunch of overlapping prefixes and compares
+        // the covering VRPs for all prefixes in the range with what a
+        // linear search finds.
+        let mut vrps = Vec::new();
+        for len in 16..=24u8 {
+            for third in (0..=255u8).step_by(usize::from(len) * 3) {
+                vrps.push(Vrp::new(
+                    u32::from(len).into(), AddressFamily::Ipv4,
+                    Prefix::new(
+                        std::net::Ipv4Addr::new(10, 1, third, 0), len
+                    ),
+                    len + 2
+                ));
+            }
+        }
+        let index: VrpIndex = vrps.iter().cloned().collect();
+        for third in 0..=255u8 {
+            for len in 8..=26u8 {
+                let prefix = Prefix::new(
+                    std::net::Ipv4Addr::new(10, 1, third, 0), len
+                );
+                let mut expected = vrps.iter().filter(|vrp| {
+                    vrp.covers(prefix)
+                }).cloned().collect::<Vec<_>>();
+                expected.sort_by_key(Vrp::sort_key);
+                expected.dedup();
+                let found = index.covering(
+                    AddressFamily::Ipv4, prefix
+                ).cloned().collect::<Vec<_>>();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn from_roa() {
+        let roa = crate::roa::Roa::decode(
+            bytes::Bytes::from_static(
+                include_bytes!("../test-data/example-ripe.roa")
+            ),
+            false
+        ).unwrap();
+        let index = VrpIndex::from_roas(Some(roa.content()));
+        assert_eq!(
+            index.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["2a0c:b642:fc0::/43-43 => AS209870"]
+        );
+        assert_eq!(
+            validate(&index, "2a0c:b642:fc0::/43", 209870), RouteState::Valid
+        );
+        assert_eq!(
+            AsId::from_str("AS209870").unwrap(),
+            index.iter().next().unwrap().asn()
+        );
+    }
+}
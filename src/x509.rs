@@ -16,6 +16,7 @@ use chrono::{
 };
 use serde::de;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::cert::OverclaimReport;
 use crate::crypto::{
     PublicKey, Signature, SignatureAlgorithm, Signer, VerificationError
 };
@@ -136,7 +137,7 @@ impl Name {
                     })? {}
                     Ok(())
                 })
-            })?
+            }).map_err(|_| ValidationErrorKind::InvalidName)?
         }
         Ok(())
     }
@@ -176,6 +177,97 @@ impl Name {
 }
 
 
+//--- PartialEq and Eq
+
+/// Names are compared by their DER encoding.
+///
+/// This is stricter than the name matching rules of RFC 5280 but is what
+/// RFC 6487 asks for: a CA must use the exact same encoding of its name as
+/// issuer in everything it issues.
+impl PartialEq for Name {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_slice() == other.0.as_slice()
+    }
+}
+
+impl Eq for Name { }
+
+
+//--- Display
+
+/// Names are displayed as a comma separated list of attributes.
+///
+/// The attributes appear in the order they are encoded. Common name and
+/// serial number attributes are shown with their usual labels, all other
+/// attributes use the dotted notation of their object identifier. Values
+/// that aren’t printable ASCII are shown as hex-encoded octets. Values
+/// that aren’t primitive are shown as their complete hex-encoded encoding,
+/// as is the entire name if it can’t be decoded at all.
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let attrs = self.0.clone().decode(|cons| {
+            let mut attrs = Vec::new();
+            cons.take_sequence(|cons| {
+                while let Some(()) = cons.take_opt_set(|cons| {
+                    while let Some(()) = cons.take_opt_sequence(|cons| {
+                        let id = Oid::take_from(cons)?;
+                        let value = cons.capture_one()?;
+                        attrs.push((id, value));
+                        Ok(())
+                    })? { }
+                    Ok(())
+                })? { }
+                Ok(())
+            })?;
+            Ok(attrs)
+        });
+        let attrs = match attrs {
+            Ok(attrs) => attrs,
+            Err(_) => return write_hex(f, self.0.as_slice()),
+        };
+        for (idx, (id, value)) in attrs.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            if *id == oid::AT_COMMON_NAME {
+                f.write_str("CN=")?;
+            }
+            else if *id == oid::AT_SERIAL_NUMBER {
+                f.write_str("SERIALNUMBER=")?;
+            }
+            else {
+                write!(f, "{}=", id)?;
+            }
+            let content = value.clone().decode(|cons| {
+                cons.take_value(|_, content| {
+                    content.as_primitive()?.take_all()
+                })
+            });
+            match content {
+                Ok(ref content) if content.iter().all(|&ch| {
+                    ch.is_ascii_graphic() || ch == b' '
+                }) => {
+                    // We just checked that this is ASCII.
+                    f.write_str(str::from_utf8(content).unwrap())?;
+                }
+                Ok(ref content) => write_hex(f, content)?,
+                Err(_) => write_hex(f, value.as_slice())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes data as a hash sign followed by the hex-encoded octets.
+fn write_hex(f: &mut fmt::Formatter, data: &[u8]) -> fmt::Result {
+    f.write_str("#")?;
+    for ch in data {
+        write!(f, "{:02x}", ch)?;
+    }
+    Ok(())
+}
+
+
 //------------ Serial --------------------------------------------------------
 
 /// A certificate serial number.
@@ -687,7 +779,7 @@ impl Time {
         now: Time
     ) -> Result<(), ValidationError> {
         if now.0 < self.0 {
-            Err(ValidationError)
+            Err(ValidationErrorKind::NotYetValid.into())
         }
         else {
             Ok(())
@@ -699,7 +791,7 @@ impl Time {
         now: Time
     ) -> Result<(), ValidationError> {
         if now.0 > self.0 {
-            Err(ValidationError)
+            Err(ValidationErrorKind::Expired.into())
         }
         else {
             Ok(())
@@ -993,30 +1085,278 @@ impl error::Error for RepresentationError { }
 
 //------------ ValidationError -----------------------------------------------
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct ValidationError;
+/// An object failed validation.
+///
+/// The error describes what kind of problem was encountered and, where
+/// possible, references the section of the RFC that was violated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ValidationError {
+    /// The kind of validation failure.
+    kind: ValidationErrorKind,
+
+    /// The section of the RFC that was violated if known.
+    section: Option<RfcSection>,
+
+    /// The overclaimed resources if the error is an overclaim.
+    ///
+    /// This is boxed to keep the error small for the common case.
+    overclaim: Option<Box<OverclaimReport>>,
+}
+
+impl ValidationError {
+    /// Creates a new error of the given kind.
+    pub fn new(kind: ValidationErrorKind) -> Self {
+        ValidationError { kind, section: None, overclaim: None }
+    }
+
+    /// Returns the error with a reference to an RFC section added.
+    ///
+    /// The `rfc` is the number of the RFC and `section` the section
+    /// within that RFC, e.g., `"4.8.10"`.
+    pub fn with_rfc(self, rfc: u16, section: &'static str) -> Self {
+        ValidationError {
+            section: Some(RfcSection { rfc, section }),
+            ..self
+        }
+    }
+
+    /// Returns the error with a report of overclaimed resources added.
+    pub fn with_overclaim(self, report: OverclaimReport) -> Self {
+        ValidationError {
+            overclaim: Some(Box::new(report)),
+            ..self
+        }
+    }
+
+    /// Returns the kind of validation failure.
+    pub fn kind(&self) -> ValidationErrorKind {
+        self.kind
+    }
+
+    /// Returns the RFC section that was violated if it is known.
+    pub fn rfc_section(&self) -> Option<RfcSection> {
+        self.section
+    }
+
+    /// Returns the overclaimed resources if the error is an overclaim.
+    pub fn overclaim_report(&self) -> Option<&OverclaimReport> {
+        self.overclaim.as_ref().map(AsRef::as_ref)
+    }
+}
+
+impl From<ValidationErrorKind> for ValidationError {
+    fn from(kind: ValidationErrorKind) -> ValidationError {
+        ValidationError::new(kind)
+    }
+}
 
 impl From<decode::Error> for ValidationError {
     fn from(_: decode::Error) -> ValidationError {
-        ValidationError
+        ValidationError::new(ValidationErrorKind::Malformed)
     }
 }
 
 impl From<VerificationError> for ValidationError {
     fn from(_: VerificationError) -> ValidationError {
-        ValidationError
+        ValidationError::new(ValidationErrorKind::BadSignature)
     }
 }
 
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str("validation error")
+        match self.section {
+            Some(section) => write!(f, "{} ({})", self.kind, section)?,
+            None => self.kind.fmt(f)?
+        }
+        if let Some(ref report) = self.overclaim {
+            write!(f, ": {}", report)?;
+        }
+        Ok(())
     }
 }
 
 impl error::Error for ValidationError { }
 
 
+//------------ ValidationErrorKind -------------------------------------------
+
+/// The kind of problem that caused validation to fail.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ValidationErrorKind {
+    /// The object or a part of it is not correctly encoded.
+    Malformed,
+
+    /// The signature algorithms given in the object differ.
+    SignatureAlgorithmMismatch,
+
+    /// The signature could not be verified.
+    BadSignature,
+
+    /// The issuer or subject name does not follow the RPKI profile.
+    InvalidName,
+
+    /// The validity period of the certificate hasn’t started yet.
+    NotYetValid,
+
+    /// The validity period of the certificate has passed.
+    Expired,
+
+    /// The basic constraints extension is missing or not allowed.
+    InvalidBasicConstraints,
+
+    /// The subject key identifier does not match the public key.
+    KeyIdentifierMismatch,
+
+    /// The authority key identifier does not match the issuer’s key.
+    AuthorityKeyIdentifierMismatch,
+
+    /// The key usage does not fit the type of certificate.
+    InvalidKeyUsage,
+
+    /// The extended key usage extension is missing or not allowed.
+    InvalidExtendedKeyUsage,
+
+    /// The subject public key has a format not allowed for the certificate.
+    InvalidPublicKey,
+
+    /// The CRL distribution points are missing or not allowed.
+    InvalidCrlDistributionPoint,
+
+    /// The authority information access is missing or not allowed.
+    InvalidAuthorityInfoAccess,
+
+    /// The subject information access is missing or incorrect.
+    InvalidSubjectInfoAccess,
+
+    /// Resources are inherited where this isn’t allowed.
+    InheritedResources,
+
+    /// The certificate claims resources not held by its issuer.
+    Overclaim,
+
+    /// The resources of a signed object aren’t covered by its certificate.
+    ResourcesNotCovered,
+
+    /// The certificate carries resources not allowed for the object type.
+    UnexpectedResources,
+
+    /// The signer identifier doesn’t match the EE certificate.
+    SignerIdentifierMismatch,
+
+    /// The message digest doesn’t match the content.
+    MessageDigestMismatch,
+
+    /// An object’s hash doesn’t match the one from the manifest.
+    HashMismatch,
+
+    /// The certificate has been revoked.
+    Revoked,
+
+    /// The trust anchor certificate’s key differs from the one in the TAL.
+    TrustAnchorKeyMismatch,
+
+    /// The manifest doesn’t list exactly the CRL of its EE certificate.
+    ManifestCrlMismatch,
+
+    /// Objects listed on the manifest are missing or corrupt.
+    IncompletePublicationPoint,
+
+    /// The certificate chain is too long or contains a loop.
+    ChainDepth,
+
+    /// The issuer name doesn’t match the subject name of the issuer.
+    IssuerNameMismatch,
+
+    /// The thisUpdate time of a CRL hasn’t been reached yet.
+    CrlNotYetValid,
+
+    /// The nextUpdate time of a CRL has passed.
+    StaleCrl,
+
+    /// The CRL number is lower than that of a previously seen CRL.
+    CrlNumberRollback,
+
+    /// The manifest number is lower than that of a previous manifest.
+    ManifestNumberRollback,
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::ValidationErrorKind::*;
+
+        f.write_str(match *self {
+            Malformed => "malformed object",
+            SignatureAlgorithmMismatch => "signature algorithm mismatch",
+            BadSignature => "invalid signature",
+            InvalidName => "invalid issuer or subject name",
+            NotYetValid => "certificate not yet valid",
+            Expired => "certificate expired",
+            InvalidBasicConstraints => "invalid basic constraints",
+            KeyIdentifierMismatch => "subject key identifier mismatch",
+            AuthorityKeyIdentifierMismatch => {
+                "authority key identifier mismatch"
+            }
+            InvalidKeyUsage => "invalid key usage",
+            InvalidExtendedKeyUsage => "invalid extended key usage",
+            InvalidPublicKey => "invalid subject public key format",
+            InvalidCrlDistributionPoint => "invalid CRL distribution points",
+            InvalidAuthorityInfoAccess => {
+                "invalid authority information access"
+            }
+            InvalidSubjectInfoAccess => "invalid subject information access",
+            InheritedResources => "inherited resources not allowed",
+            Overclaim => "resources not held by issuer",
+            ResourcesNotCovered => "resources not covered by certificate",
+            UnexpectedResources => "resources not allowed for object",
+            SignerIdentifierMismatch => "signer identifier mismatch",
+            MessageDigestMismatch => "message digest mismatch",
+            HashMismatch => "hash mismatch",
+            Revoked => "certificate revoked",
+            TrustAnchorKeyMismatch => "trust anchor key mismatch",
+            ManifestCrlMismatch => "manifest CRL mismatch",
+            IncompletePublicationPoint => "incomplete publication point",
+            ChainDepth => "certificate chain too long or looping",
+            IssuerNameMismatch => "issuer name mismatch",
+            CrlNotYetValid => "CRL not yet valid",
+            StaleCrl => "stale CRL",
+            CrlNumberRollback => "CRL number decreased",
+            ManifestNumberRollback => "manifest number decreased",
+        })
+    }
+}
+
+
+//------------ RfcSection ----------------------------------------------------
+
+/// A reference to a section of an RFC.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RfcSection {
+    /// The number of the RFC.
+    rfc: u16,
+
+    /// The section within the RFC.
+    section: &'static str,
+}
+
+impl RfcSection {
+    /// Returns the number of the RFC.
+    pub fn rfc(&self) -> u16 {
+        self.rfc
+    }
+
+    /// Returns the section within the RFC.
+    pub fn section(&self) -> &'static str {
+        self.section
+    }
+}
+
+impl fmt::Display for RfcSection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RFC {}, section {}", self.rfc, self.section)
+    }
+}
+
+
 //------------ Testing. One. Two. Three --------------------------------------
 
 #[cfg(test)]
@@ -1035,6 +1375,33 @@ mod test {
         assert_eq!(data.as_ref(), AsRef::<[u8]>::as_ref(&encoded));
     }
 
+    #[test]
+    fn name_display() {
+        let cert = crate::cert::Cert::decode(
+            include_bytes!("../test-data/ca1.cer").as_ref()
+        ).unwrap();
+        assert_eq!(cert.issuer().to_string(), "CN=ripe-ncc-ta");
+        assert_eq!(
+            cert.subject().to_string(),
+            "CN=2a7dd1d787d793e4c8af56e197d4eed92af6ba13"
+        );
+
+
+        // A constructed value is shown as its complete encoding.
+        let name = Mode::Der.decode(
+            b"\x30\x0d\x31\x0b\x30\x09\x06\x03\x55\x04\x03\
+              \x30\x02\x04\x00".as_ref(),
+            Name::take_from
+        ).unwrap();
+        assert_eq!(name.to_string(), "CN=#30020400");
+
+        // A name that isn’t a sequence is shown entirely in hex.
+        let name = Name::from_captured(Mode::Der.decode(
+            b"\x04\x00".as_ref(), |cons| cons.capture_one()
+        ).unwrap());
+        assert_eq!(name.to_string(), "#0400");
+    }
+
     #[test]
     fn serial_from_slice() {
         assert_eq!(
@@ -1188,5 +1555,34 @@ mod test {
             57
         );
     }
+
+    #[test]
+    fn validation_error_kind() {
+        let now = Time::utc(2019, 5, 1, 0, 0, 0);
+        let validity = Validity::new(
+            Time::utc(2019, 1, 1, 0, 0, 0),
+            Time::utc(2019, 4, 1, 0, 0, 0)
+        );
+        let err = validity.validate_at(now).unwrap_err();
+        assert_eq!(err.kind(), ValidationErrorKind::Expired);
+        assert_eq!(err.rfc_section(), None);
+        assert_eq!(format!("{}", err), "certificate expired");
+
+        let err = err.with_rfc(6487, "4.6");
+        assert_eq!(err.rfc_section().unwrap().rfc(), 6487);
+        assert_eq!(
+            format!("{}", err),
+            "certificate expired (RFC 6487, section 4.6)"
+        );
+
+        let validity = Validity::new(
+            Time::utc(2019, 6, 1, 0, 0, 0),
+            Time::utc(2019, 9, 1, 0, 0, 0)
+        );
+        assert_eq!(
+            validity.validate_at(now).unwrap_err().kind(),
+            ValidationErrorKind::NotYetValid
+        );
+    }
 }
 
@@ -140,7 +140,7 @@ impl Content {
                 Event::Empty(start) => {
                     op(Element::new(start, ns))?;
                     return Ok(
-                        Content { empty: false }
+                        Content { empty: true }
                     )
                 }
                 Event::Comment(_) => { }
@@ -265,6 +265,16 @@ impl<'n, 'l> Name<'n, 'l> {
             local
         }
     }
+
+    /// Returns the namespace of the name if it is qualified.
+    pub fn namespace(&self) -> Option<&'n [u8]> {
+        self.namespace
+    }
+
+    /// Returns the local part of the name.
+    pub fn local(&self) -> &'l [u8] {
+        self.local
+    }
 }
 
 impl<'n, 'l> fmt::Debug for Name<'n, 'l> {
@@ -321,6 +331,15 @@ impl<'a> Text<'a> {
             }
         }
     }
+
+    /// Decodes the text as base64 ignoring any white space.
+    pub fn base64_decode(&self) -> Result<Vec<u8>, Error> {
+        let text: Vec<_> = self.to_ascii()?.as_bytes().iter().filter_map(|b| {
+            if b.is_ascii_whitespace() { None }
+            else { Some(*b) }
+        }).collect();
+        base64::decode(&text).map_err(|_| Error::Malformed)
+    }
 }
 
 
@@ -0,0 +1,295 @@
+//! Encoding XML.
+//!
+//! This is a very small and simple XML writer that only supports what is
+//! necessary for the XML-based protocols used in RPKI: elements with
+//! unqualified attributes and either element or text content.
+
+use std::{fmt, io};
+
+
+//------------ Writer --------------------------------------------------------
+
+/// An XML writer.
+///
+/// This struct holds all state necessary for writing an XML document.
+pub struct Writer<W> {
+    writer: W,
+    indent: usize,
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Creates a new writer atop an underlying writer.
+    pub fn new(writer: W) -> Self {
+        Writer { writer, indent: 0 }
+    }
+
+    /// Starts the document element.
+    ///
+    /// There must be exactly one document element. You need to call
+    /// `Element::done` on it before calling `Writer::done`.
+    pub fn element(
+        &mut self, tag: &'static [u8]
+    ) -> Result<Element<'_, W>, io::Error> {
+        self.writer.write_all(b"<")?;
+        self.writer.write_all(tag)?;
+        Ok(Element::new(self, tag))
+    }
+
+    /// Finishes the document and returns the underlying writer.
+    pub fn done(mut self) -> Result<W, io::Error> {
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Starts a new line at the current indentation level.
+    fn newline(&mut self) -> Result<(), io::Error> {
+        self.writer.write_all(b"\n")?;
+        for _ in 0..self.indent {
+            self.writer.write_all(b"  ")?;
+        }
+        Ok(())
+    }
+}
+
+
+//------------ Element -------------------------------------------------------
+
+/// An element that is currently being written.
+///
+/// Attributes have to be added before any content. Once all content has
+/// been added, the element needs to be finished via `done`.
+pub struct Element<'a, W> {
+    writer: &'a mut Writer<W>,
+    tag: &'static [u8],
+    state: ElementState,
+}
+
+/// What has been written for an element so far.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum ElementState {
+    /// Only the start tag and possibly attributes.
+    Empty,
+
+    /// Text content.
+    Text,
+
+    /// Other elements.
+    Elements,
+}
+
+impl<'a, W: io::Write> Element<'a, W> {
+    fn new(writer: &'a mut Writer<W>, tag: &'static [u8]) -> Self {
+        Element { writer, tag, state: ElementState::Empty }
+    }
+
+    /// Adds an attribute to the element.
+    ///
+    /// The value is taken from the `Display` implementation of `value` and
+    /// escaped as necessary.
+    ///
+    /// # Panics
+    ///
+    /// The method panics if content has been added to the element already.
+    pub fn attr<T: fmt::Display + ?Sized>(
+        self,
+        name: &[u8],
+        value: &T
+    ) -> Result<Self, io::Error> {
+        assert!(self.state == ElementState::Empty);
+        self.writer.writer.write_all(b" ")?;
+        self.writer.writer.write_all(name)?;
+        self.writer.writer.write_all(b"=\"")?;
+        write_escaped(&mut self.writer.writer, &value.to_string(), true)?;
+        self.writer.writer.write_all(b"\"")?;
+        Ok(self)
+    }
+
+    /// Adds an optional attribute to the element.
+    ///
+    /// If `value` is `None`, nothing happens.
+    pub fn opt_attr<T: fmt::Display>(
+        self,
+        name: &[u8],
+        value: Option<&T>
+    ) -> Result<Self, io::Error> {
+        match value {
+            Some(value) => self.attr(name, value),
+            None => Ok(self)
+        }
+    }
+
+    /// Adds content to the element.
+    ///
+    /// The closure receives a content writer that can be used to add
+    /// child elements or text.
+    pub fn content<F>(mut self, op: F) -> Result<Self, io::Error>
+    where F: FnOnce(&mut Content<'_, W>) -> Result<(), io::Error> {
+        if self.state == ElementState::Empty {
+            self.writer.writer.write_all(b">")?;
+            // The start tag is closed now, so even if no content is added
+            // we need an end tag.
+            self.state = ElementState::Text;
+        }
+        self.writer.indent += 1;
+        let mut content = Content { writer: self.writer, state: self.state };
+        op(&mut content)?;
+        self.state = content.state;
+        self.writer.indent -= 1;
+        Ok(self)
+    }
+
+    /// Finishes the element.
+    pub fn done(self) -> Result<(), io::Error> {
+        match self.state {
+            ElementState::Empty => {
+                self.writer.writer.write_all(b"/>")
+            }
+            ElementState::Text => {
+                self.writer.writer.write_all(b"</")?;
+                self.writer.writer.write_all(self.tag)?;
+                self.writer.writer.write_all(b">")
+            }
+            ElementState::Elements => {
+                self.writer.newline()?;
+                self.writer.writer.write_all(b"</")?;
+                self.writer.writer.write_all(self.tag)?;
+                self.writer.writer.write_all(b">")
+            }
+        }
+    }
+}
+
+
+//------------ Content -------------------------------------------------------
+
+/// A writer for the content of an element.
+pub struct Content<'a, W> {
+    writer: &'a mut Writer<W>,
+    state: ElementState,
+}
+
+impl<'a, W: io::Write> Content<'a, W> {
+    /// Starts a child element.
+    pub fn element(
+        &mut self, tag: &'static [u8]
+    ) -> Result<Element<'_, W>, io::Error> {
+        self.state = ElementState::Elements;
+        self.writer.newline()?;
+        self.writer.writer.write_all(b"<")?;
+        self.writer.writer.write_all(tag)?;
+        Ok(Element::new(self.writer, tag))
+    }
+
+    /// Adds text content.
+    ///
+    /// The text is taken from the `Display` implementation of `text` and
+    /// escaped as necessary.
+    pub fn pcdata<T: fmt::Display + ?Sized>(
+        &mut self, text: &T
+    ) -> Result<(), io::Error> {
+        if self.state == ElementState::Empty {
+            self.state = ElementState::Text;
+        }
+        write_escaped(&mut self.writer.writer, &text.to_string(), false)
+    }
+
+    /// Adds binary data as base64-encoded text content.
+    pub fn base64(&mut self, data: &[u8]) -> Result<(), io::Error> {
+        if self.state == ElementState::Empty {
+            self.state = ElementState::Text;
+        }
+        // The base64 alphabet doesn’t need escaping.
+        self.writer.writer.write_all(base64::encode(data).as_bytes())
+    }
+}
+
+
+//------------ Helper Functions ----------------------------------------------
+
+/// Writes a string escaping all characters that need escaping.
+///
+/// If `attr` is `true`, quotation marks are escaped, too, so that the
+/// result can be used as an attribute value.
+fn write_escaped<W: io::Write>(
+    target: &mut W,
+    s: &str,
+    attr: bool
+) -> Result<(), io::Error> {
+    let mut start = 0;
+    for (idx, ch) in s.bytes().enumerate() {
+        let escaped: &[u8] = match ch {
+            b'&' => b"&amp;",
+            b'<' => b"&lt;",
+            b'>' => b"&gt;",
+            b'"' if attr => b"&quot;",
+            b'\'' if attr => b"&apos;",
+            _ => continue
+        };
+        target.write_all(&s.as_bytes()[start..idx])?;
+        target.write_all(escaped)?;
+        start = idx + 1;
+    }
+    target.write_all(&s.as_bytes()[start..])
+}
+
+
+//============ Tests =========================================================
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::xml::decode::{Name, Reader};
+
+    #[test]
+    fn write_document() {
+        let mut writer = Writer::new(Vec::new());
+        writer.element(b"outer").unwrap()
+            .attr(b"xmlns", "http://example.com/ns").unwrap()
+            .attr(b"name", "a \"b\" & <c>").unwrap()
+            .content(|content| {
+                content.element(b"empty").unwrap()
+                    .opt_attr(b"missing", None::<&u8>).unwrap()
+                    .done()?;
+                content.element(b"text").unwrap()
+                    .content(|content| content.pcdata("x < y"))?
+                    .done()?;
+                content.element(b"data").unwrap()
+                    .content(|content| content.base64(b"foo"))?
+                    .done()
+            }).unwrap()
+            .done().unwrap();
+        let data = writer.done().unwrap();
+        assert_eq!(
+            data,
+            b"<outer xmlns=\"http://example.com/ns\" \
+              name=\"a &quot;b&quot; &amp; &lt;c&gt;\">\n  \
+              <empty/>\n  \
+              <text>x &lt; y</text>\n  \
+              <data>Zm9v</data>\n\
+              </outer>\n".as_ref()
+        );
+
+        let ns = b"http://example.com/ns".as_ref();
+        let mut reader = Reader::new(data.as_slice());
+        let mut outer = reader.start(|element| {
+            assert_eq!(element.name(), Name::qualified(ns, b"outer"));
+            Ok::<_, crate::xml::decode::Error>(())
+        }).unwrap();
+        let mut content = outer.take_opt_element(&mut reader, |element| {
+            assert_eq!(element.name(), Name::qualified(ns, b"empty"));
+            Ok::<_, crate::xml::decode::Error>(())
+        }).unwrap().unwrap();
+        content.take_end(&mut reader).unwrap();
+        let mut content = outer.take_opt_element(&mut reader, |element| {
+            assert_eq!(element.name(), Name::qualified(ns, b"text"));
+            Ok::<_, crate::xml::decode::Error>(())
+        }).unwrap().unwrap();
+        assert_eq!(
+            content.take_text(&mut reader, |text| {
+                text.to_ascii().map(|s| s.into_owned())
+            }).unwrap(),
+            "x < y"
+        );
+    }
+}